@@ -0,0 +1,286 @@
+use gtk4::prelude::*;
+use gtk4::{glib, ApplicationWindow};
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::database::Database;
+use crate::graph_analysis::{describe_path, shortest_path, GraphAnalysis};
+use crate::link_graph::LinkGraph;
+
+/// The analysis window: hubs, orphans, broken links, clusters and a path
+/// finder that can show its result on the tangle map.
+pub fn show_analysis_dialog(app: &gtk4::Application, parent: &ApplicationWindow, db: &Database) {
+    let dialog = gtk4::Window::builder()
+        .title("Tangle Analysis")
+        .default_width(460)
+        .default_height(560)
+        .transient_for(parent)
+        .modal(false)
+        .build();
+    dialog.add_css_class("note-list-dialog");
+
+    let vbox = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Vertical)
+        .spacing(8)
+        .margin_top(12)
+        .margin_bottom(12)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+
+    let summary = gtk4::Label::builder()
+        .label("Analysing\u{2026}")
+        .xalign(0.0)
+        .wrap(true)
+        .css_classes(["dim-label"])
+        .build();
+    vbox.append(&summary);
+
+    let stack = gtk4::Stack::new();
+    let switcher = gtk4::StackSwitcher::builder().stack(&stack).halign(gtk4::Align::Center).build();
+    vbox.append(&switcher);
+
+    let hubs_list = analysis_list();
+    let orphans_list = analysis_list();
+    let dangling_list = analysis_list();
+    let clusters_list = analysis_list();
+    for (list, name, title) in [
+        (&hubs_list, "hubs", "Hubs"),
+        (&orphans_list, "orphans", "Orphans"),
+        (&dangling_list, "broken", "Broken Links"),
+        (&clusters_list, "clusters", "Clusters"),
+    ] {
+        let scrolled = gtk4::ScrolledWindow::builder()
+            .child(list)
+            .vexpand(true)
+            .hexpand(true)
+            .min_content_height(300)
+            .build();
+        stack.add_titled(&scrolled, Some(name), title);
+    }
+
+    // Path finder: two searchable pickers, the result and a map button
+    let path_box = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Vertical)
+        .spacing(6)
+        .margin_top(6)
+        .build();
+    let titles = gtk4::StringList::new(&[]);
+    let title_expression = gtk4::PropertyExpression::new(
+        gtk4::StringObject::static_type(),
+        None::<gtk4::Expression>,
+        "string",
+    );
+    let from_drop = gtk4::DropDown::builder()
+        .model(&titles)
+        .expression(&title_expression)
+        .enable_search(true)
+        .build();
+    let to_drop = gtk4::DropDown::builder()
+        .model(&titles)
+        .expression(&title_expression)
+        .enable_search(true)
+        .build();
+    path_box.append(&gtk4::Label::builder().label("From").xalign(0.0).css_classes(["dim-label"]).build());
+    path_box.append(&from_drop);
+    path_box.append(&gtk4::Label::builder().label("To").xalign(0.0).css_classes(["dim-label"]).build());
+    path_box.append(&to_drop);
+    let path_label = gtk4::Label::builder()
+        .xalign(0.0)
+        .wrap(true)
+        .selectable(true)
+        .margin_top(6)
+        .build();
+    path_box.append(&path_label);
+    let path_buttons = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Horizontal)
+        .spacing(6)
+        .halign(gtk4::Align::End)
+        .build();
+    let find_btn = gtk4::Button::builder().label("Find Path").css_classes(["save-button"]).build();
+    let map_btn = gtk4::Button::builder().label("Show on Map").sensitive(false).build();
+    path_buttons.append(&map_btn);
+    path_buttons.append(&find_btn);
+    path_box.append(&path_buttons);
+    stack.add_titled(&path_box, Some("path"), "Path");
+
+    vbox.append(&stack);
+
+    let button_box = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Horizontal)
+        .spacing(6)
+        .halign(gtk4::Align::End)
+        .build();
+    let close_btn = gtk4::Button::builder().label("Close").css_classes(["close-button"]).build();
+    button_box.append(&close_btn);
+    vbox.append(&button_box);
+    dialog.set_child(Some(&vbox));
+
+    let dialog_close = dialog.clone();
+    close_btn.connect_clicked(move |_| dialog_close.close());
+
+    // Any tangle row opens its tangle; the titles behind each list's rows, in order
+    let hub_titles: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(Vec::new()));
+    let orphan_titles: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(Vec::new()));
+    let dangling_titles: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(Vec::new()));
+    for (list, row_titles) in [
+        (&hubs_list, &hub_titles),
+        (&orphans_list, &orphan_titles),
+        (&dangling_list, &dangling_titles),
+    ] {
+        let app_open = app.clone();
+        let row_titles = row_titles.clone();
+        list.connect_row_activated(move |_, row| {
+            if let Some(title) = row_titles.borrow().get(row.index() as usize) {
+                app_open.activate_action("open-tangle", Some(&title.to_variant()));
+            }
+        });
+    }
+
+    let graph: Rc<RefCell<Option<LinkGraph>>> = Rc::new(RefCell::new(None));
+    let found_path: Rc<RefCell<Vec<i64>>> = Rc::new(RefCell::new(Vec::new()));
+
+    let graph_find = graph.clone();
+    let found_find = found_path.clone();
+    let path_label_find = path_label.clone();
+    let map_btn_find = map_btn.clone();
+    let from_find = from_drop.clone();
+    let to_find = to_drop.clone();
+    find_btn.connect_clicked(move |_| {
+        let graph = graph_find.borrow();
+        let Some(graph) = graph.as_ref() else { return };
+        let (from, to) = (from_find.selected() as usize, to_find.selected() as usize);
+        let path = shortest_path(graph, from, to);
+        path_label_find.set_text(&match path {
+            Some(ref path) => describe_path(graph, path),
+            None => "No path: these tangles are in different clusters.".to_string(),
+        });
+        *found_find.borrow_mut() = path
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|&i| graph.notes[i].id)
+            .collect();
+        map_btn_find.set_sensitive(found_find.borrow().len() > 1);
+    });
+
+    let app_map = app.clone();
+    let parent_map = parent.clone();
+    let db_map = db.clone();
+    map_btn.connect_clicked(move |_| {
+        crate::tangle_map::show_tangle_map(&app_map, &parent_map, &db_map, None, &found_path.borrow());
+    });
+
+    dialog.present();
+
+    // Load and analyse off the main thread
+    let db_bg = db.clone();
+    let (tx, rx) = std::sync::mpsc::channel::<rusqlite::Result<(LinkGraph, GraphAnalysis)>>();
+    std::thread::spawn(move || {
+        let result = LinkGraph::load(&db_bg).map(|graph| {
+            let analysis = GraphAnalysis::new(&graph);
+            (graph, analysis)
+        });
+        let _ = tx.send(result);
+    });
+    glib::timeout_add_local(std::time::Duration::from_millis(30), move || {
+        match rx.try_recv() {
+            Ok(Ok((loaded, analysis))) => {
+                summary.set_text(&format!(
+                    "{} tangles, {} links, {} clusters, {} orphans, {} with broken links",
+                    loaded.notes.len(),
+                    loaded.edges.len(),
+                    analysis.components.len(),
+                    analysis.orphans.len(),
+                    analysis.dangling.len(),
+                ));
+
+                for hub in &analysis.hubs {
+                    let detail = format!(
+                        "{} in \u{b7} {} out \u{b7} rank {:.3}",
+                        hub.in_degree, hub.out_degree, hub.rank,
+                    );
+                    append_tangle_row(&hubs_list, &hub_titles, &loaded.notes[hub.note].title, &detail);
+                }
+                for &i in &analysis.orphans {
+                    append_tangle_row(&orphans_list, &orphan_titles, &loaded.notes[i].title, "");
+                }
+                for (i, missing) in &analysis.dangling {
+                    let detail = format!("\u{2192} {}", missing.join(", "));
+                    append_tangle_row(&dangling_list, &dangling_titles, &loaded.notes[*i].title, &detail);
+                }
+                for (k, component) in analysis.components.iter().enumerate() {
+                    let mut names: Vec<&str> = component.iter().take(8).map(|&i| loaded.notes[i].title.as_str()).collect();
+                    if component.len() > names.len() {
+                        names.push("\u{2026}");
+                    }
+                    let row = append_row(
+                        &clusters_list,
+                        &format!("Cluster {} \u{2014} {} tangles", k + 1, component.len()),
+                        &names.join(", "),
+                    );
+                    row.set_activatable(false);
+                }
+                for (list, empty) in [
+                    (&hubs_list, "No tangle is linked to yet"),
+                    (&orphans_list, "Every tangle has a link"),
+                    (&dangling_list, "No broken links"),
+                    (&clusters_list, "No links yet"),
+                ] {
+                    if list.first_child().is_none() {
+                        let row = append_row(list, empty, "");
+                        row.set_activatable(false);
+                    }
+                }
+
+                for note in &loaded.notes {
+                    titles.append(&note.title);
+                }
+                *graph.borrow_mut() = Some(loaded);
+                glib::ControlFlow::Break
+            }
+            Ok(Err(e)) => {
+                summary.set_text(&format!("Analysis failed: {}", e));
+                glib::ControlFlow::Break
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+            Err(_) => glib::ControlFlow::Break,
+        }
+    });
+}
+
+fn analysis_list() -> gtk4::ListBox {
+    let list = gtk4::ListBox::builder()
+        .selection_mode(gtk4::SelectionMode::Single)
+        .build();
+    list.add_css_class("boxed-list");
+    list
+}
+
+/// A row for the tangle called `title`, which activating it opens.
+fn append_tangle_row(list: &gtk4::ListBox, row_titles: &RefCell<Vec<String>>, title: &str, detail: &str) {
+    append_row(list, title, detail);
+    row_titles.borrow_mut().push(title.to_string());
+}
+
+/// A row with a title and a dim detail line.
+fn append_row(list: &gtk4::ListBox, title: &str, detail: &str) -> gtk4::ListBoxRow {
+    let vbox = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Vertical)
+        .spacing(2)
+        .css_classes(["note-row"])
+        .build();
+    vbox.append(&gtk4::Label::builder().label(title).xalign(0.0).ellipsize(gtk4::pango::EllipsizeMode::End).build());
+    if !detail.is_empty() {
+        vbox.append(
+            &gtk4::Label::builder()
+                .label(detail)
+                .xalign(0.0)
+                .wrap(true)
+                .css_classes(["dim-label"])
+                .build(),
+        );
+    }
+    let row = gtk4::ListBoxRow::new();
+    row.set_child(Some(&vbox));
+    list.append(&row);
+    row
+}
@@ -0,0 +1,481 @@
+//! Headless access to the Tangles database for scripts and the terminal.
+//!
+//! Works on the same SQLite file as the GUI (WAL mode with a busy timeout),
+//! so it is safe to run while the app is open. `append` goes through the
+//! running app's D-Bus service when there is one, so a tangle open in a
+//! window gets the text in its editor instead of losing it on the next save.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::json;
+use std::io::Read;
+use std::path::PathBuf;
+use tangles::database::{self, Database, Note, SNIPPET_END, SNIPPET_START};
+use tangles::html;
+use tangles::link_graph::{self, GraphFormat, LinkGraph};
+use tangles::markdown::{self, ExportOptions, LinkStyle};
+
+const USAGE: &str = "\
+Usage: tangles-cli [--db PATH] [--json] <command> [args]
+
+Commands:
+  list                          List tangles, most recently updated first
+  show <title> [--format F]     Print a tangle (F: text, html, md; default text)
+  new <title> [text] [--format F]
+                                Create a tangle (text from stdin if omitted)
+  append <title> [text] [--format F]
+                                Append to a tangle (text from stdin if omitted)
+  search <query> [--limit N]    Full-text search, best match first
+  links <title>                 Tangles this one links to
+  backlinks <title>             Tangles that link to this one
+  export <dir> [--links wiki|relative] [--no-images]
+                                Export every tangle to Markdown files
+  graph [file] [--as F]         Export the link graph (F: dot, graphml, json;
+                                default from the file extension, else dot)
+
+Options:
+  --db PATH    Database file (default: ~/.local/share/tangles/tangles.db)
+  --json       Print machine-readable JSON
+  -h, --help   Show this help
+";
+
+/// Where the running app exports its D-Bus service (see `dbus_service`).
+const APP_BUS_NAME: &str = "org.tangles.Notes";
+const APP_OBJECT_PATH: &str = "/org/tangles/Notes";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Html,
+    Markdown,
+}
+
+impl Format {
+    fn parse(s: &str) -> Result<Self> {
+        match s {
+            "text" | "txt" => Ok(Format::Text),
+            "html" => Ok(Format::Html),
+            "md" | "markdown" => Ok(Format::Markdown),
+            other => bail!("unknown format '{}' (expected text, html or md)", other),
+        }
+    }
+}
+
+/// Parsed command line: global flags, positional arguments and `--key value` options.
+struct Args {
+    db: Option<PathBuf>,
+    json: bool,
+    positional: Vec<String>,
+    format: Format,
+    limit: Option<usize>,
+    link_style: LinkStyle,
+    copy_images: bool,
+    graph_format: Option<GraphFormat>,
+}
+
+fn parse_args(raw: impl Iterator<Item = String>) -> Result<Args> {
+    let mut args = Args {
+        db: None,
+        json: false,
+        positional: Vec::new(),
+        format: Format::Text,
+        limit: None,
+        link_style: LinkStyle::Wiki,
+        copy_images: true,
+        graph_format: None,
+    };
+    let mut raw = raw.peekable();
+    while let Some(arg) = raw.next() {
+        let mut value = |name: &str| raw.next().ok_or_else(|| anyhow!("{} needs a value", name));
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                std::process::exit(0);
+            }
+            "--json" => args.json = true,
+            "--db" => args.db = Some(PathBuf::from(value("--db")?)),
+            "--format" | "-f" => args.format = Format::parse(&value("--format")?)?,
+            "--limit" | "-n" => {
+                let n = value("--limit")?;
+                args.limit = Some(n.parse().with_context(|| format!("invalid --limit '{}'", n))?);
+            }
+            "--links" => {
+                args.link_style = match value("--links")?.as_str() {
+                    "wiki" => LinkStyle::Wiki,
+                    "relative" => LinkStyle::Relative,
+                    other => bail!("unknown link style '{}' (expected wiki or relative)", other),
+                }
+            }
+            "--no-images" => args.copy_images = false,
+            "--as" => {
+                let name = value("--as")?;
+                args.graph_format = Some(GraphFormat::parse(&name).ok_or_else(|| {
+                    anyhow!("unknown graph format '{}' (expected dot, graphml or json)", name)
+                })?);
+            }
+            "--" => args.positional.extend(raw.by_ref()),
+            flag if flag.starts_with("--") => bail!("unknown option '{}'", flag),
+            _ => args.positional.push(arg),
+        }
+    }
+    Ok(args)
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("tangles-cli: {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    let args = parse_args(std::env::args().skip(1))?;
+    let Some(command) = args.positional.first().cloned() else {
+        eprint!("{}", USAGE);
+        std::process::exit(2);
+    };
+    let rest = &args.positional[1..];
+
+    let db_path = match &args.db {
+        Some(p) => p.clone(),
+        None => database::default_db_path().context("could not determine data directory")?,
+    };
+    if let Some(dir) = db_path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    let db = Database::new(&db_path).with_context(|| format!("opening {}", db_path.display()))?;
+
+    match command.as_str() {
+        "list" | "ls" => cmd_list(&db, &args),
+        "show" | "cat" => cmd_show(&db, &args, title_arg(rest)?),
+        "new" => cmd_new(&db, &args, title_arg(rest)?, rest.get(1)),
+        "append" => cmd_append(&db, &args, title_arg(rest)?, rest.get(1)),
+        "search" => {
+            if rest.is_empty() {
+                bail!("search needs a query");
+            }
+            cmd_search(&db, &args, &rest.join(" "))
+        }
+        "links" => cmd_links(&db, &args, title_arg(rest)?),
+        "backlinks" => cmd_backlinks(&db, &args, title_arg(rest)?),
+        "export" => {
+            let dir = rest.first().ok_or_else(|| anyhow!("export needs a target directory"))?;
+            cmd_export(&db, &args, &PathBuf::from(dir))
+        }
+        "graph" => cmd_graph(&db, &args, rest.first().map(PathBuf::from)),
+        other => bail!("unknown command '{}' (see --help)", other),
+    }
+}
+
+fn title_arg(rest: &[String]) -> Result<&str> {
+    match rest.first() {
+        Some(t) if !t.trim().is_empty() => Ok(t.as_str()),
+        _ => bail!("missing tangle title"),
+    }
+}
+
+fn find_note(db: &Database, title: &str) -> Result<Note> {
+    db.get_note_by_title(title)?
+        .ok_or_else(|| anyhow!("no tangle titled '{}'", title))
+}
+
+fn note_id(note: &Note) -> i64 {
+    note.id.unwrap_or_default()
+}
+
+fn print_json(value: &serde_json::Value) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+// ── Commands ───────────────────────────────────────────────────────
+
+fn cmd_list(db: &Database, args: &Args) -> Result<()> {
+    let mut notes = db.get_all_notes()?;
+    if let Some(limit) = args.limit {
+        notes.truncate(limit);
+    }
+    if args.json {
+        let items: Vec<_> = notes
+            .iter()
+            .map(|n| {
+                json!({
+                    "id": n.id,
+                    "title": n.title,
+                    "created_at": n.created_at,
+                    "updated_at": n.updated_at,
+                    "star_color": n.star_color,
+                    "is_visible": n.is_visible,
+                })
+            })
+            .collect();
+        return print_json(&json!(items));
+    }
+    for n in &notes {
+        println!("{}\t{}", short_date(&n.updated_at), n.title);
+    }
+    Ok(())
+}
+
+fn cmd_show(db: &Database, args: &Args, title: &str) -> Result<()> {
+    let note = find_note(db, title)?;
+    let body = render(&note.content, args.format);
+    if args.json {
+        return print_json(&json!({
+            "id": note.id,
+            "title": note.title,
+            "created_at": note.created_at,
+            "updated_at": note.updated_at,
+            "star_color": note.star_color,
+            "content": body,
+        }));
+    }
+    print!("{}", body);
+    if !body.ends_with('\n') {
+        println!();
+    }
+    Ok(())
+}
+
+fn cmd_new(db: &Database, args: &Args, title: &str, text: Option<&String>) -> Result<()> {
+    if db.get_note_by_title(title)?.is_some() {
+        bail!("a tangle titled '{}' already exists", title);
+    }
+    let input = read_input(text)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let note = Note {
+        id: None,
+        title: title.to_string(),
+        content: to_html(&input, args.format),
+        created_at: now.clone(),
+        updated_at: now,
+        position_x: 100.0,
+        position_y: 100.0,
+        is_visible: false,
+        always_on_top: false,
+        width: 500,
+        height: 400,
+        theme_bg: None,
+        theme_fg: None,
+        theme_accent: None,
+        custom_colors: None,
+        chromeless: false,
+        star_color: None,
+    };
+    let id = db.create_note(&note)?;
+    if args.json {
+        return print_json(&json!({ "id": id, "title": title }));
+    }
+    println!("Created '{}'", title);
+    Ok(())
+}
+
+fn cmd_append(db: &Database, args: &Args, title: &str, text: Option<&String>) -> Result<()> {
+    let note = find_note(db, title)?;
+    let input = read_input(text)?;
+    let html = to_html(&input, args.format);
+    if html.is_empty() {
+        bail!("nothing to append");
+    }
+    // A running app appends through the tangle's window if it's open, so its
+    // next save keeps the text; otherwise write to the database directly
+    if args.db.is_some() || !append_via_app(&note.title, &html) {
+        // Keep the pre-append version so the change can be undone from History
+        db.create_revision(note_id(&note), &note.title, &note.content)?;
+        let separator = if note.content.is_empty() || note.content.ends_with('\n') { "" } else { "\n" };
+        db.append_note_content(note_id(&note), &format!("{}{}", separator, html))?;
+    }
+    if args.json {
+        return print_json(&json!({ "id": note.id, "title": note.title }));
+    }
+    println!("Appended to '{}'", note.title);
+    Ok(())
+}
+
+/// Call the app's `AppendToNote` D-Bus method with `gdbus`. False when the
+/// app isn't running or the call couldn't be made.
+fn append_via_app(title: &str, html: &str) -> bool {
+    // gdbus parses each argument as GVariant text, so pass quoted strings
+    let quote = |s: &str| {
+        let mut out = String::from('"');
+        for c in s.chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+        out
+    };
+    std::process::Command::new("gdbus")
+        .args(["call", "--session", "--timeout", "10"])
+        .args(["--dest", APP_BUS_NAME, "--object-path", APP_OBJECT_PATH])
+        .args(["--method", "org.tangles.Notes.AppendToNote"])
+        .arg(quote(title))
+        .arg(quote(html))
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+fn cmd_search(db: &Database, args: &Args, query: &str) -> Result<()> {
+    let mut hits = db.search_notes_ranked(query)?;
+    if let Some(limit) = args.limit {
+        hits.truncate(limit);
+    }
+    if args.json {
+        let items: Vec<_> = hits
+            .iter()
+            .map(|h| {
+                json!({
+                    "id": h.note.id,
+                    "title": h.note.title,
+                    "rank": h.rank,
+                    "snippet": collapse_whitespace(&h.snippet.replace([SNIPPET_START, SNIPPET_END], "")),
+                })
+            })
+            .collect();
+        return print_json(&json!(items));
+    }
+    for hit in &hits {
+        let snippet = collapse_whitespace(&hit.snippet.replace(SNIPPET_START, "[").replace(SNIPPET_END, "]"));
+        println!("{}\n    {}", hit.note.title, snippet);
+    }
+    Ok(())
+}
+
+fn cmd_links(db: &Database, args: &Args, title: &str) -> Result<()> {
+    let note = find_note(db, title)?;
+    let targets: Vec<(String, Option<i64>)> = database::extract_tangle_targets(&note.content)
+        .into_iter()
+        .map(|t| {
+            let id = db.get_note_by_title(&t).ok().flatten().and_then(|n| n.id);
+            (t, id)
+        })
+        .collect();
+    if args.json {
+        let items: Vec<_> = targets
+            .iter()
+            .map(|(t, id)| json!({ "title": t, "id": id, "exists": id.is_some() }))
+            .collect();
+        return print_json(&json!(items));
+    }
+    for (t, id) in &targets {
+        if id.is_some() {
+            println!("{}", t);
+        } else {
+            println!("{}\t(missing)", t);
+        }
+    }
+    Ok(())
+}
+
+fn cmd_backlinks(db: &Database, args: &Args, title: &str) -> Result<()> {
+    let note = find_note(db, title)?;
+    let sources = db.get_backlinks(note_id(&note))?;
+    if args.json {
+        let items: Vec<_> = sources
+            .iter()
+            .map(|n| json!({ "id": n.id, "title": n.title, "updated_at": n.updated_at }))
+            .collect();
+        return print_json(&json!(items));
+    }
+    for n in &sources {
+        println!("{}", n.title);
+    }
+    Ok(())
+}
+
+fn cmd_export(db: &Database, args: &Args, dir: &std::path::Path) -> Result<()> {
+    let options = ExportOptions { link_style: args.link_style, copy_images: args.copy_images };
+    let report = markdown::export_database(db, dir, &options)
+        .with_context(|| format!("exporting to {}", dir.display()))?;
+    if args.json {
+        return print_json(&json!({
+            "directory": dir.display().to_string(),
+            "notes": report.notes,
+            "images": report.images,
+            "errors": report.errors,
+        }));
+    }
+    println!("Exported {} tangles and {} images to {}", report.notes, report.images, dir.display());
+    for e in &report.errors {
+        eprintln!("  {}", e);
+    }
+    Ok(())
+}
+
+/// Write the link graph to `file`, or to stdout without one.
+fn cmd_graph(db: &Database, args: &Args, file: Option<PathBuf>) -> Result<()> {
+    let from_extension = file
+        .as_ref()
+        .and_then(|f| f.extension())
+        .and_then(|e| GraphFormat::parse(&e.to_string_lossy()));
+    let format = args.graph_format.or(from_extension).unwrap_or(GraphFormat::Dot);
+    let graph = LinkGraph::load(db)?;
+    let output = link_graph::export_graph(&graph, format);
+    match file {
+        Some(path) if path.as_os_str() != "-" => {
+            std::fs::write(&path, output).with_context(|| format!("writing {}", path.display()))?;
+            eprintln!("Wrote {} tangles and {} links to {}", graph.notes.len(), graph.edges.len(), path.display());
+        }
+        _ => print!("{}", output),
+    }
+    Ok(())
+}
+
+// ── Helpers ────────────────────────────────────────────────────────
+
+/// The text argument, or all of stdin when it is missing or `-`.
+fn read_input(text: Option<&String>) -> Result<String> {
+    match text.map(String::as_str) {
+        Some(t) if t != "-" => Ok(t.to_string()),
+        _ => {
+            let mut buf = String::new();
+            std::io::stdin().read_to_string(&mut buf).context("reading stdin")?;
+            Ok(buf)
+        }
+    }
+}
+
+fn render(html: &str, format: Format) -> String {
+    match format {
+        // Blocks are already separated by their end tags; drop the serializer's newlines
+        Format::Text => database::html_to_plain_text(&html.replace(">\n<", "><")),
+        Format::Html => html.to_string(),
+        Format::Markdown => markdown::html_to_markdown(html, LinkStyle::Wiki),
+    }
+}
+
+/// Convert CLI input into the editor's HTML subset. Plain text becomes one
+/// paragraph per line; Markdown wikilinks become tangle links.
+fn to_html(input: &str, format: Format) -> String {
+    let input = input.trim_end_matches(['\n', '\r']);
+    if input.is_empty() {
+        return String::new();
+    }
+    match format {
+        Format::Html => input.to_string(),
+        Format::Markdown => markdown::markdown_to_html(
+            input,
+            &|target| target.trim_end_matches(".md").to_string(),
+            &mut |src| Some(src.to_string()),
+        ),
+        Format::Text => html::plain_text_to_html(input),
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn short_date(rfc3339: &str) -> String {
+    match chrono::DateTime::parse_from_rfc3339(rfc3339) {
+        Ok(dt) => dt.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string(),
+        Err(_) => rfc3339.to_string(),
+    }
+}
@@ -1,7 +1,10 @@
-use rusqlite::{Connection, Result, params};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use rusqlite::{Connection, OptionalExtension, Result, params};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
+use crate::html::{escape_html, escape_html_attr, unescape_html};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Note {
@@ -37,16 +40,62 @@ pub struct Link {
 pub enum LinkType {
     Connection,     // Visual mind-map connection
     WordReference,  // Word highlighting link
+    Tangle,         // tangle:// anchor in note content (kept in sync on save)
 }
 
+impl LinkType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LinkType::Connection => "connection",
+            LinkType::WordReference => "word_reference",
+            LinkType::Tangle => "tangle",
+        }
+    }
+
+    fn from_str(s: &str) -> Self {
+        match s {
+            "word_reference" => LinkType::WordReference,
+            "tangle" => LinkType::Tangle,
+            _ => LinkType::Connection,
+        }
+    }
+}
+
+/// A saved snapshot of a note's title and content.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct WordIndex {
+pub struct NoteRevision {
     pub id: Option<i64>,
-    pub word: String,
     pub note_id: i64,
-    pub frequency: i32, // How many times word appears in note
+    pub title: String,
+    pub content: String,
+    pub created_at: String,
+}
+
+/// Minimum time between automatic snapshots taken while a note is being edited.
+const REVISION_INTERVAL_SECS: i64 = 10 * 60;
+/// Revisions kept per note; older ones are pruned when a new one is added.
+const REVISIONS_PER_NOTE: i64 = 100;
+
+/// A full-text search result: the matching note, its BM25 rank (lower is better)
+/// and a short excerpt with matches wrapped in `SNIPPET_START`/`SNIPPET_END`.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub note: Note,
+    pub rank: f64,
+    pub snippet: String,
+}
+
+/// A note in the trash and when it was moved there.
+#[derive(Debug, Clone)]
+pub struct TrashedNote {
+    pub note: Note,
+    pub deleted_at: String,
 }
 
+/// Markers placed around matched terms in `SearchHit::snippet`.
+pub const SNIPPET_START: char = '\u{E000}';
+pub const SNIPPET_END: char = '\u{E001}';
+
 #[derive(Clone)]
 pub struct Database {
     conn: Arc<Mutex<Connection>>,
@@ -55,6 +104,9 @@ pub struct Database {
 impl Database {
     pub fn new(db_path: &Path) -> Result<Self> {
         let conn = Connection::open(db_path)?;
+        // The GUI and tangles-cli may hold the database at the same time;
+        // wait for the other writer instead of failing with SQLITE_BUSY.
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
         // Performance pragmas
         conn.execute_batch(
             "PRAGMA journal_mode=WAL;
@@ -94,17 +146,17 @@ impl Database {
                 FOREIGN KEY (source_note_id) REFERENCES notes (id) ON DELETE CASCADE,
                 FOREIGN KEY (target_note_id) REFERENCES notes (id) ON DELETE CASCADE
             );
-            CREATE TABLE IF NOT EXISTS word_index (
+            CREATE INDEX IF NOT EXISTS idx_links_source ON links(source_note_id);
+            CREATE INDEX IF NOT EXISTS idx_links_target ON links(target_note_id);
+            CREATE TABLE IF NOT EXISTS note_revisions (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
-                word TEXT NOT NULL,
                 note_id INTEGER NOT NULL,
-                frequency INTEGER DEFAULT 1,
+                title TEXT NOT NULL,
+                content TEXT NOT NULL,
+                created_at TEXT NOT NULL,
                 FOREIGN KEY (note_id) REFERENCES notes (id) ON DELETE CASCADE
             );
-            CREATE INDEX IF NOT EXISTS idx_word_index_word ON word_index(word);
-            CREATE INDEX IF NOT EXISTS idx_word_index_note ON word_index(note_id);
-            CREATE INDEX IF NOT EXISTS idx_links_source ON links(source_note_id);
-            CREATE INDEX IF NOT EXISTS idx_links_target ON links(target_note_id);
+            CREATE INDEX IF NOT EXISTS idx_note_revisions_note ON note_revisions(note_id, created_at);
             CREATE TABLE IF NOT EXISTS settings (
                 key TEXT PRIMARY KEY,
                 value TEXT NOT NULL
@@ -141,6 +193,82 @@ impl Database {
                  ALTER TABLE notes ADD COLUMN star_color TEXT;"
             )?;
         }
+        // Trash: a set deleted_at marks a soft-deleted note
+        let has_deleted_at: bool = conn
+            .prepare("SELECT deleted_at FROM notes LIMIT 0")
+            .is_ok();
+        if !has_deleted_at {
+            conn.execute_batch("ALTER TABLE notes ADD COLUMN deleted_at TEXT;")?;
+        }
+        // Tangle-map node positions, kept apart from the window position so
+        // moving a window no longer rearranges the map (and vice versa).
+        // Earlier maps stored nodes in position_x/y; carry those over, skipping
+        // the defaults new tangles were created with.
+        let has_map_pos: bool = conn
+            .prepare("SELECT map_x FROM notes LIMIT 0")
+            .is_ok();
+        if !has_map_pos {
+            conn.execute_batch(
+                "ALTER TABLE notes ADD COLUMN map_x REAL;
+                 ALTER TABLE notes ADD COLUMN map_y REAL;
+                 UPDATE notes SET map_x = position_x, map_y = position_y
+                  WHERE NOT (position_x = 0.0 AND position_y = 0.0)
+                    AND NOT (position_x = 100.0 AND position_y = 100.0);"
+            )?;
+        }
+        // Optional text shown on a link's edge in the tangle map
+        let has_link_label: bool = conn
+            .prepare("SELECT label FROM links LIMIT 0")
+            .is_ok();
+        if !has_link_label {
+            conn.execute_batch("ALTER TABLE links ADD COLUMN label TEXT;")?;
+        }
+        // Full-text index over tag-stripped note text (rowid = notes.id).
+        // Replaces the old hand-rolled word_index table.
+        let has_fts: bool = conn
+            .prepare("SELECT rowid FROM notes_fts LIMIT 0")
+            .is_ok();
+        if !has_fts {
+            conn.execute_batch(
+                "CREATE VIRTUAL TABLE notes_fts USING fts5(title, body, tokenize = 'unicode61 remove_diacritics 2');
+                 DROP TABLE IF EXISTS word_index;"
+            )?;
+            let notes: Vec<(i64, String, String)> = {
+                let mut stmt = conn.prepare("SELECT id, title, content FROM notes")?;
+                let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+                rows.collect::<Result<_>>()?
+            };
+            let tx = conn.unchecked_transaction()?;
+            for (id, title, content) in &notes {
+                Self::index_note_text_with_conn(&tx, *id, title, content)?;
+            }
+            tx.commit()?;
+        }
+        // Backfill tangle links from content the first time the pair index is created
+        let has_link_pair_index: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'index' AND name = 'idx_links_pair')",
+                [],
+                |row| row.get(0),
+            )?;
+        if !has_link_pair_index {
+            let tx = conn.unchecked_transaction()?;
+            tx.execute_batch(
+                "DELETE FROM links WHERE id NOT IN (
+                     SELECT MIN(id) FROM links GROUP BY source_note_id, target_note_id, link_type
+                 );
+                 CREATE UNIQUE INDEX idx_links_pair ON links(source_note_id, target_note_id, link_type);"
+            )?;
+            let notes: Vec<(i64, String)> = {
+                let mut stmt = tx.prepare("SELECT id, content FROM notes")?;
+                let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                rows.collect::<Result<_>>()?
+            };
+            for (id, content) in &notes {
+                Self::sync_tangle_links_with_conn(&tx, *id, content)?;
+            }
+            tx.commit()?;
+        }
         Ok(())
     }
 
@@ -157,12 +285,17 @@ impl Database {
             ],
         )?;
         let note_id = conn.last_insert_rowid();
-        Self::index_note_words_with_conn(&conn, note_id, &note.content)?;
+        Self::index_note_text_with_conn(&conn, note_id, &note.title, &note.content)?;
+        Self::sync_tangle_links_with_conn(&conn, note_id, &note.content)?;
+        Self::resolve_links_to_title_with_conn(&conn, &note.title)?;
         Ok(note_id)
     }
 
     pub fn update_note(&self, note: &Note) -> Result<()> {
         let conn = self.conn.lock().unwrap();
+        if let Some(note_id) = note.id {
+            Self::snapshot_if_due_with_conn(&conn, note_id, &note.content)?;
+        }
         conn.execute(
             "UPDATE notes SET title = ?1, content = ?2, updated_at = ?3, position_x = ?4, position_y = ?5, is_visible = ?6, always_on_top = ?7, width = ?8, height = ?9, theme_bg = ?10, theme_fg = ?11, theme_accent = ?12, custom_colors = ?13, chromeless = ?14, star_color = ?15
              WHERE id = ?16",
@@ -174,27 +307,113 @@ impl Database {
             ],
         )?;
         if let Some(note_id) = note.id {
-            conn.execute("DELETE FROM word_index WHERE note_id = ?1", [note_id])?;
-            Self::index_note_words_with_conn(&conn, note_id, &note.content)?;
+            Self::index_note_text_with_conn(&conn, note_id, &note.title, &note.content)?;
+            Self::sync_tangle_links_with_conn(&conn, note_id, &note.content)?;
         }
         Ok(())
     }
 
-    pub fn update_note_position(&self, id: i64, x: f64, y: f64) -> Result<()> {
+    /// Rename a note and rewrite every `tangle://Old` href (in all notes,
+    /// including self-links) to point at the new title, in one transaction.
+    /// Fails with a constraint error if another note already has `new_title`.
+    /// Returns the ids of notes whose content was rewritten.
+    pub fn rename_note(&self, id: i64, new_title: &str) -> Result<Vec<i64>> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        let old_title: String = tx.query_row(
+            "SELECT title FROM notes WHERE id = ?1",
+            [id],
+            |row| row.get(0),
+        )?;
+        if old_title == new_title {
+            return Ok(Vec::new());
+        }
+        let taken: bool = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM notes WHERE title = ?1 AND id != ?2 AND deleted_at IS NULL)",
+            params![new_title, id],
+            |row| row.get(0),
+        )?;
+        if taken {
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT),
+                Some(format!("a tangle titled '{}' already exists", new_title)),
+            ));
+        }
+
+        tx.execute("UPDATE notes SET title = ?1 WHERE id = ?2", params![new_title, id])?;
+
+        // Anchors come from the serializer (attribute-escaped) or from the map
+        // (raw title), so rewrite both spellings of the href.
+        let mut replacements = vec![(
+            format!("href=\"tangle://{}\"", escape_html_attr(&old_title)),
+            format!("href=\"tangle://{}\"", escape_html_attr(new_title)),
+        )];
+        let raw_old = format!("href=\"tangle://{}\"", old_title);
+        if raw_old != replacements[0].0 {
+            replacements.push((raw_old, replacements[0].1.clone()));
+        }
+
+        let candidates: Vec<(i64, String, String)> = {
+            let mut stmt = tx.prepare(
+                "SELECT id, title, content FROM notes WHERE instr(content, 'tangle://') > 0",
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            rows.collect::<Result<_>>()?
+        };
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut changed = Vec::new();
+        for (note_id, title, content) in candidates {
+            let mut rewritten = content.clone();
+            for (from, to) in &replacements {
+                rewritten = rewritten.replace(from.as_str(), to);
+            }
+            if rewritten != content {
+                tx.execute(
+                    "UPDATE notes SET content = ?1, updated_at = ?2 WHERE id = ?3",
+                    params![rewritten, now, note_id],
+                )?;
+                Self::index_note_text_with_conn(&tx, note_id, &title, &rewritten)?;
+                changed.push(note_id);
+            }
+        }
+        if !changed.contains(&id) {
+            let content: String = tx.query_row(
+                "SELECT content FROM notes WHERE id = ?1",
+                [id],
+                |row| row.get(0),
+            )?;
+            Self::index_note_text_with_conn(&tx, id, new_title, &content)?;
+        }
+        // Links that dangled on the new title now have a target
+        Self::resolve_links_to_title_with_conn(&tx, new_title)?;
+        tx.commit()?;
+        Ok(changed)
+    }
+
+    /// Store where a tangle's node sits on the tangle map. Independent of the
+    /// window position in `position_x`/`position_y`.
+    pub fn update_note_map_position(&self, id: i64, x: f64, y: f64) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "UPDATE notes SET position_x = ?1, position_y = ?2 WHERE id = ?3",
+            "UPDATE notes SET map_x = ?1, map_y = ?2 WHERE id = ?3",
             params![x, y, id],
         )?;
         Ok(())
     }
 
-    pub fn append_note_content(&self, id: i64, html: &str) -> Result<()> {
+    /// Saved tangle-map positions by note id; tangles never placed on the map are absent.
+    pub fn get_map_positions(&self) -> Result<std::collections::HashMap<i64, (f64, f64)>> {
         let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "UPDATE notes SET content = content || ?1 WHERE id = ?2",
-            params![html, id],
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, map_x, map_y FROM notes
+             WHERE map_x IS NOT NULL AND map_y IS NOT NULL AND deleted_at IS NULL"
         )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?;
+        rows.collect()
+    }
+
+    pub fn append_note_content(&self, id: i64, html: &str) -> Result<()> {
+        self.replace_note_content(id, |content| Some(format!("{}{}", content, html)))?;
         Ok(())
     }
 
@@ -215,7 +434,7 @@ impl Database {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare_cached(
             "SELECT id, title, content, created_at, updated_at, position_x, position_y, is_visible, always_on_top, width, height, theme_bg, theme_fg, theme_accent, custom_colors, chromeless, star_color
-             FROM notes ORDER BY updated_at DESC"
+             FROM notes WHERE deleted_at IS NULL ORDER BY updated_at DESC"
         )?;
         let rows = stmt.query_map([], Self::row_to_note)?;
         rows.collect()
@@ -225,7 +444,7 @@ impl Database {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare_cached(
             "SELECT id, title, content, created_at, updated_at, position_x, position_y, is_visible, always_on_top, width, height, theme_bg, theme_fg, theme_accent, custom_colors, chromeless, star_color
-             FROM notes WHERE is_visible = 1"
+             FROM notes WHERE is_visible = 1 AND deleted_at IS NULL"
         )?;
         let rows = stmt.query_map([], Self::row_to_note)?;
         rows.collect()
@@ -235,7 +454,7 @@ impl Database {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare_cached(
             "SELECT id, title, content, created_at, updated_at, position_x, position_y, is_visible, always_on_top, width, height, theme_bg, theme_fg, theme_accent, custom_colors, chromeless, star_color
-             FROM notes ORDER BY updated_at DESC LIMIT ?1"
+             FROM notes WHERE deleted_at IS NULL ORDER BY updated_at DESC LIMIT ?1"
         )?;
         let rows = stmt.query_map([limit as i64], Self::row_to_note)?;
         rows.collect()
@@ -245,7 +464,7 @@ impl Database {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare_cached(
             "SELECT id, title, content, created_at, updated_at, position_x, position_y, is_visible, always_on_top, width, height, theme_bg, theme_fg, theme_accent, custom_colors, chromeless, star_color
-             FROM notes WHERE title = ?1"
+             FROM notes WHERE title = ?1 AND deleted_at IS NULL"
         )?;
         let mut rows = stmt.query_map([title], Self::row_to_note)?;
         match rows.next() {
@@ -254,53 +473,358 @@ impl Database {
         }
     }
 
-    pub fn get_notes_linking_to(&self, title: &str) -> Result<Vec<Note>> {
+    /// Notes whose content links to `note_id`, most recently updated first.
+    pub fn get_backlinks(&self, note_id: i64) -> Result<Vec<Note>> {
         let conn = self.conn.lock().unwrap();
-        let pattern = format!("%tangle://{}%", title);
         let mut stmt = conn.prepare_cached(
-            "SELECT id, title, content, created_at, updated_at, position_x, position_y, is_visible, always_on_top, width, height, theme_bg, theme_fg, theme_accent, custom_colors, chromeless, star_color
-             FROM notes WHERE content LIKE ?1
-             ORDER BY updated_at DESC"
+            "SELECT n.id, n.title, n.content, n.created_at, n.updated_at, n.position_x, n.position_y, n.is_visible, n.always_on_top, n.width, n.height, n.theme_bg, n.theme_fg, n.theme_accent, n.custom_colors, n.chromeless, n.star_color
+             FROM links l
+             JOIN notes n ON n.id = l.source_note_id
+             WHERE l.target_note_id = ?1 AND l.link_type = 'tangle' AND l.source_note_id != ?1
+               AND n.deleted_at IS NULL
+             ORDER BY n.updated_at DESC"
+        )?;
+        let rows = stmt.query_map([note_id], Self::row_to_note)?;
+        rows.collect()
+    }
+
+    /// All (source, target) note-id pairs for tangle links.
+    pub fn get_tangle_edges(&self) -> Result<Vec<(i64, i64)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT l.source_note_id, l.target_note_id
+             FROM links l
+             JOIN notes s ON s.id = l.source_note_id
+             JOIN notes t ON t.id = l.target_note_id
+             WHERE l.link_type = 'tangle' AND s.deleted_at IS NULL AND t.deleted_at IS NULL"
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// Labels of tangle links by (source, target) note id; unlabelled links are absent.
+    pub fn get_link_labels(&self) -> Result<std::collections::HashMap<(i64, i64), String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT source_note_id, target_note_id, label FROM links
+             WHERE link_type = 'tangle' AND label IS NOT NULL AND label != ''"
         )?;
-        let rows = stmt.query_map([pattern], Self::row_to_note)?;
+        let rows = stmt.query_map([], |row| Ok(((row.get(0)?, row.get(1)?), row.get(2)?)))?;
         rows.collect()
     }
 
+    /// Set or clear (`None`) the label of the tangle link from `source_id` to
+    /// `target_id`. The row is created if the link has not been synced yet; a
+    /// later content sync keeps it as long as the anchor exists.
+    pub fn set_link_label(&self, source_id: i64, target_id: i64, label: Option<&str>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO links (source_note_id, target_note_id, link_type, created_at, label)
+             VALUES (?1, ?2, 'tangle', ?3, ?4)
+             ON CONFLICT (source_note_id, target_note_id, link_type) DO UPDATE SET label = excluded.label",
+            params![source_id, target_id, chrono::Utc::now().to_rfc3339(), label],
+        )?;
+        Ok(())
+    }
+
+    /// Notes whose text mentions `title` without linking to it, most
+    /// recently updated first. Candidates come from the full-text index and
+    /// are confirmed against the HTML, so mentions inside other links and
+    /// partial words don't count.
+    pub fn get_unlinked_mentions(&self, note_id: i64, title: &str) -> Result<Vec<Note>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT n.id, n.title, n.content, n.created_at, n.updated_at, n.position_x, n.position_y, n.is_visible, n.always_on_top, n.width, n.height, n.theme_bg, n.theme_fg, n.theme_accent, n.custom_colors, n.chromeless, n.star_color
+             FROM notes_fts
+             JOIN notes n ON n.id = notes_fts.rowid
+             WHERE notes_fts MATCH ?1 AND n.id != ?2 AND n.deleted_at IS NULL
+               AND NOT EXISTS (
+                   SELECT 1 FROM links l
+                   WHERE l.source_note_id = n.id AND l.target_note_id = ?2 AND l.link_type = 'tangle'
+               )
+             ORDER BY n.updated_at DESC"
+        )?;
+        let query = format!("body : {}", fts_quote(title));
+        let rows = stmt.query_map(params![query, note_id], Self::row_to_note)?;
+        let mut notes = Vec::new();
+        for note in rows {
+            let note = note?;
+            if find_unlinked_mention(&note.content, title).is_some() {
+                notes.push(note);
+            }
+        }
+        Ok(notes)
+    }
+
+    /// Replace a note's HTML with what `edit` makes of it (`None` leaves the
+    /// note alone). Content, `updated_at`, the search index and the link rows
+    /// change in one transaction. Returns the new HTML if there was a change.
+    pub fn replace_note_content(
+        &self,
+        id: i64,
+        edit: impl FnOnce(&str) -> Option<String>,
+    ) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        let replaced = Self::replace_note_content_with_conn(&tx, id, edit)?;
+        tx.commit()?;
+        Ok(replaced)
+    }
+
+    /// `replace_note_content` for a `ContentEdit`. A retargeted link takes its
+    /// label along in the same transaction.
+    pub fn apply_content_edit(&self, id: i64, edit: &ContentEdit) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        let label: Option<String> = match edit {
+            ContentEdit::RewriteLink { target, new_target: Some(_) } => tx
+                .query_row(
+                    "SELECT l.label FROM links l JOIN notes t ON t.id = l.target_note_id
+                     WHERE l.source_note_id = ?1 AND l.link_type = 'tangle'
+                       AND t.title = ?2 AND t.deleted_at IS NULL",
+                    params![id, target],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .flatten(),
+            _ => None,
+        };
+        let replaced = Self::replace_note_content_with_conn(&tx, id, |html| edit.apply(html))?;
+        if let (Some(_), Some(label), ContentEdit::RewriteLink { new_target: Some(new_target), .. }) =
+            (&replaced, label, edit)
+        {
+            // A link the note already had to the new target keeps its own label
+            tx.execute(
+                "UPDATE links SET label = COALESCE(label, ?1)
+                 WHERE source_note_id = ?2 AND link_type = 'tangle'
+                   AND target_note_id = (SELECT id FROM notes WHERE title = ?3 AND deleted_at IS NULL)",
+                params![label, id, new_target],
+            )?;
+        }
+        tx.commit()?;
+        Ok(replaced)
+    }
+
+    fn replace_note_content_with_conn(
+        conn: &Connection,
+        id: i64,
+        edit: impl FnOnce(&str) -> Option<String>,
+    ) -> Result<Option<String>> {
+        let (title, content): (String, String) = conn.query_row(
+            "SELECT title, content FROM notes WHERE id = ?1",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let Some(replaced) = edit(&content).filter(|new| *new != content) else {
+            return Ok(None);
+        };
+        conn.execute(
+            "UPDATE notes SET content = ?1, updated_at = ?2 WHERE id = ?3",
+            params![replaced, chrono::Utc::now().to_rfc3339(), id],
+        )?;
+        Self::index_note_text_with_conn(conn, id, &title, &replaced)?;
+        Self::sync_tangle_links_with_conn(conn, id, &replaced)?;
+        Ok(Some(replaced))
+    }
+
     pub fn get_all_note_titles(&self) -> Result<Vec<String>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare_cached("SELECT title FROM notes ORDER BY title")?;
+        let mut stmt = conn.prepare_cached("SELECT title FROM notes WHERE deleted_at IS NULL ORDER BY title")?;
         let rows = stmt.query_map([], |row| row.get(0))?;
         rows.collect()
     }
 
+    /// Ranked full-text search. Returns notes only; see `search_notes_ranked`
+    /// for ranks and snippets.
     pub fn search_notes(&self, query: &str) -> Result<Vec<Note>> {
+        Ok(self.search_notes_ranked(query)?.into_iter().map(|hit| hit.note).collect())
+    }
+
+    /// Full-text search over titles and tag-stripped content, best match first.
+    /// Supports `"exact phrases"`, `prefix*`, `-excluded` / `NOT excluded` and `OR`;
+    /// the last word is prefix-matched so results update while typing.
+    pub fn search_notes_ranked(&self, query: &str) -> Result<Vec<SearchHit>> {
+        let fts_query = match build_fts_query(query) {
+            Some(q) => q,
+            None => return Ok(Vec::new()),
+        };
         let conn = self.conn.lock().unwrap();
-        let pattern = format!("%{}%", query);
         let mut stmt = conn.prepare_cached(
-            "SELECT id, title, content, created_at, updated_at, position_x, position_y, is_visible, always_on_top, width, height, theme_bg, theme_fg, theme_accent, custom_colors, chromeless, star_color
-             FROM notes WHERE title LIKE ?1 OR content LIKE ?1
-             ORDER BY updated_at DESC"
+            "SELECT n.id, n.title, n.content, n.created_at, n.updated_at, n.position_x, n.position_y, n.is_visible, n.always_on_top, n.width, n.height, n.theme_bg, n.theme_fg, n.theme_accent, n.custom_colors, n.chromeless, n.star_color,
+                    bm25(notes_fts, 10.0, 1.0) AS score,
+                    snippet(notes_fts, 1, ?2, ?3, '\u{2026}', 12)
+             FROM notes_fts
+             JOIN notes n ON n.id = notes_fts.rowid
+             WHERE notes_fts MATCH ?1 AND n.deleted_at IS NULL
+             ORDER BY score"
+        )?;
+        let rows = stmt.query_map(
+            params![fts_query, SNIPPET_START.to_string(), SNIPPET_END.to_string()],
+            |row| {
+                Ok(SearchHit {
+                    note: Self::row_to_note(row)?,
+                    rank: row.get(17)?,
+                    snippet: row.get(18)?,
+                })
+            },
         )?;
-        let rows = stmt.query_map([pattern], Self::row_to_note)?;
         rows.collect()
     }
 
+    /// Move a note to the trash. It stays in the database (with its links and
+    /// history) but is hidden from lists, search, the map and link resolution
+    /// until restored or purged.
     pub fn delete_note(&self, id: i64) -> Result<()> {
         let conn = self.conn.lock().unwrap();
-        conn.execute("DELETE FROM notes WHERE id = ?1", [id])?;
+        conn.execute(
+            "UPDATE notes SET deleted_at = ?1, is_visible = 0 WHERE id = ?2",
+            params![chrono::Utc::now().to_rfc3339(), id],
+        )?;
         Ok(())
     }
 
-    pub fn create_link(&self, link: &Link) -> Result<i64> {
-        let link_type_str = match link.link_type {
-            LinkType::Connection => "connection",
-            LinkType::WordReference => "word_reference",
+    /// Bring a note back from the trash. If a live note has taken its title in
+    /// the meantime, the restored one gets a numbered title instead.
+    /// Returns the title it was restored under.
+    pub fn restore_note(&self, id: i64) -> Result<String> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        let (title, content): (String, String) = tx.query_row(
+            "SELECT title, content FROM notes WHERE id = ?1",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let mut restored_title = title.clone();
+        let mut n = 2;
+        loop {
+            let taken: bool = tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM notes WHERE title = ?1 AND id != ?2 AND deleted_at IS NULL)",
+                params![restored_title, id],
+                |row| row.get(0),
+            )?;
+            if !taken {
+                break;
+            }
+            restored_title = format!("{} ({})", title, n);
+            n += 1;
+        }
+        tx.execute(
+            "UPDATE notes SET deleted_at = NULL, title = ?1 WHERE id = ?2",
+            params![restored_title, id],
+        )?;
+        if restored_title != title {
+            Self::index_note_text_with_conn(&tx, id, &restored_title, &content)?;
+        }
+        Self::sync_tangle_links_with_conn(&tx, id, &content)?;
+        Self::resolve_links_to_title_with_conn(&tx, &restored_title)?;
+        tx.commit()?;
+        Ok(restored_title)
+    }
+
+    /// Notes in the trash, most recently deleted first.
+    pub fn get_trashed_notes(&self) -> Result<Vec<TrashedNote>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, title, content, created_at, updated_at, position_x, position_y, is_visible, always_on_top, width, height, theme_bg, theme_fg, theme_accent, custom_colors, chromeless, star_color, deleted_at
+             FROM notes WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(TrashedNote {
+                note: Self::row_to_note(row)?,
+                deleted_at: row.get(17)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Permanently delete every trashed note moved to the trash more than
+    /// `retention_days` ago. Returns how many were purged.
+    pub fn purge_expired_trash(&self, retention_days: i64) -> Result<usize> {
+        let cutoff = (chrono::Utc::now() - chrono::Duration::days(retention_days)).to_rfc3339();
+        let ids: Vec<i64> = {
+            let conn = self.conn.lock().unwrap();
+            let mut stmt = conn.prepare_cached(
+                "SELECT id FROM notes WHERE deleted_at IS NOT NULL AND deleted_at < ?1"
+            )?;
+            let rows = stmt.query_map([cutoff], |row| row.get(0))?;
+            rows.collect::<Result<_>>()?
         };
+        for id in &ids {
+            self.purge_note(*id)?;
+        }
+        Ok(ids.len())
+    }
+
+    /// Permanently delete every note in the trash.
+    pub fn empty_trash(&self) -> Result<usize> {
+        let ids: Vec<i64> = self.get_trashed_notes()?
+            .iter()
+            .filter_map(|t| t.note.id)
+            .collect();
+        for id in &ids {
+            self.purge_note(*id)?;
+        }
+        Ok(ids.len())
+    }
+
+    /// Permanently delete a note together with its index entry, links and history.
+    pub fn purge_note(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM notes WHERE id = ?1", [id])?;
+        tx.execute("DELETE FROM notes_fts WHERE rowid = ?1", [id])?;
+        tx.execute(
+            "DELETE FROM links WHERE source_note_id = ?1 OR target_note_id = ?1",
+            [id],
+        )?;
+        tx.execute("DELETE FROM note_revisions WHERE note_id = ?1", [id])?;
+        tx.commit()
+    }
+
+    /// Snapshot a note's title and content into its history.
+    /// Returns false (and stores nothing) if it matches the latest revision.
+    pub fn create_revision(&self, note_id: i64, title: &str, content: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let latest: Option<(String, String)> = conn
+            .query_row(
+                "SELECT title, content FROM note_revisions WHERE note_id = ?1 ORDER BY id DESC LIMIT 1",
+                [note_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        if latest.is_some_and(|(t, c)| t == title && c == content) {
+            return Ok(false);
+        }
+        Self::insert_revision_with_conn(&conn, note_id, title, content)?;
+        Ok(true)
+    }
+
+    /// Revisions of a note, newest first.
+    pub fn get_revisions(&self, note_id: i64) -> Result<Vec<NoteRevision>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, note_id, title, content, created_at
+             FROM note_revisions WHERE note_id = ?1
+             ORDER BY id DESC"
+        )?;
+        let rows = stmt.query_map([note_id], |row| {
+            Ok(NoteRevision {
+                id: Some(row.get(0)?),
+                note_id: row.get(1)?,
+                title: row.get(2)?,
+                content: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    pub fn create_link(&self, link: &Link) -> Result<i64> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT INTO links (source_note_id, target_note_id, link_type, created_at)
+            "INSERT OR IGNORE INTO links (source_note_id, target_note_id, link_type, created_at)
              VALUES (?1, ?2, ?3, ?4)",
-            params![link.source_note_id, link.target_note_id, link_type_str, link.created_at],
+            params![link.source_note_id, link.target_note_id, link.link_type.as_str(), link.created_at],
         )?;
         Ok(conn.last_insert_rowid())
     }
@@ -314,15 +838,11 @@ impl Database {
         )?;
         let rows = stmt.query_map([note_id], |row| {
             let link_type_str: String = row.get(3)?;
-            let link_type = match link_type_str.as_str() {
-                "word_reference" => LinkType::WordReference,
-                _ => LinkType::Connection,
-            };
             Ok(Link {
                 id: Some(row.get(0)?),
                 source_note_id: row.get(1)?,
                 target_note_id: row.get(2)?,
-                link_type,
+                link_type: LinkType::from_str(&link_type_str),
                 created_at: row.get(4)?,
             })
         })?;
@@ -333,12 +853,12 @@ impl Database {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare_cached(
             "SELECT n.id, n.title, n.content, n.created_at, n.updated_at, n.position_x, n.position_y, n.is_visible, n.always_on_top, n.width, n.height, n.theme_bg, n.theme_fg, n.theme_accent, n.custom_colors, n.chromeless, n.star_color
-             FROM notes n
-             JOIN word_index w ON n.id = w.note_id
-             WHERE w.word = ?1
-             ORDER BY w.frequency DESC"
+             FROM notes_fts
+             JOIN notes n ON n.id = notes_fts.rowid
+             WHERE notes_fts MATCH ?1 AND n.deleted_at IS NULL
+             ORDER BY bm25(notes_fts)"
         )?;
-        let rows = stmt.query_map([word.to_lowercase()], Self::row_to_note)?;
+        let rows = stmt.query_map([fts_quote(word)], Self::row_to_note)?;
         rows.collect()
     }
 
@@ -379,31 +899,728 @@ impl Database {
         Ok(())
     }
 
-    fn index_note_words_with_conn(conn: &Connection, note_id: i64, content: &str) -> Result<()> {
-        use regex::Regex;
-        use std::collections::HashMap;
+    /// Before an update overwrites a note, keep its stored version if the latest
+    /// revision is missing or older than `REVISION_INTERVAL_SECS` and differs from it.
+    fn snapshot_if_due_with_conn(conn: &Connection, note_id: i64, new_content: &str) -> Result<()> {
+        let (title, content): (String, String) = match conn.query_row(
+            "SELECT title, content FROM notes WHERE id = ?1",
+            [note_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ) {
+            Ok(row) => row,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        if content == new_content || content.is_empty() {
+            return Ok(());
+        }
+        let latest: Option<(String, String)> = conn
+            .query_row(
+                "SELECT content, created_at FROM note_revisions WHERE note_id = ?1 ORDER BY id DESC LIMIT 1",
+                [note_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .ok();
+        let due = match latest {
+            None => true,
+            Some((latest_content, created_at)) => {
+                let age = chrono::DateTime::parse_from_rfc3339(&created_at)
+                    .map(|t| chrono::Utc::now().signed_duration_since(t).num_seconds())
+                    .unwrap_or(i64::MAX);
+                latest_content != content && age >= REVISION_INTERVAL_SECS
+            }
+        };
+        if due {
+            Self::insert_revision_with_conn(conn, note_id, &title, &content)?;
+        }
+        Ok(())
+    }
 
-        // Strip HTML tags before indexing
-        let tag_re = Regex::new(r"<[^>]+>").unwrap();
-        let plain = tag_re.replace_all(content, "");
+    fn insert_revision_with_conn(conn: &Connection, note_id: i64, title: &str, content: &str) -> Result<()> {
+        conn.execute(
+            "INSERT INTO note_revisions (note_id, title, content, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![note_id, title, content, chrono::Utc::now().to_rfc3339()],
+        )?;
+        conn.execute(
+            "DELETE FROM note_revisions WHERE note_id = ?1 AND id NOT IN (
+                 SELECT id FROM note_revisions WHERE note_id = ?1 ORDER BY id DESC LIMIT ?2
+             )",
+            params![note_id, REVISIONS_PER_NOTE],
+        )?;
+        Ok(())
+    }
 
-        let word_regex = Regex::new(r"\b\w+\b").unwrap();
-        let mut word_count: HashMap<String, i32> = HashMap::new();
-        for cap in word_regex.find_iter(&plain) {
-            let word = cap.as_str().to_lowercase();
-            *word_count.entry(word).or_insert(0) += 1;
-        }
-        // Batch all inserts in a single transaction
-        let tx = conn.unchecked_transaction()?;
+    /// Make the note's `tangle` rows in `links` match the anchors in its content.
+    /// Targets that don't exist yet are skipped until a note with that title appears.
+    fn sync_tangle_links_with_conn(conn: &Connection, note_id: i64, content: &str) -> Result<()> {
+        use std::collections::HashSet;
+
+        let mut targets: HashSet<i64> = HashSet::new();
         {
-            let mut stmt = tx.prepare_cached(
-                "INSERT INTO word_index (word, note_id, frequency) VALUES (?1, ?2, ?3)"
+            let mut stmt = conn.prepare_cached("SELECT id FROM notes WHERE title = ?1 AND deleted_at IS NULL")?;
+            for title in extract_tangle_targets(content) {
+                if let Ok(id) = stmt.query_row([&title], |row| row.get(0)) {
+                    targets.insert(id);
+                }
+            }
+        }
+        let existing: HashSet<i64> = {
+            let mut stmt = conn.prepare_cached(
+                "SELECT target_note_id FROM links WHERE source_note_id = ?1 AND link_type = 'tangle'"
+            )?;
+            let rows = stmt.query_map([note_id], |row| row.get(0))?;
+            rows.collect::<Result<_>>()?
+        };
+
+        for stale in existing.difference(&targets) {
+            conn.execute(
+                "DELETE FROM links WHERE source_note_id = ?1 AND target_note_id = ?2 AND link_type = 'tangle'",
+                params![note_id, stale],
             )?;
-            for (word, frequency) in &word_count {
-                stmt.execute(params![word, note_id, frequency])?;
+        }
+        let now = chrono::Utc::now().to_rfc3339();
+        for added in targets.difference(&existing) {
+            conn.execute(
+                "INSERT OR IGNORE INTO links (source_note_id, target_note_id, link_type, created_at)
+                 VALUES (?1, ?2, 'tangle', ?3)",
+                params![note_id, added, now],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Re-sync links in notes that mention `tangle://title`, so anchors written
+    /// before the target existed start counting once it does.
+    fn resolve_links_to_title_with_conn(conn: &Connection, title: &str) -> Result<()> {
+        let sources: Vec<(i64, String)> = {
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, content FROM notes WHERE instr(content, 'tangle://') > 0"
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<_>>()?
+        };
+        for (id, content) in sources {
+            if extract_tangle_targets(&content).iter().any(|t| t == title) {
+                Self::sync_tangle_links_with_conn(conn, id, &content)?;
             }
         }
-        tx.commit()?;
         Ok(())
     }
+
+    fn index_note_text_with_conn(conn: &Connection, note_id: i64, title: &str, content: &str) -> Result<()> {
+        conn.execute("DELETE FROM notes_fts WHERE rowid = ?1", [note_id])?;
+        conn.execute(
+            "INSERT INTO notes_fts (rowid, title, body) VALUES (?1, ?2, ?3)",
+            params![note_id, title, html_to_plain_text(content)],
+        )?;
+        Ok(())
+    }
+}
+
+/// Location of the database shared by the app and `tangles-cli`
+/// (`~/.local/share/tangles/tangles.db` on Linux).
+pub fn default_db_path() -> Option<std::path::PathBuf> {
+    dirs::data_dir().map(|d| d.join("tangles").join("tangles.db"))
+}
+
+/// Convert stored note HTML into plain text: tags removed, block ends turned
+/// into newlines and the entities produced by the serializer decoded.
+pub fn html_to_plain_text(html: &str) -> String {
+    static BLOCK_END_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"(?i)</(p|h[1-4]|li|pre)>|<br\s*/?>").unwrap());
+    static TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]+>").unwrap());
+
+    let text = BLOCK_END_RE.replace_all(html, "\n");
+    let text = TAG_RE.replace_all(&text, "");
+    unescape_html(&text)
+}
+
+/// A `tangle://` anchor: the (escaped) target title, then the link's inner HTML.
+static TANGLE_ANCHOR_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)<a\s[^>]*?href="tangle://([^"]*)"[^>]*>(.*?)</a>"#).unwrap()
+});
+
+/// The paragraphs of note HTML that link to `target`, as plain text with
+/// each link's text wrapped in `SNIPPET_START`/`SNIPPET_END`. Long
+/// paragraphs are cut down to the sentences around the links.
+pub fn tangle_link_contexts(html: &str, target: &str) -> Vec<String> {
+    const MAX_CHARS: usize = 240;
+
+    let marked = TANGLE_ANCHOR_RE.replace_all(html, |cap: &Captures| {
+        if unescape_html(&cap[1]) == target {
+            format!("{}{}{}", SNIPPET_START, &cap[2], SNIPPET_END)
+        } else {
+            cap[0].to_string()
+        }
+    });
+    let is_sentence_end = |c: char| matches!(c, '.' | '!' | '?');
+
+    let mut contexts: Vec<String> = Vec::new();
+    for paragraph in html_to_plain_text(&marked).lines() {
+        let paragraph = paragraph.trim();
+        let (Some(first), Some(last)) = (paragraph.find(SNIPPET_START), paragraph.rfind(SNIPPET_END)) else {
+            continue;
+        };
+        let mut context = paragraph.to_string();
+        if paragraph.chars().count() > MAX_CHARS {
+            // From the end of the sentence before the first link to the end of the one with the last
+            let start = paragraph[..first]
+                .rfind(is_sentence_end)
+                .map(|i| i + 1)
+                .unwrap_or(0);
+            let end = paragraph[last..]
+                .find(is_sentence_end)
+                .map(|i| last + i + 1)
+                .unwrap_or(paragraph.len());
+            context = paragraph[start..end].trim().to_string();
+            if start > 0 {
+                context.insert_str(0, "\u{2026} ");
+            }
+            if end < paragraph.len() {
+                context.push_str(" \u{2026}");
+            }
+        }
+        if !contexts.contains(&context) {
+            contexts.push(context);
+        }
+    }
+    contexts
+}
+
+/// Titles referenced by `tangle://` anchors in note HTML, in document order
+/// and without duplicates.
+pub fn extract_tangle_targets(html: &str) -> Vec<String> {
+    static TANGLE_HREF_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"href="tangle://([^"]*)""#).unwrap());
+
+    let mut targets: Vec<String> = Vec::new();
+    for cap in TANGLE_HREF_RE.captures_iter(html) {
+        let title = unescape_html(&cap[1]);
+        if !title.is_empty() && !targets.contains(&title) {
+            targets.push(title);
+        }
+    }
+    targets
+}
+
+/// Rewrite the `tangle://target` anchors in note HTML. With `new_target`
+/// each one points there instead (and its text follows when it was the old
+/// title); without, the anchor is removed and its text kept.
+pub fn rewrite_tangle_anchors(html: &str, target: &str, new_target: Option<&str>) -> String {
+    static TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]+>").unwrap());
+
+    TANGLE_ANCHOR_RE
+        .replace_all(html, |cap: &Captures| {
+            let whole = &cap[0];
+            if unescape_html(&cap[1]) != target {
+                return whole.to_string();
+            }
+            let inner = &cap[2];
+            let Some(new_target) = new_target else {
+                return inner.to_string();
+            };
+            // Swap the href value, keeping the rest of the opening tag
+            let base = cap.get(0).unwrap().start();
+            let value = cap.get(1).unwrap();
+            let open_end = cap.get(2).unwrap().start() - base;
+            let open_tag = format!(
+                "{}{}{}",
+                &whole[..value.start() - base],
+                escape_html_attr(new_target),
+                &whole[value.end() - base..open_end],
+            );
+            let text = if unescape_html(&TAG_RE.replace_all(inner, "")) == target {
+                escape_html(new_target)
+            } else {
+                inner.to_string()
+            };
+            format!("{}{}</a>", open_tag, text)
+        })
+        .into_owned()
+}
+
+/// A change to a note's HTML made from outside its editor: link edits on the
+/// tangle map and "Link it" in the backlinks pane. Applied to the stored
+/// content, or by an open window to its editor's.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ContentEdit {
+    /// Point `tangle://target` anchors at `new_target`, or unwrap them
+    RewriteLink { target: String, new_target: Option<String> },
+    /// Link the first unlinked mention of `title`
+    LinkMention { title: String },
+}
+
+impl ContentEdit {
+    /// The edited HTML, or `None` when the edit changes nothing.
+    pub fn apply(&self, html: &str) -> Option<String> {
+        match self {
+            ContentEdit::RewriteLink { target, new_target } => {
+                let rewritten = rewrite_tangle_anchors(html, target, new_target.as_deref());
+                (rewritten != html).then_some(rewritten)
+            }
+            ContentEdit::LinkMention { title } => link_mention(html, title),
+        }
+    }
+}
+
+/// Byte range in note HTML of the first case-insensitive, whole-word
+/// occurrence of `title` in text that isn't already inside a link.
+pub fn find_unlinked_mention(html: &str, title: &str) -> Option<std::ops::Range<usize>> {
+    static TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]*>").unwrap());
+
+    let title = title.trim();
+    if title.is_empty() {
+        return None;
+    }
+    // Text is stored escaped, so look for the escaped title, with `"` either
+    // as `&quot;` or raw as older notes have it; word boundaries only apply
+    // where the title starts or ends with a word character
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut pattern = String::from("(?i)");
+    if title.starts_with(is_word) {
+        pattern.push_str(r"\b");
+    }
+    let pieces: Vec<String> = title.split('"').map(|p| regex::escape(&escape_html(p))).collect();
+    pattern.push_str(&pieces.join("(?:\"|&quot;)"));
+    if title.ends_with(is_word) {
+        pattern.push_str(r"\b");
+    }
+    let mention_re = Regex::new(&pattern).ok()?;
+
+    let mut in_link = 0usize;
+    let mut text_start = 0;
+    let check = |start: usize, end: usize, in_link: usize| {
+        if in_link > 0 || start >= end {
+            return None;
+        }
+        mention_re.find(&html[start..end]).map(|m| start + m.start()..start + m.end())
+    };
+    for tag in TAG_RE.find_iter(html) {
+        if let Some(range) = check(text_start, tag.start(), in_link) {
+            return Some(range);
+        }
+        let lower = tag.as_str().to_ascii_lowercase();
+        if lower.starts_with("<a ") || lower == "<a>" {
+            in_link += 1;
+        } else if lower.starts_with("</a") {
+            in_link = in_link.saturating_sub(1);
+        }
+        text_start = tag.end();
+    }
+    check(text_start, html.len(), in_link)
+}
+
+/// Wrap the first unlinked mention of `title` in a tangle anchor, keeping the
+/// mention's own capitalisation as the link text.
+pub fn link_mention(html: &str, title: &str) -> Option<String> {
+    let range = find_unlinked_mention(html, title)?;
+    Some(format!(
+        "{}<a href=\"tangle://{}\" class=\"tangle\">{}</a>{}",
+        &html[..range.start],
+        escape_html_attr(title.trim()),
+        &html[range.clone()],
+        &html[range.end..],
+    ))
+}
+
+/// Quote a single term for FTS5 so punctuation is treated as text, not syntax.
+fn fts_quote(term: &str) -> String {
+    format!("\"{}\"", term.replace('"', "\"\""))
+}
+
+/// Translate a user search string into an FTS5 MATCH expression.
+/// Returns `None` when there is nothing positive to match.
+fn build_fts_query(input: &str) -> Option<String> {
+    enum Part {
+        Term(String),
+        Or,
+    }
+
+    let mut positives: Vec<Part> = Vec::new();
+    let mut negatives: Vec<String> = Vec::new();
+    let mut negate_next = false;
+    let mut last_bare: Option<usize> = None;
+
+    let chars: Vec<char> = input.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i].is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let mut negate = std::mem::take(&mut negate_next);
+        if chars[i] == '-' {
+            negate = true;
+            i += 1;
+        }
+        let (expr, bare) = if i < chars.len() && chars[i] == '"' {
+            // Quoted phrase up to the closing quote (or end of input)
+            let start = i + 1;
+            let mut end = start;
+            while end < chars.len() && chars[end] != '"' {
+                end += 1;
+            }
+            let phrase: String = chars[start..end].iter().collect();
+            i = (end + 1).min(chars.len());
+            if !phrase.chars().any(|c| c.is_alphanumeric()) {
+                continue;
+            }
+            (fts_quote(&phrase), false)
+        } else {
+            let start = i;
+            while i < chars.len() && !chars[i].is_whitespace() {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            if !negate && word == "NOT" {
+                negate_next = true;
+                continue;
+            }
+            if !negate && word == "OR" {
+                if matches!(positives.last(), Some(Part::Term(_))) {
+                    positives.push(Part::Or);
+                }
+                last_bare = None;
+                continue;
+            }
+            let (stem, prefix) = match word.strip_suffix('*') {
+                Some(stem) => (stem, true),
+                None => (word.as_str(), false),
+            };
+            if !stem.chars().any(|c| c.is_alphanumeric()) {
+                continue;
+            }
+            let quoted = fts_quote(stem);
+            if prefix {
+                (format!("{}*", quoted), false)
+            } else {
+                (quoted, true)
+            }
+        };
+        if negate {
+            negatives.push(expr);
+            last_bare = None;
+        } else {
+            last_bare = if bare { Some(positives.len()) } else { None };
+            positives.push(Part::Term(expr));
+        }
+    }
+
+    if matches!(positives.last(), Some(Part::Or)) {
+        positives.pop();
+    }
+    // Prefix-match the word still being typed
+    if !input.ends_with(char::is_whitespace) {
+        if let Some(Part::Term(expr)) = last_bare.and_then(|idx| positives.get_mut(idx)) {
+            expr.push('*');
+        }
+    }
+
+    let mut query = positives
+        .iter()
+        .map(|p| match p {
+            Part::Term(expr) => expr.as_str(),
+            Part::Or => "OR",
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    if query.is_empty() {
+        return None;
+    }
+    // NOT binds tighter than OR, so `a OR b NOT c` would only exclude from b
+    if !negatives.is_empty() && positives.iter().any(|p| matches!(p, Part::Or)) {
+        query = format!("({})", query);
+    }
+    for neg in &negatives {
+        query.push_str(" NOT ");
+        query.push_str(neg);
+    }
+    Some(query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Database {
+        Database::new(Path::new(":memory:")).unwrap()
+    }
+
+    fn add_note(db: &Database, title: &str, content: &str) -> i64 {
+        let now = chrono::Utc::now().to_rfc3339();
+        db.create_note(&Note {
+            id: None,
+            title: title.to_string(),
+            content: content.to_string(),
+            created_at: now.clone(),
+            updated_at: now,
+            position_x: 0.0,
+            position_y: 0.0,
+            is_visible: false,
+            always_on_top: false,
+            width: 400,
+            height: 300,
+            theme_bg: None,
+            theme_fg: None,
+            theme_accent: None,
+            custom_colors: None,
+            chromeless: false,
+            star_color: None,
+        })
+        .unwrap()
+    }
+
+    fn search_titles(db: &Database, query: &str) -> Vec<String> {
+        let mut titles: Vec<String> = db
+            .search_notes_ranked(query)
+            .unwrap()
+            .into_iter()
+            .map(|hit| hit.note.title)
+            .collect();
+        titles.sort();
+        titles
+    }
+
+    #[test]
+    fn fts_query_prefixes_the_word_being_typed() {
+        assert_eq!(build_fts_query("garden").as_deref(), Some("\"garden\"*"));
+        assert_eq!(build_fts_query("garden ").as_deref(), Some("\"garden\""));
+        assert_eq!(build_fts_query("raised gar").as_deref(), Some("\"raised\" \"gar\"*"));
+    }
+
+    #[test]
+    fn fts_query_keeps_explicit_prefixes_and_phrases() {
+        assert_eq!(build_fts_query("gard* ").as_deref(), Some("\"gard\"*"));
+        assert_eq!(build_fts_query("\"raised bed\" soil ").as_deref(), Some("\"raised bed\" \"soil\""));
+        // An unterminated phrase runs to the end and isn't prefix-matched
+        assert_eq!(build_fts_query("\"raised bed").as_deref(), Some("\"raised bed\""));
+        assert_eq!(build_fts_query("say\"hi ").as_deref(), Some("\"say\"\"hi\""));
+    }
+
+    #[test]
+    fn fts_query_or() {
+        assert_eq!(build_fts_query("garden OR balcony ").as_deref(), Some("\"garden\" OR \"balcony\""));
+        // Dangling and leading ORs are dropped
+        assert_eq!(build_fts_query("OR garden OR ").as_deref(), Some("\"garden\""));
+    }
+
+    #[test]
+    fn fts_query_not_and_minus() {
+        assert_eq!(build_fts_query("garden -weeds").as_deref(), Some("\"garden\" NOT \"weeds\""));
+        assert_eq!(build_fts_query("garden NOT weeds ").as_deref(), Some("\"garden\" NOT \"weeds\""));
+        assert_eq!(
+            build_fts_query("garden -\"green fly\" -weeds ").as_deref(),
+            Some("\"garden\" NOT \"green fly\" NOT \"weeds\""),
+        );
+        // Nothing positive to match
+        assert_eq!(build_fts_query("-weeds"), None);
+        assert_eq!(build_fts_query("  "), None);
+    }
+
+    #[test]
+    fn fts_query_groups_or_before_not() {
+        assert_eq!(
+            build_fts_query("garden OR balcony -weeds").as_deref(),
+            Some("(\"garden\" OR \"balcony\") NOT \"weeds\""),
+        );
+    }
+
+    #[test]
+    fn link_contexts_mark_each_link_to_the_target() {
+        let html = concat!(
+            "<p>First <a href=\"tangle://Tom &amp; Jerry\" class=\"tangle\">the <b>duo</b></a> here.</p>",
+            "<p>Nothing, or <a href=\"tangle://Other\" class=\"tangle\">Other</a>.</p>",
+            "<p>First <a href=\"tangle://Tom &amp; Jerry\" class=\"tangle\">the <b>duo</b></a> here.</p>",
+        );
+        let marked = format!("First {}the duo{} here.", SNIPPET_START, SNIPPET_END);
+        assert_eq!(tangle_link_contexts(html, "Tom & Jerry"), [marked]);
+        assert!(tangle_link_contexts(html, "Missing").is_empty());
+    }
+
+    #[test]
+    fn link_contexts_trim_long_paragraphs_to_the_linking_sentence() {
+        let filler = "Padding words go here. ".repeat(8);
+        let html = format!(
+            "<p>{}It links <a href=\"tangle://T\" class=\"tangle\">T</a> now! {}</p>",
+            filler, filler,
+        );
+        assert_eq!(
+            tangle_link_contexts(&html, "T"),
+            [format!("\u{2026} It links {}T{} now! \u{2026}", SNIPPET_START, SNIPPET_END)],
+        );
+    }
+
+    #[test]
+    fn rewrite_tangle_anchors_retargets_and_unlinks() {
+        let html = concat!(
+            "<p>See <a href=\"tangle://Old &amp; Gold\" class=\"tangle\">Old &amp; Gold</a> ",
+            "and <a href=\"tangle://Old &amp; Gold\" class=\"tangle\">the <b>old</b> one</a>, ",
+            "not <a href=\"tangle://Other\" class=\"tangle\">Other</a>.</p>",
+        );
+        assert_eq!(
+            rewrite_tangle_anchors(html, "Old & Gold", Some("New \"Gold\"")),
+            concat!(
+                "<p>See <a href=\"tangle://New &quot;Gold&quot;\" class=\"tangle\">New &quot;Gold&quot;</a> ",
+                "and <a href=\"tangle://New &quot;Gold&quot;\" class=\"tangle\">the <b>old</b> one</a>, ",
+                "not <a href=\"tangle://Other\" class=\"tangle\">Other</a>.</p>",
+            ),
+        );
+        assert_eq!(
+            rewrite_tangle_anchors(html, "Old & Gold", None),
+            "<p>See Old &amp; Gold and the <b>old</b> one, not <a href=\"tangle://Other\" class=\"tangle\">Other</a>.</p>",
+        );
+        assert_eq!(rewrite_tangle_anchors(html, "Missing", None), html);
+    }
+
+    #[test]
+    fn unlinked_mention_skips_links_and_partial_words() {
+        let html = "<p><a href=\"tangle://Rust\" class=\"tangle\">Rust</a> and rusty, then rust.</p>";
+        let range = find_unlinked_mention(html, "Rust").unwrap();
+        assert_eq!(&html[range], "rust");
+        assert_eq!(find_unlinked_mention("<p>Trusted</p>", "rust"), None);
+        assert_eq!(find_unlinked_mention("<p>anything</p>", "  "), None);
+    }
+
+    #[test]
+    fn unlinked_mention_matches_escaped_text() {
+        let html = "<p>Ask about Q&amp;A and &quot;Say &quot;hi&quot;&quot; today</p>";
+        assert_eq!(&html[find_unlinked_mention(html, "q&a").unwrap()], "Q&amp;A");
+        assert_eq!(&html[find_unlinked_mention(html, "Say \"hi\"").unwrap()], "Say &quot;hi&quot;");
+        // Older notes kept the quotes raw in text
+        let html = "<p>Ask \"Say \"hi\"\"</p>";
+        assert_eq!(&html[find_unlinked_mention(html, "Say \"hi\"").unwrap()], "Say \"hi\"");
+    }
+
+    #[test]
+    fn link_mention_wraps_the_first_mention() {
+        assert_eq!(
+            link_mention("<p>Use <b>Tom &amp; Jerry</b> or Tom &amp; Jerry</p>", "Tom & Jerry").as_deref(),
+            Some("<p>Use <b><a href=\"tangle://Tom &amp; Jerry\" class=\"tangle\">Tom &amp; Jerry</a></b> or Tom &amp; Jerry</p>"),
+        );
+        assert_eq!(
+            ContentEdit::LinkMention { title: "Say \"hi\"".into() }.apply("<p>Say \"hi\"</p>").as_deref(),
+            Some("<p><a href=\"tangle://Say &quot;hi&quot;\" class=\"tangle\">Say \"hi\"</a></p>"),
+        );
+        assert_eq!(link_mention("<p>Nothing here</p>", "Tom"), None);
+    }
+
+    #[test]
+    fn revisions_skip_unchanged_snapshots() {
+        let db = test_db();
+        let id = add_note(&db, "Plan", "<p>Goals</p>");
+        assert!(db.create_revision(id, "Plan", "<p>Goals</p>").unwrap());
+        assert!(!db.create_revision(id, "Plan", "<p>Goals</p>").unwrap());
+        // A rename alone is still worth a snapshot
+        assert!(db.create_revision(id, "Plans", "<p>Goals</p>").unwrap());
+        let titles: Vec<String> = db.get_revisions(id).unwrap().into_iter().map(|r| r.title).collect();
+        assert_eq!(titles, ["Plans", "Plan"]);
+    }
+
+    #[test]
+    fn restore_renames_around_a_live_title() {
+        let db = test_db();
+        let plan = add_note(&db, "Plan", "<p>Goals</p>");
+        let other = add_note(&db, "Other", "<p><a href=\"tangle://Plan\" class=\"tangle\">Plan</a></p>");
+        db.delete_note(plan).unwrap();
+        assert_eq!(search_titles(&db, "goals"), Vec::<String>::new());
+        assert!(db.get_tangle_edges().unwrap().is_empty());
+
+        // Nothing took the title: restored as it was, links and all
+        assert_eq!(db.restore_note(plan).unwrap(), "Plan");
+        assert_eq!(db.get_tangle_edges().unwrap(), [(other, plan)]);
+
+        db.delete_note(plan).unwrap();
+        let new_plan = add_note(&db, "Plan", "");
+        add_note(&db, "Plan (2)", "");
+        assert_eq!(db.restore_note(plan).unwrap(), "Plan (3)");
+        assert_eq!(search_titles(&db, "goals"), ["Plan (3)"]);
+        assert_eq!(db.get_tangle_edges().unwrap(), [(other, new_plan)]);
+    }
+
+    #[test]
+    fn purge_expired_trash_keeps_recent_deletions() {
+        let db = test_db();
+        let old = add_note(&db, "Old", "<p><a href=\"tangle://Recent\" class=\"tangle\">Recent</a></p>");
+        let recent = add_note(&db, "Recent", "");
+        db.create_revision(old, "Old", "<p>Before</p>").unwrap();
+        db.delete_note(old).unwrap();
+        db.delete_note(recent).unwrap();
+        let long_ago = (chrono::Utc::now() - chrono::Duration::days(40)).to_rfc3339();
+        db.conn.lock().unwrap()
+            .execute("UPDATE notes SET deleted_at = ?1 WHERE id = ?2", params![long_ago, old])
+            .unwrap();
+
+        assert_eq!(db.purge_expired_trash(30).unwrap(), 1);
+        assert!(db.get_note(old).unwrap().is_none());
+        assert!(db.get_revisions(old).unwrap().is_empty());
+        let conn = db.conn.lock().unwrap();
+        let leftovers: i64 = conn
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM links) + (SELECT COUNT(*) FROM notes_fts WHERE rowid = ?1)",
+                [old],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(leftovers, 0);
+        drop(conn);
+        let trashed: Vec<i64> = db.get_trashed_notes().unwrap().iter().filter_map(|t| t.note.id).collect();
+        assert_eq!(trashed, [recent]);
+    }
+
+    #[test]
+    fn rename_leaves_dangling_links_to_other_titles_alone() {
+        let db = test_db();
+        let other = add_note(&db, "Other", "<p><a href=\"tangle://Meet\" class=\"tangle\">Meet</a></p>");
+        let plan = add_note(&db, "Plan", "");
+
+        // Straight to the committed title: the link to "Meet" still dangles
+        assert_eq!(db.rename_note(plan, "Meeting").unwrap(), Vec::<i64>::new());
+        assert!(db.get_tangle_edges().unwrap().is_empty());
+        assert!(db.get_note(other).unwrap().unwrap().content.contains("tangle://Meet\""));
+
+        // Via the intermediate title the link is taken over, and follows on
+        assert_eq!(db.rename_note(plan, "Meet").unwrap(), Vec::<i64>::new());
+        assert_eq!(db.get_tangle_edges().unwrap(), [(other, plan)]);
+        let before = db.get_note(other).unwrap().unwrap().updated_at;
+        assert_eq!(db.rename_note(plan, "Meeting").unwrap(), [other]);
+        let after = db.get_note(other).unwrap().unwrap();
+        assert!(after.content.contains("tangle://Meeting\""));
+        assert!(after.updated_at > before);
+    }
+
+    #[test]
+    fn content_edit_moves_the_link_label_with_a_retarget() {
+        let db = test_db();
+        let source = add_note(&db, "Source", "<p><a href=\"tangle://A\" class=\"tangle\">A</a></p>");
+        let a = add_note(&db, "A", "");
+        let b = add_note(&db, "B", "");
+        db.replace_note_content(source, |html| Some(html.to_string() + " ")).unwrap();
+        db.set_link_label(source, a, Some("because")).unwrap();
+        let before = db.get_note(source).unwrap().unwrap().updated_at;
+
+        let edit = ContentEdit::RewriteLink { target: "A".into(), new_target: Some("B".into()) };
+        let content = db.apply_content_edit(source, &edit).unwrap().unwrap();
+        assert!(content.contains("tangle://B"));
+        assert_eq!(db.get_tangle_edges().unwrap(), [(source, b)]);
+        let labels = db.get_link_labels().unwrap();
+        assert_eq!(labels.get(&(source, b)).map(String::as_str), Some("because"));
+        assert!(!labels.contains_key(&(source, a)));
+        assert!(db.get_note(source).unwrap().unwrap().updated_at > before);
+
+        // Nothing left to rewrite
+        assert_eq!(db.apply_content_edit(source, &edit).unwrap(), None);
+    }
+
+    #[test]
+    fn search_excludes_from_every_or_branch() {
+        let db = test_db();
+        add_note(&db, "Garden", "<p>tomatoes and weeds</p>");
+        add_note(&db, "Balcony", "<p>herbs and weeds</p>");
+        add_note(&db, "Patio", "<p>herbs in pots</p>");
+        add_note(&db, "Allotment", "<p>tomatoes</p>");
+
+        assert_eq!(search_titles(&db, "tomatoes OR herbs -weeds"), ["Allotment", "Patio"]);
+        assert_eq!(search_titles(&db, "tomatoes OR herbs "), ["Allotment", "Balcony", "Garden", "Patio"]);
+        assert_eq!(search_titles(&db, "tom"), ["Allotment", "Garden"]);
+    }
 }
@@ -0,0 +1,239 @@
+use gtk4::prelude::*;
+use gtk4::{gio, glib};
+use crate::database::{Database, Note};
+
+/// Well-known name, object path and interface of the scripting service.
+/// The object is also reachable under the application id, which GApplication owns.
+pub const BUS_NAME: &str = "org.tangles.Notes";
+pub const OBJECT_PATH: &str = "/org/tangles/Notes";
+pub const INTERFACE: &str = "org.tangles.Notes";
+
+const ERROR_NOT_FOUND: &str = "org.tangles.Notes.Error.NotFound";
+const ERROR_EXISTS: &str = "org.tangles.Notes.Error.Exists";
+const ERROR_FAILED: &str = "org.tangles.Notes.Error.Failed";
+
+const INTROSPECTION_XML: &str = r#"
+<node>
+  <interface name="org.tangles.Notes">
+    <method name="OpenNote">
+      <arg type="s" name="title" direction="in"/>
+    </method>
+    <method name="CreateNote">
+      <arg type="s" name="title" direction="in"/>
+      <arg type="s" name="html" direction="in"/>
+      <arg type="x" name="id" direction="out"/>
+    </method>
+    <method name="AppendToNote">
+      <arg type="s" name="title" direction="in"/>
+      <arg type="s" name="html" direction="in"/>
+    </method>
+    <method name="Search">
+      <arg type="s" name="query" direction="in"/>
+      <arg type="as" name="titles" direction="out"/>
+    </method>
+    <method name="ShowMap"/>
+    <signal name="NoteChanged">
+      <arg type="x" name="id"/>
+      <arg type="s" name="title"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+/// Export the `org.tangles.Notes` object on the application's session bus
+/// connection and claim the `org.tangles.Notes` name. Call once the app is
+/// registered (from `activate`); does nothing without a bus.
+pub fn register(app: &gtk4::Application, db: &Database) {
+    let Some(connection) = app.dbus_connection() else { return };
+    let node = match gio::DBusNodeInfo::for_xml(INTROSPECTION_XML) {
+        Ok(node) => node,
+        Err(e) => {
+            eprintln!("Error parsing D-Bus interface: {}", e);
+            return;
+        }
+    };
+    let Some(interface) = node.lookup_interface(INTERFACE) else { return };
+
+    let app = app.clone();
+    let db = db.clone();
+    let registration = connection
+        .register_object(OBJECT_PATH, &interface)
+        .method_call(move |_, _, _, _, method, params, invocation| {
+            handle_method_call(&app, &db, method, &params, invocation);
+        })
+        .build();
+    if let Err(e) = registration {
+        eprintln!("Error exporting D-Bus object: {}", e);
+        return;
+    }
+
+    gio::bus_own_name_on_connection(
+        &connection,
+        BUS_NAME,
+        gio::BusNameOwnerFlags::NONE,
+        |_, _| {},
+        |_, name| eprintln!("D-Bus name {} is owned by another process", name),
+    );
+}
+
+/// Broadcast `NoteChanged(id, title)` after a tangle has been saved, and
+/// refresh the backlinks panes of the other open tangles (each window
+/// coalesces these into one refresh per second).
+pub fn emit_note_changed(app: &gtk4::Application, id: i64, title: &str) {
+    // Other open tangles may have gained or lost a backlink
+    let own_class = format!("note-{}", id);
+    for win in app.windows() {
+        if win.has_css_class("note-window") && !win.has_css_class(&own_class) {
+            let _ = win.activate_action("note.refresh-backlinks", None);
+        }
+    }
+
+    let Some(connection) = app.dbus_connection() else { return };
+    if let Err(e) = connection.emit_signal(
+        None,
+        OBJECT_PATH,
+        INTERFACE,
+        "NoteChanged",
+        Some(&(id, title).to_variant()),
+    ) {
+        eprintln!("Error emitting NoteChanged: {}", e);
+    }
+}
+
+fn handle_method_call(
+    app: &gtk4::Application,
+    db: &Database,
+    method: &str,
+    params: &glib::Variant,
+    invocation: gio::DBusMethodInvocation,
+) {
+    match method {
+        "OpenNote" => {
+            let Some((title,)) = params.get::<(String,)>() else {
+                return invocation.return_dbus_error(ERROR_FAILED, "expected (s)");
+            };
+            app.activate_action("open-tangle", Some(&title.to_variant()));
+            invocation.return_value(None);
+        }
+        "CreateNote" => {
+            let Some((title, html)) = params.get::<(String, String)>() else {
+                return invocation.return_dbus_error(ERROR_FAILED, "expected (ss)");
+            };
+            match create_note(db, title.trim(), &html) {
+                Ok(id) => {
+                    emit_note_changed(app, id, title.trim());
+                    invocation.return_value(Some(&(id,).to_variant()));
+                }
+                Err((name, message)) => invocation.return_dbus_error(name, &message),
+            }
+        }
+        "AppendToNote" => {
+            let Some((title, html)) = params.get::<(String, String)>() else {
+                return invocation.return_dbus_error(ERROR_FAILED, "expected (ss)");
+            };
+            match append_to_note(app, db, &title, &html) {
+                Ok(()) => invocation.return_value(None),
+                Err((name, message)) => invocation.return_dbus_error(name, &message),
+            }
+        }
+        "Search" => {
+            let Some((query,)) = params.get::<(String,)>() else {
+                return invocation.return_dbus_error(ERROR_FAILED, "expected (s)");
+            };
+            // Query off the main thread, reply from it
+            let db_bg = db.clone();
+            let (tx, rx) = std::sync::mpsc::channel::<Result<Vec<String>, String>>();
+            std::thread::spawn(move || {
+                let titles = db_bg
+                    .search_notes_ranked(&query)
+                    .map(|hits| hits.into_iter().map(|h| h.note.title).collect())
+                    .map_err(|e| e.to_string());
+                let _ = tx.send(titles);
+            });
+            let mut invocation = Some(invocation);
+            glib::timeout_add_local(std::time::Duration::from_millis(30), move || {
+                match rx.try_recv() {
+                    Ok(result) => {
+                        if let Some(invocation) = invocation.take() {
+                            match result {
+                                Ok(titles) => invocation.return_value(Some(&(titles,).to_variant())),
+                                Err(e) => invocation.return_dbus_error(ERROR_FAILED, &e),
+                            }
+                        }
+                        glib::ControlFlow::Break
+                    }
+                    Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+                    Err(_) => glib::ControlFlow::Break,
+                }
+            });
+        }
+        "ShowMap" => {
+            app.activate_action("tangle-map", None);
+            invocation.return_value(None);
+        }
+        _ => invocation.return_dbus_error(
+            "org.freedesktop.DBus.Error.UnknownMethod",
+            &format!("Unknown method {}", method),
+        ),
+    }
+}
+
+type MethodError = (&'static str, String);
+
+fn create_note(db: &Database, title: &str, html: &str) -> Result<i64, MethodError> {
+    if title.is_empty() {
+        return Err((ERROR_FAILED, "title is empty".to_string()));
+    }
+    match db.get_note_by_title(title) {
+        Ok(Some(_)) => return Err((ERROR_EXISTS, format!("a tangle titled '{}' already exists", title))),
+        Ok(None) => {}
+        Err(e) => return Err((ERROR_FAILED, e.to_string())),
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+    let note = Note {
+        id: None,
+        title: title.to_string(),
+        content: html.to_string(),
+        created_at: now.clone(),
+        updated_at: now,
+        position_x: 100.0,
+        position_y: 100.0,
+        is_visible: false,
+        always_on_top: false,
+        width: 500,
+        height: 400,
+        theme_bg: None,
+        theme_fg: None,
+        theme_accent: None,
+        custom_colors: None,
+        chromeless: false,
+        star_color: None,
+    };
+    db.create_note(&note).map_err(|e| (ERROR_FAILED, e.to_string()))
+}
+
+/// Append to an open tangle through its editor (so the window's next save
+/// keeps the new text), otherwise straight to the database.
+fn append_to_note(app: &gtk4::Application, db: &Database, title: &str, html: &str) -> Result<(), MethodError> {
+    let note = match db.get_note_by_title(title) {
+        Ok(Some(note)) => note,
+        Ok(None) => return Err((ERROR_NOT_FOUND, format!("no tangle titled '{}'", title))),
+        Err(e) => return Err((ERROR_FAILED, e.to_string())),
+    };
+    let id = note.id.unwrap_or_default();
+    let class = format!("note-{}", id);
+    if let Some(win) = app.windows().into_iter().find(|w| w.has_css_class(&class)) {
+        return win
+            .activate_action("note.append-html", Some(&html.to_variant()))
+            .map_err(|e| (ERROR_FAILED, e.to_string()));
+    }
+    // Keep the pre-append version so the change can be undone from History
+    let result = db
+        .create_revision(id, &note.title, &note.content)
+        .and_then(|_| db.append_note_content(id, html));
+    if let Err(e) = result {
+        return Err((ERROR_FAILED, e.to_string()));
+    }
+    emit_note_changed(app, id, &note.title);
+    Ok(())
+}
@@ -0,0 +1,423 @@
+//! Import and export windows. The conversions they run live in GTK-free
+//! modules that `tangles-cli` shares.
+
+use gtk4::prelude::*;
+use gtk4::{gio, glib, Button, CheckButton, Label};
+use std::path::{Path, PathBuf};
+use crate::database::Database;
+use crate::link_graph::{export_graph, GraphFormat, LinkGraph};
+use crate::markdown::{
+    export_database, export_notes, ExportOptions, ExportReport, ImportOptions, ImportReport, LinkStyle,
+};
+
+const SETTING_LINK_STYLE: &str = "markdown_link_style";
+const SETTING_COPY_IMAGES: &str = "markdown_copy_images";
+
+// ── Markdown ───────────────────────────────────────────────────────
+
+/// Ask for link style and a folder, then export `note_id` (or every tangle
+/// if None) on a background thread and report the result in the dialog.
+pub fn show_markdown_export_dialog(parent: &impl IsA<gtk4::Window>, db: &Database, note_id: Option<i64>) {
+    let win = gtk4::Window::builder()
+        .title(if note_id.is_some() { "Export Tangle to Markdown" } else { "Export Tangles to Markdown" })
+        .default_width(380)
+        .transient_for(parent)
+        .modal(false)
+        .build();
+    win.add_css_class("note-list-dialog");
+
+    let vbox = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Vertical)
+        .spacing(8)
+        .margin_top(12)
+        .margin_bottom(12)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+
+    vbox.append(&Label::builder().label("Tangle links").xalign(0.0).css_classes(["dim-label"]).build());
+    let link_style = gtk4::DropDown::from_strings(&["[[Wikilinks]]", "Relative .md links"]);
+    if db.get_setting(SETTING_LINK_STYLE).as_deref() == Some("relative") {
+        link_style.set_selected(1);
+    }
+    vbox.append(&link_style);
+
+    let copy_images = CheckButton::builder()
+        .label("Copy images into an images/ folder")
+        .active(db.get_setting(SETTING_COPY_IMAGES).as_deref() != Some("false"))
+        .build();
+    vbox.append(&copy_images);
+
+    let status = Label::builder()
+        .xalign(0.0)
+        .wrap(true)
+        .css_classes(["dim-label"])
+        .build();
+    vbox.append(&status);
+
+    let button_box = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Horizontal)
+        .spacing(6)
+        .halign(gtk4::Align::End)
+        .build();
+    let close_btn = Button::builder().label("Close").css_classes(["close-button"]).build();
+    let export_btn = Button::builder().label("Choose Folder\u{2026}").css_classes(["save-button"]).build();
+    button_box.append(&close_btn);
+    button_box.append(&export_btn);
+    vbox.append(&button_box);
+    win.set_child(Some(&vbox));
+
+    let win_close = win.clone();
+    close_btn.connect_clicked(move |_| win_close.close());
+
+    let db = db.clone();
+    let win_ref = win.clone();
+    export_btn.connect_clicked(move |btn| {
+        let options = ExportOptions {
+            link_style: if link_style.selected() == 1 { LinkStyle::Relative } else { LinkStyle::Wiki },
+            copy_images: copy_images.is_active(),
+        };
+        let _ = db.set_setting(
+            SETTING_LINK_STYLE,
+            if options.link_style == LinkStyle::Relative { "relative" } else { "wiki" },
+        );
+        let _ = db.set_setting(SETTING_COPY_IMAGES, if options.copy_images { "true" } else { "false" });
+
+        let chooser = gtk4::FileDialog::builder()
+            .title("Export to Folder")
+            .modal(true)
+            .build();
+        let db = db.clone();
+        let status = status.clone();
+        let btn = btn.clone();
+        chooser.select_folder(Some(&win_ref), gio::Cancellable::NONE, move |result| {
+            let Some(dir) = result.ok().and_then(|f| f.path()) else { return };
+            btn.set_sensitive(false);
+            status.set_text("Exporting\u{2026}");
+
+            let (tx, rx) = std::sync::mpsc::channel::<std::io::Result<ExportReport>>();
+            let dir_bg = dir.clone();
+            std::thread::spawn(move || {
+                let result = match note_id {
+                    Some(id) => match db.get_note(id) {
+                        Ok(Some(note)) => export_notes(&[note], &dir_bg, &options),
+                        Ok(None) => Ok(ExportReport::default()),
+                        Err(e) => Err(std::io::Error::other(e)),
+                    },
+                    None => export_database(&db, &dir_bg, &options),
+                };
+                let _ = tx.send(result);
+            });
+            glib::timeout_add_local(std::time::Duration::from_millis(50), move || {
+                match rx.try_recv() {
+                    Ok(result) => {
+                        btn.set_sensitive(true);
+                        status.set_text(&match result {
+                            Ok(report) => {
+                                let mut msg = format!(
+                                    "Exported {} tangle{} and {} image{} to {}",
+                                    report.notes, if report.notes == 1 { "" } else { "s" },
+                                    report.images, if report.images == 1 { "" } else { "s" },
+                                    dir.display(),
+                                );
+                                for err in &report.errors {
+                                    eprintln!("Markdown export: {}", err);
+                                }
+                                if !report.errors.is_empty() {
+                                    msg.push_str(&format!(" ({} problem{}, see log)",
+                                        report.errors.len(), if report.errors.len() == 1 { "" } else { "s" }));
+                                }
+                                msg
+                            }
+                            Err(e) => format!("Export failed: {}", e),
+                        });
+                        glib::ControlFlow::Break
+                    }
+                    Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+                    Err(_) => glib::ControlFlow::Break,
+                }
+            });
+        });
+    });
+
+    win.present();
+}
+
+/// Importer run by `show_import_dialog` on the chosen folder.
+pub type FolderImporter = fn(&Database, &Path, &ImportOptions) -> std::io::Result<ImportReport>;
+
+/// Pick a folder and run `import` on it on a background thread, listing any
+/// title collisions. `start_dir` is where the folder chooser opens.
+pub fn show_import_dialog(
+    parent: &impl IsA<gtk4::Window>,
+    db: &Database,
+    title: &str,
+    description: &str,
+    start_dir: Option<PathBuf>,
+    import: FolderImporter,
+) {
+    let win = gtk4::Window::builder()
+        .title(title)
+        .default_width(380)
+        .transient_for(parent)
+        .modal(false)
+        .build();
+    win.add_css_class("note-list-dialog");
+
+    let vbox = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Vertical)
+        .spacing(8)
+        .margin_top(12)
+        .margin_bottom(12)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+
+    vbox.append(&Label::builder()
+        .label(description)
+        .xalign(0.0)
+        .wrap(true)
+        .css_classes(["dim-label"])
+        .build());
+
+    let rename_collisions = CheckButton::builder()
+        .label("Import files whose title already exists as \"Title (imported)\"")
+        .active(false)
+        .build();
+    vbox.append(&rename_collisions);
+
+    let status = Label::builder()
+        .xalign(0.0)
+        .wrap(true)
+        .selectable(true)
+        .css_classes(["dim-label"])
+        .build();
+    vbox.append(&status);
+
+    let button_box = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Horizontal)
+        .spacing(6)
+        .halign(gtk4::Align::End)
+        .build();
+    let close_btn = Button::builder().label("Close").css_classes(["close-button"]).build();
+    let import_btn = Button::builder().label("Choose Folder\u{2026}").css_classes(["save-button"]).build();
+    button_box.append(&close_btn);
+    button_box.append(&import_btn);
+    vbox.append(&button_box);
+    win.set_child(Some(&vbox));
+
+    let win_close = win.clone();
+    close_btn.connect_clicked(move |_| win_close.close());
+
+    let db = db.clone();
+    let win_ref = win.clone();
+    import_btn.connect_clicked(move |btn| {
+        let options = ImportOptions { rename_collisions: rename_collisions.is_active() };
+        let chooser = gtk4::FileDialog::builder()
+            .title("Import from Folder")
+            .modal(true)
+            .build();
+        if let Some(ref dir) = start_dir {
+            chooser.set_initial_folder(Some(&gio::File::for_path(dir)));
+        }
+        let db = db.clone();
+        let status = status.clone();
+        let btn = btn.clone();
+        chooser.select_folder(Some(&win_ref), gio::Cancellable::NONE, move |result| {
+            let Some(dir) = result.ok().and_then(|f| f.path()) else { return };
+            btn.set_sensitive(false);
+            status.set_text("Importing\u{2026}");
+
+            let renamed = options.rename_collisions;
+            let (tx, rx) = std::sync::mpsc::channel::<std::io::Result<ImportReport>>();
+            std::thread::spawn(move || {
+                let _ = tx.send(import(&db, &dir, &options));
+            });
+            glib::timeout_add_local(std::time::Duration::from_millis(50), move || {
+                match rx.try_recv() {
+                    Ok(result) => {
+                        btn.set_sensitive(true);
+                        status.set_text(&match result {
+                            Ok(report) => {
+                                let count = report.imported.len();
+                                let mut msg = format!(
+                                    "Imported {} tangle{} and {} image{}.",
+                                    count, if count == 1 { "" } else { "s" },
+                                    report.images, if report.images == 1 { "" } else { "s" },
+                                );
+                                if !report.collisions.is_empty() {
+                                    msg.push_str(&format!(
+                                        "\n{} already existed{}: {}",
+                                        report.collisions.len(),
+                                        if renamed { " (imported under a new name)" } else { " (skipped)" },
+                                        report.collisions.join(", "),
+                                    ));
+                                }
+                                for err in &report.errors {
+                                    eprintln!("Import: {}", err);
+                                }
+                                if !report.errors.is_empty() {
+                                    msg.push_str(&format!("\n{} file{} could not be read, see log.",
+                                        report.errors.len(), if report.errors.len() == 1 { "" } else { "s" }));
+                                }
+                                msg
+                            }
+                            Err(e) => format!("Import failed: {}", e),
+                        });
+                        glib::ControlFlow::Break
+                    }
+                    Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+                    Err(_) => glib::ControlFlow::Break,
+                }
+            });
+        });
+    });
+
+    win.present();
+}
+
+// ── Link graph ─────────────────────────────────────────────────────
+
+/// Pick a format and file, then export the link graph in the background.
+pub fn show_link_graph_export_dialog(parent: &impl IsA<gtk4::Window>, db: &Database) {
+    let db = db.clone();
+    export_dialog(
+        parent,
+        "Export Link Graph",
+        "tangles",
+        &[
+            ("GraphViz DOT", GraphFormat::Dot),
+            ("GraphML", GraphFormat::GraphMl),
+            ("JSON (nodes and edges)", GraphFormat::Json),
+        ],
+        GraphFormat::extension,
+        move |format, path, status, btn| {
+            btn.set_sensitive(false);
+            status.set_text("Exporting\u{2026}");
+
+            let (tx, rx) = std::sync::mpsc::channel::<std::io::Result<(usize, usize)>>();
+            let db = db.clone();
+            let path_bg = path.clone();
+            std::thread::spawn(move || {
+                let result = LinkGraph::load(&db)
+                    .map_err(std::io::Error::other)
+                    .and_then(|graph| {
+                        std::fs::write(&path_bg, export_graph(&graph, format))?;
+                        Ok((graph.notes.len(), graph.edges.len()))
+                    });
+                let _ = tx.send(result);
+            });
+            let status = status.clone();
+            let btn = btn.clone();
+            glib::timeout_add_local(std::time::Duration::from_millis(50), move || {
+                match rx.try_recv() {
+                    Ok(result) => {
+                        btn.set_sensitive(true);
+                        status.set_text(&match result {
+                            Ok((notes, links)) => format!(
+                                "Exported {} tangle{} and {} link{} to {}",
+                                notes, if notes == 1 { "" } else { "s" },
+                                links, if links == 1 { "" } else { "s" },
+                                path.display(),
+                            ),
+                            Err(e) => format!("Export failed: {}", e),
+                        });
+                        glib::ControlFlow::Break
+                    }
+                    Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+                    Err(_) => glib::ControlFlow::Break,
+                }
+            });
+        },
+    );
+}
+
+// ── Export window ──────────────────────────────────────────────────
+
+/// A small export window: a Format dropdown over `formats`, a status line,
+/// and Close / "Save As…". After the file chooser, `on_save` gets the
+/// chosen format, the path (given the format's extension when it had none),
+/// and the status label and button for reporting progress. Further options
+/// go in the returned box, which sits between the format and the status.
+pub fn export_dialog<F: Copy + 'static>(
+    parent: &impl IsA<gtk4::Window>,
+    title: &str,
+    file_stem: &str,
+    formats: &[(&str, F)],
+    extension: fn(F) -> &'static str,
+    on_save: impl Fn(F, PathBuf, &Label, &Button) + 'static,
+) -> gtk4::Box {
+    let win = gtk4::Window::builder()
+        .title(title)
+        .default_width(340)
+        .transient_for(parent)
+        .modal(false)
+        .build();
+    win.add_css_class("note-list-dialog");
+
+    let vbox = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Vertical)
+        .spacing(8)
+        .margin_top(12)
+        .margin_bottom(12)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+
+    vbox.append(&Label::builder().label("Format").xalign(0.0).css_classes(["dim-label"]).build());
+    let labels: Vec<&str> = formats.iter().map(|(label, _)| *label).collect();
+    let format_drop = gtk4::DropDown::from_strings(&labels);
+    vbox.append(&format_drop);
+
+    let extras = gtk4::Box::new(gtk4::Orientation::Vertical, 8);
+    vbox.append(&extras);
+
+    let status = Label::builder()
+        .xalign(0.0)
+        .wrap(true)
+        .css_classes(["dim-label"])
+        .build();
+    vbox.append(&status);
+
+    let button_box = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Horizontal)
+        .spacing(6)
+        .halign(gtk4::Align::End)
+        .build();
+    let close_btn = Button::builder().label("Close").css_classes(["close-button"]).build();
+    let export_btn = Button::builder().label("Save As\u{2026}").css_classes(["save-button"]).build();
+    button_box.append(&close_btn);
+    button_box.append(&export_btn);
+    vbox.append(&button_box);
+    win.set_child(Some(&vbox));
+
+    let win_close = win.clone();
+    close_btn.connect_clicked(move |_| win_close.close());
+
+    let formats: Vec<F> = formats.iter().map(|(_, format)| *format).collect();
+    let title = title.to_string();
+    let file_stem = file_stem.to_string();
+    let on_save = std::rc::Rc::new(on_save);
+    let win_ref = win.clone();
+    export_btn.connect_clicked(move |btn| {
+        let Some(&format) = formats.get(format_drop.selected() as usize) else { return };
+        let chooser = gtk4::FileDialog::builder()
+            .title(title.as_str())
+            .initial_name(format!("{}.{}", file_stem, extension(format)))
+            .modal(true)
+            .build();
+        let on_save = on_save.clone();
+        let status = status.clone();
+        let btn = btn.clone();
+        chooser.save(Some(&win_ref), gio::Cancellable::NONE, move |result| {
+            let Some(mut path) = result.ok().and_then(|f| f.path()) else { return };
+            if path.extension().is_none() {
+                path.set_extension(extension(format));
+            }
+            on_save(format, path, &status, &btn);
+        });
+    });
+
+    win.present();
+    extras
+}
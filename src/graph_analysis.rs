@@ -0,0 +1,250 @@
+use std::collections::{HashSet, VecDeque};
+use crate::database::extract_tangle_targets;
+use crate::link_graph::LinkGraph;
+
+/// How many hubs the panel lists.
+const HUB_COUNT: usize = 25;
+
+/// A tangle ranked by how much the rest of the graph points at it.
+pub struct Hub {
+    pub note: usize,
+    pub in_degree: usize,
+    pub out_degree: usize,
+    pub rank: f64,
+}
+
+/// Structure of the link graph. Indices are into `LinkGraph::notes`.
+pub struct GraphAnalysis {
+    /// Tangles with no links in or out
+    pub orphans: Vec<usize>,
+    /// Tangles with `tangle://` anchors to titles that don't exist, and those titles
+    pub dangling: Vec<(usize, Vec<String>)>,
+    /// Linked-to tangles, highest PageRank first
+    pub hubs: Vec<Hub>,
+    /// Groups of tangles connected by links in either direction, largest
+    /// first; orphans are left out
+    pub components: Vec<Vec<usize>>,
+}
+
+impl GraphAnalysis {
+    pub fn new(graph: &LinkGraph) -> Self {
+        let n = graph.notes.len();
+        let mut in_degree = vec![0usize; n];
+        let mut out_degree = vec![0usize; n];
+        for &(s, t) in &graph.edges {
+            out_degree[s] += 1;
+            in_degree[t] += 1;
+        }
+
+        let orphans = (0..n).filter(|&i| in_degree[i] == 0 && out_degree[i] == 0).collect();
+
+        let titles: HashSet<&str> = graph.notes.iter().map(|n| n.title.as_str()).collect();
+        let dangling = graph
+            .notes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, note)| {
+                let missing: Vec<String> = extract_tangle_targets(&note.content)
+                    .into_iter()
+                    .filter(|t| !titles.contains(t.as_str()))
+                    .collect();
+                (!missing.is_empty()).then_some((i, missing))
+            })
+            .collect();
+
+        let ranks = pagerank(n, &graph.edges);
+        let mut hubs: Vec<Hub> = (0..n)
+            .filter(|&i| in_degree[i] > 0)
+            .map(|i| Hub { note: i, in_degree: in_degree[i], out_degree: out_degree[i], rank: ranks[i] })
+            .collect();
+        hubs.sort_by(|a, b| b.rank.total_cmp(&a.rank).then(b.in_degree.cmp(&a.in_degree)));
+        hubs.truncate(HUB_COUNT);
+
+        let neighbours = undirected_neighbours(n, &graph.edges);
+        let mut seen = vec![false; n];
+        let mut components: Vec<Vec<usize>> = Vec::new();
+        for start in 0..n {
+            if seen[start] || neighbours[start].is_empty() {
+                continue;
+            }
+            seen[start] = true;
+            let mut component = vec![start];
+            let mut k = 0;
+            while k < component.len() {
+                for &j in &neighbours[component[k]] {
+                    if !seen[j] {
+                        seen[j] = true;
+                        component.push(j);
+                    }
+                }
+                k += 1;
+            }
+            components.push(component);
+        }
+        components.sort_by_key(|c| std::cmp::Reverse(c.len()));
+
+        GraphAnalysis { orphans, dangling, hubs, components }
+    }
+}
+
+fn undirected_neighbours(n: usize, edges: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let mut neighbours: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for &(s, t) in edges {
+        neighbours[s].push(t);
+        neighbours[t].push(s);
+    }
+    neighbours
+}
+
+/// PageRank over the directed links (damping 0.85). Tangles without
+/// outgoing links share their rank with everyone, so the ranks sum to 1.
+pub fn pagerank(n: usize, edges: &[(usize, usize)]) -> Vec<f64> {
+    const DAMPING: f64 = 0.85;
+    if n == 0 {
+        return Vec::new();
+    }
+    let mut out_degree = vec![0usize; n];
+    for &(s, _) in edges {
+        out_degree[s] += 1;
+    }
+    let mut rank = vec![1.0 / n as f64; n];
+    for _ in 0..100 {
+        let sink: f64 = (0..n).filter(|&i| out_degree[i] == 0).map(|i| rank[i]).sum();
+        let base = (1.0 - DAMPING) / n as f64 + DAMPING * sink / n as f64;
+        let mut next = vec![base; n];
+        for &(s, t) in edges {
+            next[t] += DAMPING * rank[s] / out_degree[s] as f64;
+        }
+        let delta: f64 = next.iter().zip(&rank).map(|(a, b)| (a - b).abs()).sum();
+        rank = next;
+        if delta < 1e-9 {
+            break;
+        }
+    }
+    rank
+}
+
+/// Fewest links from `from` to `to`, following links in either direction;
+/// both ends included.
+pub fn shortest_path(graph: &LinkGraph, from: usize, to: usize) -> Option<Vec<usize>> {
+    let n = graph.notes.len();
+    if from >= n || to >= n {
+        return None;
+    }
+    let neighbours = undirected_neighbours(n, &graph.edges);
+    let mut previous: Vec<Option<usize>> = vec![None; n];
+    let mut seen = vec![false; n];
+    seen[from] = true;
+    let mut queue = VecDeque::from([from]);
+    while let Some(i) = queue.pop_front() {
+        if i == to {
+            let mut path = vec![to];
+            let mut k = to;
+            while let Some(p) = previous[k] {
+                path.push(p);
+                k = p;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for &j in &neighbours[i] {
+            if !seen[j] {
+                seen[j] = true;
+                previous[j] = Some(i);
+                queue.push_back(j);
+            }
+        }
+    }
+    None
+}
+
+/// "A → B ← C": arrows follow each link's direction.
+pub fn describe_path(graph: &LinkGraph, path: &[usize]) -> String {
+    let mut text = graph.notes[path[0]].title.clone();
+    for pair in path.windows(2) {
+        let arrow = if graph.edges.contains(&(pair[0], pair[1])) { "\u{2192}" } else { "\u{2190}" };
+        text.push_str(&format!(" {} {}", arrow, graph.notes[pair[1]].title));
+    }
+    if path.len() == 1 {
+        text.push_str(" (same tangle)");
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Note;
+
+    fn note(title: &str, content: &str) -> Note {
+        Note {
+            id: None,
+            title: title.to_string(),
+            content: content.to_string(),
+            created_at: String::new(),
+            updated_at: String::new(),
+            position_x: 0.0,
+            position_y: 0.0,
+            is_visible: false,
+            always_on_top: false,
+            width: 400,
+            height: 300,
+            theme_bg: None,
+            theme_fg: None,
+            theme_accent: None,
+            custom_colors: None,
+            chromeless: false,
+            star_color: None,
+        }
+    }
+
+    /// A → B → C ← D, E on its own; A also links to the missing "Gone".
+    fn sample() -> LinkGraph {
+        LinkGraph {
+            notes: vec![
+                note("A", "<p><a href=\"tangle://B\" class=\"tangle\">B</a> <a href=\"tangle://Gone\" class=\"tangle\">x</a></p>"),
+                note("B", ""),
+                note("C", ""),
+                note("D", ""),
+                note("E", ""),
+            ],
+            edges: vec![(0, 1), (1, 2), (3, 2)],
+        }
+    }
+
+    #[test]
+    fn pagerank_sums_to_one_and_favours_the_linked_to() {
+        assert!(pagerank(0, &[]).is_empty());
+        let ranks = pagerank(4, &[(1, 0), (2, 0), (3, 0), (0, 1)]);
+        assert!((ranks.iter().sum::<f64>() - 1.0).abs() < 1e-6);
+        assert!(ranks[0] > ranks[1] && ranks[1] > ranks[2]);
+        assert!((ranks[2] - ranks[3]).abs() < 1e-9);
+        // With no links everyone is equal
+        assert!(pagerank(3, &[]).iter().all(|r| (r - 1.0 / 3.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn shortest_path_follows_links_either_way() {
+        let graph = sample();
+        assert_eq!(shortest_path(&graph, 0, 3), Some(vec![0, 1, 2, 3]));
+        assert_eq!(shortest_path(&graph, 2, 2), Some(vec![2]));
+        assert_eq!(shortest_path(&graph, 0, 4), None);
+        assert_eq!(shortest_path(&graph, 0, 9), None);
+        assert_eq!(describe_path(&graph, &[0, 1, 2, 3]), "A \u{2192} B \u{2192} C \u{2190} D");
+        assert_eq!(describe_path(&graph, &[4]), "E (same tangle)");
+    }
+
+    #[test]
+    fn analysis_finds_orphans_dangling_hubs_and_clusters() {
+        let analysis = GraphAnalysis::new(&sample());
+        assert_eq!(analysis.orphans, [4]);
+        assert_eq!(analysis.dangling, [(0, vec!["Gone".to_string()])]);
+        assert_eq!(analysis.hubs[0].note, 2);
+        assert_eq!(analysis.hubs[0].in_degree, 2);
+        assert_eq!(analysis.hubs.len(), 2);
+        assert_eq!(analysis.components.len(), 1);
+        let mut cluster = analysis.components[0].clone();
+        cluster.sort();
+        assert_eq!(cluster, [0, 1, 2, 3]);
+    }
+}
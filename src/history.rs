@@ -0,0 +1,283 @@
+use gtk4::prelude::*;
+use gtk4::{glib, Button, Label, ListBox, ListBoxRow, ScrolledWindow, TextView};
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::database::{self, Database, NoteRevision};
+
+/// One line of a line-based diff.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffLine {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+/// Line diff from `old` to `new` (longest common subsequence).
+/// Very large inputs fall back to "everything removed, everything added".
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let (n, m) = (a.len(), b.len());
+
+    if n.saturating_mul(m) > 4_000_000 {
+        let mut out: Vec<DiffLine> = a.iter().map(|l| DiffLine::Removed(l.to_string())).collect();
+        out.extend(b.iter().map(|l| DiffLine::Added(l.to_string())));
+        return out;
+    }
+
+    // lcs[i][j] = LCS length of a[i..] and b[j..]
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a[i] == b[j] {
+            out.push(DiffLine::Same(a[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            out.push(DiffLine::Removed(a[i].to_string()));
+            i += 1;
+        } else {
+            out.push(DiffLine::Added(b[j].to_string()));
+            j += 1;
+        }
+    }
+    out.extend(a[i..].iter().map(|l| DiffLine::Removed(l.to_string())));
+    out.extend(b[j..].iter().map(|l| DiffLine::Added(l.to_string())));
+    out
+}
+
+/// History window for one tangle: revision list on the left, a diff of the
+/// selected revision against the current content on the right, and Restore.
+/// `current_content` returns the editor's live HTML; `on_restore` receives the
+/// chosen revision and applies its title and content (snapshotting the current
+/// state first).
+pub fn show_history_window(
+    parent: &impl IsA<gtk4::Window>,
+    db: &Database,
+    note_id: i64,
+    current_content: Rc<dyn Fn() -> String>,
+    on_restore: Rc<dyn Fn(&NoteRevision)>,
+) -> gtk4::Window {
+    let win = gtk4::Window::builder()
+        .title("Tangle History")
+        .default_width(720)
+        .default_height(480)
+        .transient_for(parent)
+        .modal(false)
+        .build();
+    win.add_css_class("note-list-dialog");
+
+    let list_box = ListBox::builder()
+        .selection_mode(gtk4::SelectionMode::Single)
+        .build();
+    list_box.add_css_class("boxed-list");
+    let list_scrolled = ScrolledWindow::builder()
+        .child(&list_box)
+        .vexpand(true)
+        .min_content_width(220)
+        .build();
+
+    let diff_view = TextView::builder()
+        .editable(false)
+        .cursor_visible(false)
+        .monospace(true)
+        .wrap_mode(gtk4::WrapMode::WordChar)
+        .css_classes(["rich-editor", "history-diff"])
+        .left_margin(8)
+        .right_margin(8)
+        .top_margin(8)
+        .bottom_margin(8)
+        .build();
+    let diff_buf = diff_view.buffer();
+    let table = diff_buf.tag_table();
+    table.add(&gtk4::TextTag::builder().name("diff-added").background("#1b5e20").build());
+    table.add(&gtk4::TextTag::builder().name("diff-removed").background("#7f1d1d").strikethrough(true).build());
+    table.add(&gtk4::TextTag::builder().name("diff-same").foreground("#9e9e9e").build());
+    diff_buf.set_text("Select a revision to compare it with the current version.");
+
+    let diff_scrolled = ScrolledWindow::builder()
+        .child(&diff_view)
+        .vexpand(true)
+        .hexpand(true)
+        .build();
+
+    let restore_btn = Button::builder()
+        .label("Restore This Version")
+        .css_classes(["save-button"])
+        .halign(gtk4::Align::End)
+        .sensitive(false)
+        .build();
+
+    let right = gtk4::Box::builder()
+        .orientation(gtk4::Orientation::Vertical)
+        .spacing(6)
+        .build();
+    right.append(&Label::builder()
+        .label("Changes since this revision (red: removed, green: added)")
+        .xalign(0.0)
+        .css_classes(["dim-label"])
+        .build());
+    right.append(&diff_scrolled);
+    right.append(&restore_btn);
+
+    let paned = gtk4::Paned::builder()
+        .orientation(gtk4::Orientation::Horizontal)
+        .start_child(&list_scrolled)
+        .end_child(&right)
+        .position(240)
+        .margin_top(8)
+        .margin_bottom(8)
+        .margin_start(8)
+        .margin_end(8)
+        .build();
+    win.set_child(Some(&paned));
+
+    let revisions: Rc<RefCell<Vec<NoteRevision>>> = Rc::new(RefCell::new(Vec::new()));
+
+    // Selecting a revision shows its diff against the live content
+    let revs_sel = revisions.clone();
+    let diff_buf_sel = diff_buf.clone();
+    let restore_sel = restore_btn.clone();
+    list_box.connect_row_selected(move |_, row| {
+        let revs = revs_sel.borrow();
+        let Some(rev) = row.and_then(|r| revs.get(r.index() as usize)) else {
+            restore_sel.set_sensitive(false);
+            return;
+        };
+        let old_text = database::html_to_plain_text(&rev.content);
+        let new_text = database::html_to_plain_text(&current_content());
+        render_diff(&diff_buf_sel, &diff_lines(&old_text, &new_text));
+        restore_sel.set_sensitive(true);
+    });
+
+    let revs_restore = revisions.clone();
+    let list_restore = list_box.clone();
+    let win_restore = win.clone();
+    restore_btn.connect_clicked(move |_| {
+        let Some(row) = list_restore.selected_row() else { return };
+        let rev = match revs_restore.borrow().get(row.index() as usize) {
+            Some(rev) => rev.clone(),
+            None => return,
+        };
+        on_restore(&rev);
+        win_restore.close();
+    });
+
+    // Load revisions off the main thread
+    let db_bg = db.clone();
+    let (tx, rx) = std::sync::mpsc::channel::<Vec<NoteRevision>>();
+    std::thread::spawn(move || {
+        let _ = tx.send(db_bg.get_revisions(note_id).unwrap_or_default());
+    });
+    let list_fill = list_box.clone();
+    glib::timeout_add_local(std::time::Duration::from_millis(30), move || {
+        match rx.try_recv() {
+            Ok(revs) => {
+                populate_revision_list(&list_fill, &revs);
+                *revisions.borrow_mut() = revs;
+                glib::ControlFlow::Break
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+            Err(_) => glib::ControlFlow::Break,
+        }
+    });
+
+    win
+}
+
+fn populate_revision_list(list_box: &ListBox, revisions: &[NoteRevision]) {
+    if revisions.is_empty() {
+        let empty = Label::builder()
+            .label("No history yet")
+            .css_classes(["dim-label"])
+            .margin_top(20)
+            .margin_bottom(20)
+            .build();
+        let row = ListBoxRow::new();
+        row.set_child(Some(&empty));
+        row.set_activatable(false);
+        row.set_selectable(false);
+        list_box.append(&row);
+        return;
+    }
+    for rev in revisions {
+        let info_box = gtk4::Box::builder()
+            .orientation(gtk4::Orientation::Vertical)
+            .spacing(2)
+            .css_classes(["note-row"])
+            .build();
+        info_box.append(&Label::builder()
+            .label(format_revision_time(&rev.created_at))
+            .xalign(0.0)
+            .css_classes(["note-row-title"])
+            .build());
+        let words = database::html_to_plain_text(&rev.content).split_whitespace().count();
+        info_box.append(&Label::builder()
+            .label(format!("{}  \u{2022}  {} words", rev.title, words))
+            .xalign(0.0)
+            .ellipsize(gtk4::pango::EllipsizeMode::End)
+            .css_classes(["note-row-preview"])
+            .build());
+        let row = ListBoxRow::new();
+        row.set_child(Some(&info_box));
+        list_box.append(&row);
+    }
+}
+
+fn render_diff(buffer: &gtk4::TextBuffer, diff: &[DiffLine]) {
+    buffer.set_text("");
+    if diff.iter().all(|l| matches!(l, DiffLine::Same(_))) {
+        buffer.set_text("No differences from the current version.");
+        return;
+    }
+    for line in diff {
+        let (prefix, text, tag) = match line {
+            DiffLine::Same(t) => ("  ", t, "diff-same"),
+            DiffLine::Added(t) => ("+ ", t, "diff-added"),
+            DiffLine::Removed(t) => ("- ", t, "diff-removed"),
+        };
+        let mut end = buffer.end_iter();
+        buffer.insert_with_tags_by_name(&mut end, &format!("{}{}\n", prefix, text), &[tag]);
+    }
+}
+
+fn format_revision_time(rfc3339: &str) -> String {
+    match chrono::DateTime::parse_from_rfc3339(rfc3339) {
+        Ok(dt) => dt.with_timezone(&chrono::Local).format("%b %d, %Y  %H:%M").to_string(),
+        Err(_) => rfc3339.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_keeps_common_lines_in_order() {
+        use DiffLine::*;
+        assert_eq!(
+            diff_lines("a\nb\nc\nd", "a\nc\nx\nd"),
+            [Same("a".into()), Removed("b".into()), Same("c".into()), Added("x".into()), Same("d".into())],
+        );
+    }
+
+    #[test]
+    fn diff_of_empty_sides() {
+        use DiffLine::*;
+        assert_eq!(diff_lines("", "a\nb"), [Added("a".into()), Added("b".into())]);
+        assert_eq!(diff_lines("a", ""), [Removed("a".into())]);
+        assert!(diff_lines("", "").is_empty());
+        assert_eq!(diff_lines("same", "same"), [Same("same".into())]);
+    }
+}
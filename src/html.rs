@@ -0,0 +1,90 @@
+//! Escaping and tokenizing for the editor's stored HTML subset.
+//!
+//! Text runs are escaped with `escape_html`, attribute values (link targets,
+//! image paths) with `escape_html_attr`. Both escape `"`, but notes written
+//! by older builds may hold it raw in text, so byte-level searches over note
+//! HTML have to accept either spelling.
+
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::{
+    BufferQueue, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+};
+use std::cell::RefCell;
+
+/// Escape a text run the way the editor's serializer writes it.
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Escape a double-quoted attribute value.
+pub(crate) fn escape_html_attr(text: &str) -> String {
+    escape_html(text)
+}
+
+/// Decode the entities `escape_html` produces, plus the few others
+/// pasted HTML commonly carries.
+pub(crate) fn unescape_html(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+/// Plain text as editor HTML: one paragraph per line.
+pub fn plain_text_to_html(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("<p>{}</p>\n", escape_html(line)))
+        .collect()
+}
+
+struct HtmlSink {
+    tokens: RefCell<Vec<HtmlToken>>,
+}
+
+#[derive(Debug)]
+pub(crate) enum HtmlToken {
+    StartTag(String, Vec<(String, String)>),
+    EndTag(String),
+    Text(String),
+}
+
+impl TokenSink for HtmlSink {
+    type Handle = ();
+
+    fn process_token(&self, token: Token, _line_number: u64) -> TokenSinkResult<()> {
+        match token {
+            Token::TagToken(tag) => {
+                let name = tag.name.to_string();
+                let attrs: Vec<(String, String)> = tag.attrs.iter()
+                    .map(|a| (a.name.local.to_string(), a.value.to_string()))
+                    .collect();
+                match tag.kind {
+                    TagKind::StartTag => self.tokens.borrow_mut().push(HtmlToken::StartTag(name, attrs)),
+                    TagKind::EndTag => self.tokens.borrow_mut().push(HtmlToken::EndTag(name)),
+                }
+            }
+            Token::CharacterTokens(s) => {
+                self.tokens.borrow_mut().push(HtmlToken::Text(s.to_string()));
+            }
+            _ => {}
+        }
+        TokenSinkResult::Continue
+    }
+}
+
+/// Split stored note HTML into start tags (with attributes), end tags and
+/// text runs. Entities in text and attribute values are decoded.
+pub(crate) fn tokenize_html(html: &str) -> Vec<HtmlToken> {
+    let sink = HtmlSink { tokens: RefCell::new(Vec::new()) };
+    let tokenizer = Tokenizer::new(sink, TokenizerOpts::default());
+    let queue = BufferQueue::default();
+    queue.push_back(StrTendril::from(html));
+    let _ = tokenizer.feed(&queue);
+    tokenizer.end();
+    tokenizer.sink.tokens.into_inner()
+}
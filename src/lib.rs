@@ -1,6 +1,10 @@
+//! The GTK-free core shared by the app and `tangles-cli`: the database,
+//! HTML and Markdown conversion, imports and link-graph analysis.
+
 pub mod database;
-pub mod note_window;
-pub mod pickers;
-pub mod rich_editor;
-pub mod theme;
-pub mod tangle_map;
+pub mod graph_analysis;
+pub mod html;
+pub mod link_graph;
+pub mod map_layout;
+pub mod markdown;
+pub mod tomboy;
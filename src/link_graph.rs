@@ -0,0 +1,240 @@
+use crate::database::{html_to_plain_text, Database, Note};
+use crate::html::escape_html;
+use serde_json::json;
+
+/// Every tangle and the tangle links between them, as drawn by the map.
+pub struct LinkGraph {
+    pub notes: Vec<Note>,
+    /// (source, target) indices into `notes`
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl LinkGraph {
+    /// Load all tangles and their links. Links whose ends are missing and
+    /// links from a tangle to itself are left out.
+    pub fn load(db: &Database) -> rusqlite::Result<Self> {
+        let notes = db.get_all_notes()?;
+        let index: std::collections::HashMap<i64, usize> = notes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, n)| n.id.map(|id| (id, i)))
+            .collect();
+        let edges = db
+            .get_tangle_edges()?
+            .into_iter()
+            .filter_map(|(s, t)| Some((*index.get(&s)?, *index.get(&t)?)))
+            .filter(|(s, t)| s != t)
+            .collect();
+        Ok(LinkGraph { notes, edges })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    GraphMl,
+    Json,
+}
+
+impl GraphFormat {
+    /// Format from a name or file extension (`dot`/`gv`, `graphml`, `json`).
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "dot" | "gv" => Some(GraphFormat::Dot),
+            "graphml" => Some(GraphFormat::GraphMl),
+            "json" => Some(GraphFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "dot",
+            GraphFormat::GraphMl => "graphml",
+            GraphFormat::Json => "json",
+        }
+    }
+}
+
+/// Serialize `graph` in `format`. Every node carries its note id, title,
+/// star colour, creation and update times and word count.
+pub fn export_graph(graph: &LinkGraph, format: GraphFormat) -> String {
+    match format {
+        GraphFormat::Dot => to_dot(graph),
+        GraphFormat::GraphMl => to_graphml(graph),
+        GraphFormat::Json => to_json(graph),
+    }
+}
+
+pub fn word_count(html: &str) -> usize {
+    html_to_plain_text(html).split_whitespace().count()
+}
+
+fn note_id(note: &Note) -> i64 {
+    note.id.unwrap_or_default()
+}
+
+fn to_dot(graph: &LinkGraph) -> String {
+    let quote = |s: &str| {
+        let escaped = s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+        format!("\"{}\"", escaped)
+    };
+    let mut out = String::from("digraph tangles {\n    node [shape=box, style=rounded];\n");
+    for note in &graph.notes {
+        let mut attrs = vec![format!("label={}", quote(&note.title))];
+        if let Some(ref color) = note.star_color {
+            attrs.push(format!("star_color={}", quote(color)));
+            attrs.push(format!("color={}", quote(color)));
+        }
+        attrs.push(format!("created_at={}", quote(&note.created_at)));
+        attrs.push(format!("updated_at={}", quote(&note.updated_at)));
+        attrs.push(format!("word_count={}", word_count(&note.content)));
+        out.push_str(&format!("    n{} [{}];\n", note_id(note), attrs.join(", ")));
+    }
+    for &(s, t) in &graph.edges {
+        out.push_str(&format!("    n{} -> n{};\n", note_id(&graph.notes[s]), note_id(&graph.notes[t])));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn to_graphml(graph: &LinkGraph) -> String {
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"title\" for=\"node\" attr.name=\"title\" attr.type=\"string\"/>\n",
+        "  <key id=\"star_color\" for=\"node\" attr.name=\"star_color\" attr.type=\"string\"/>\n",
+        "  <key id=\"created_at\" for=\"node\" attr.name=\"created_at\" attr.type=\"string\"/>\n",
+        "  <key id=\"updated_at\" for=\"node\" attr.name=\"updated_at\" attr.type=\"string\"/>\n",
+        "  <key id=\"word_count\" for=\"node\" attr.name=\"word_count\" attr.type=\"int\"/>\n",
+        "  <graph id=\"tangles\" edgedefault=\"directed\">\n",
+    ));
+    for note in &graph.notes {
+        out.push_str(&format!("    <node id=\"n{}\">\n", note_id(note)));
+        out.push_str(&format!("      <data key=\"title\">{}</data>\n", escape_html(&note.title)));
+        if let Some(ref color) = note.star_color {
+            out.push_str(&format!("      <data key=\"star_color\">{}</data>\n", escape_html(color)));
+        }
+        out.push_str(&format!("      <data key=\"created_at\">{}</data>\n", escape_html(&note.created_at)));
+        out.push_str(&format!("      <data key=\"updated_at\">{}</data>\n", escape_html(&note.updated_at)));
+        out.push_str(&format!("      <data key=\"word_count\">{}</data>\n", word_count(&note.content)));
+        out.push_str("    </node>\n");
+    }
+    for (i, &(s, t)) in graph.edges.iter().enumerate() {
+        out.push_str(&format!(
+            "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\"/>\n",
+            i,
+            note_id(&graph.notes[s]),
+            note_id(&graph.notes[t]),
+        ));
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+fn to_json(graph: &LinkGraph) -> String {
+    let nodes: Vec<_> = graph
+        .notes
+        .iter()
+        .map(|n| {
+            json!({
+                "id": note_id(n),
+                "title": n.title,
+                "star_color": n.star_color,
+                "created_at": n.created_at,
+                "updated_at": n.updated_at,
+                "word_count": word_count(&n.content),
+            })
+        })
+        .collect();
+    let edges: Vec<_> = graph
+        .edges
+        .iter()
+        .map(|&(s, t)| json!({ "source": note_id(&graph.notes[s]), "target": note_id(&graph.notes[t]) }))
+        .collect();
+    let mut out = serde_json::to_string_pretty(&json!({ "nodes": nodes, "edges": edges }))
+        .unwrap_or_default();
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(id: i64, title: &str, content: &str, star_color: Option<&str>) -> Note {
+        Note {
+            id: Some(id),
+            title: title.to_string(),
+            content: content.to_string(),
+            created_at: "2024-01-01".to_string(),
+            updated_at: "2024-01-02".to_string(),
+            position_x: 0.0,
+            position_y: 0.0,
+            is_visible: false,
+            always_on_top: false,
+            width: 400,
+            height: 300,
+            theme_bg: None,
+            theme_fg: None,
+            theme_accent: None,
+            custom_colors: None,
+            chromeless: false,
+            star_color: star_color.map(str::to_string),
+        }
+    }
+
+    /// `Say "hi"` (starred) → `a\b` plus line break, `<Tom> & Jerry` on its own.
+    fn sample() -> LinkGraph {
+        LinkGraph {
+            notes: vec![
+                note(1, "Say \"hi\"", "<p>Two words</p>", Some("#ffd700")),
+                note(2, "a\\b\nc", "", None),
+                note(3, "<Tom> & Jerry", "", None),
+            ],
+            edges: vec![(0, 1)],
+        }
+    }
+
+    #[test]
+    fn dot_quotes_titles() {
+        let dot = to_dot(&sample());
+        assert!(dot.contains(
+            "    n1 [label=\"Say \\\"hi\\\"\", star_color=\"#ffd700\", color=\"#ffd700\", \
+             created_at=\"2024-01-01\", updated_at=\"2024-01-02\", word_count=2];\n"
+        ), "{}", dot);
+        assert!(dot.contains("    n2 [label=\"a\\\\b\\nc\", created_at"), "{}", dot);
+        assert!(dot.contains("    n1 -> n2;\n"), "{}", dot);
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn graphml_escapes_data() {
+        let graphml = to_graphml(&sample());
+        assert!(graphml.contains("<data key=\"title\">Say &quot;hi&quot;</data>"), "{}", graphml);
+        assert!(graphml.contains("<data key=\"title\">&lt;Tom&gt; &amp; Jerry</data>"), "{}", graphml);
+        assert!(graphml.contains("<data key=\"word_count\">2</data>"), "{}", graphml);
+        assert!(graphml.contains("<edge id=\"e0\" source=\"n1\" target=\"n2\"/>"), "{}", graphml);
+        // Only the starred node carries a colour
+        assert_eq!(graphml.matches("key=\"star_color\">").count(), 1);
+    }
+
+    #[test]
+    fn json_lists_nodes_and_edges() {
+        let value: serde_json::Value = serde_json::from_str(&to_json(&sample())).unwrap();
+        assert_eq!(
+            value["nodes"][0],
+            json!({
+                "id": 1,
+                "title": "Say \"hi\"",
+                "star_color": "#ffd700",
+                "created_at": "2024-01-01",
+                "updated_at": "2024-01-02",
+                "word_count": 2,
+            }),
+        );
+        assert_eq!(value["nodes"][1]["star_color"], serde_json::Value::Null);
+        assert_eq!(value["nodes"].as_array().unwrap().len(), 3);
+        assert_eq!(value["edges"], json!([{ "source": 1, "target": 2 }]));
+    }
+}
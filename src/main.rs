@@ -3,13 +3,24 @@ use gtk4::{
     gio, glib, Application, ApplicationWindow, Box, Button, Entry, Image, Label, ListBox,
     ListBoxRow, Orientation, PopoverMenu, ScrolledWindow, Window,
 };
+use std::collections::HashMap;
 
+mod analysis_window;
 mod database;
+mod dbus_service;
+mod dialogs;
+mod graph_analysis;
+mod history;
+mod html;
+mod link_graph;
+mod map_layout;
+mod markdown;
 mod pickers;
 mod rich_editor;
 mod note_window;
 mod theme;
 mod tangle_map;
+mod tomboy;
 
 const APP_ID: &str = "com.tangles.Tangles";
 
@@ -19,25 +30,135 @@ const SETTING_WIN_H: &str = "win_h";
 const SETTING_WIN_X: &str = "win_x";
 const SETTING_WIN_Y: &str = "win_y";
 const SETTING_STAY_ON_TOP: &str = "brain_stay_on_top";
+const SETTING_TRASH_RETENTION_DAYS: &str = "trash_retention_days";
+const SETTING_RESTORE_SESSION: &str = "restore_session";
+
+/// Days a tangle stays in the trash before it is purged (0 keeps it forever).
+const DEFAULT_TRASH_RETENTION_DAYS: i64 = 30;
+/// How often expired tangles are purged from the trash while the app runs.
+const TRASH_PURGE_INTERVAL_SECS: u32 = 60 * 60;
 
 fn main() -> glib::ExitCode {
-    let app = Application::builder().application_id(APP_ID).build();
+    let app = Application::builder()
+        .application_id(APP_ID)
+        .flags(gio::ApplicationFlags::HANDLES_COMMAND_LINE)
+        .build();
+    add_command_line_options(&app);
     app.connect_activate(build_ui);
+    app.connect_command_line(handle_command_line);
     app.run()
 }
 
+/// Something a command line asks the running instance to do.
+enum CommandLineRequest {
+    Open(String),
+    New,
+    Search(String),
+    Map,
+}
+
+fn add_command_line_options(app: &Application) {
+    app.set_option_context_parameter_string(Some("[tangle://TITLE…]"));
+    app.add_main_option(
+        "open", glib::Char::from(b'o'), glib::OptionFlags::NONE, glib::OptionArg::String,
+        "Open the tangle with this title, creating it if needed", Some("TITLE"),
+    );
+    app.add_main_option(
+        "new", glib::Char::from(b'n'), glib::OptionFlags::NONE, glib::OptionArg::None,
+        "Create a new tangle", None,
+    );
+    app.add_main_option(
+        "search", glib::Char::from(b's'), glib::OptionFlags::NONE, glib::OptionArg::String,
+        "Search tangles for TERM", Some("TERM"),
+    );
+    app.add_main_option(
+        "map", glib::Char::from(b'm'), glib::OptionFlags::NONE, glib::OptionArg::None,
+        "Show the tangle map", None,
+    );
+}
+
+/// Runs in the primary instance for every launch; later launches forward
+/// their arguments here over D-Bus and exit.
+fn handle_command_line(app: &Application, cmdline: &gio::ApplicationCommandLine) -> glib::ExitCode {
+    let requests = match parse_command_line(cmdline) {
+        Ok(requests) => requests,
+        Err(message) => {
+            // Printed by this instance; a forwarded launch only gets the exit code
+            glib::g_printerr!("tangles: {}\n", message);
+            return glib::ExitCode::FAILURE;
+        }
+    };
+
+    // First launch builds the UI; a bare relaunch raises the brain icon
+    if requests.is_empty() || brain_window(app).is_none() {
+        app.activate();
+    }
+    for request in requests {
+        match request {
+            CommandLineRequest::Open(title) => app.activate_action("open-tangle", Some(&title.to_variant())),
+            CommandLineRequest::New => app.activate_action("new-note", None),
+            CommandLineRequest::Search(term) => app.activate_action("search-for", Some(&term.to_variant())),
+            CommandLineRequest::Map => app.activate_action("tangle-map", None),
+        }
+    }
+    glib::ExitCode::SUCCESS
+}
+
+fn parse_command_line(cmdline: &gio::ApplicationCommandLine) -> Result<Vec<CommandLineRequest>, String> {
+    let options = cmdline.options_dict();
+    let mut requests = Vec::new();
+    if let Ok(Some(title)) = options.lookup::<String>("open") {
+        requests.push(CommandLineRequest::Open(title));
+    }
+    if options.contains("new") {
+        requests.push(CommandLineRequest::New);
+    }
+    if let Ok(Some(term)) = options.lookup::<String>("search") {
+        requests.push(CommandLineRequest::Search(term));
+    }
+    if options.contains("map") {
+        requests.push(CommandLineRequest::Map);
+    }
+    for arg in cmdline.arguments().iter().skip(1) {
+        let arg = arg.to_string_lossy();
+        match rich_editor::parse_tangle_uri(&arg) {
+            Some(title) => requests.push(CommandLineRequest::Open(title)),
+            None => return Err(format!("unexpected argument '{}' (see --help)", arg)),
+        }
+    }
+    Ok(requests)
+}
+
+/// The floating brain icon window, once `build_ui` has run.
+fn brain_window(app: &Application) -> Option<Window> {
+    app.windows().into_iter().find(|w| w.has_css_class("brain-window"))
+}
+
 fn build_ui(app: &Application) {
+    if let Some(brain) = brain_window(app) {
+        brain.present();
+        return;
+    }
+
     install_app_icon();
     load_css();
 
     // Initialize database
-    let data_dir = dirs::data_dir()
-        .expect("Could not determine data directory")
-        .join("tangles");
-    std::fs::create_dir_all(&data_dir).expect("Failed to create data directory");
-    let db_path = data_dir.join("tangles.db");
+    let db_path = database::default_db_path().expect("Could not determine data directory");
+    if let Some(data_dir) = db_path.parent() {
+        std::fs::create_dir_all(data_dir).expect("Failed to create data directory");
+    }
     let db = database::Database::new(&db_path).expect("Failed to initialize database");
 
+    // Purge tangles that have outlived the trash retention period, now and
+    // periodically for as long as the app runs
+    purge_expired_trash(&db, |_| {});
+    let db_purge = db.clone();
+    glib::timeout_add_seconds_local(TRASH_PURGE_INTERVAL_SECS, move || {
+        purge_expired_trash(&db_purge, |_| {});
+        glib::ControlFlow::Continue
+    });
+
     // Load saved settings
     let icon_size: i32 = db
         .get_setting(SETTING_ICON_SIZE)
@@ -181,6 +302,7 @@ fn build_ui(app: &Application) {
 
     // Register app actions
     register_actions(app, &window, &db);
+    dbus_service::register(app, &db);
 
     window.set_child(Some(&icon_box));
 
@@ -204,12 +326,21 @@ fn build_ui(app: &Application) {
 
     window.present();
 
-    // Restore previously-open notes
+    // Restore the tangles that were open at quit (unless turned off)
     let app_restore = app.clone();
     let db_restore = db.clone();
+    let restore_session = restore_session_enabled(&db);
     glib::idle_add_local_once(move || {
+        if !restore_session {
+            return;
+        }
         if let Ok(notes) = db_restore.get_visible_notes() {
             for note in notes {
+                // Skip tangles a command line already opened
+                let class = format!("note-{}", note.id.unwrap_or_default());
+                if app_restore.windows().iter().any(|w| w.has_css_class(&class)) {
+                    continue;
+                }
                 let nw = note_window::NoteWindow::new(&app_restore, db_restore.clone(), Some(note));
                 nw.present();
             }
@@ -346,6 +477,51 @@ fn install_app_icon() {
     gtk4::Window::set_default_icon_name("tangles");
 }
 
+/// Make this binary the handler for `tangle://` links system-wide (xdg-open,
+/// browsers, terminals): install a desktop entry for it into the user's
+/// applications folder and set it as the default for the scheme. Run from
+/// the menu; packages ship `assets/tangles.desktop` instead.
+fn register_uri_handler() -> Result<(), String> {
+    let data_dir = dirs::data_dir().ok_or("could not determine data directory")?;
+    let src = find_asset_path("tangles.desktop").ok_or("assets/tangles.desktop not found")?;
+    let template = std::fs::read_to_string(&src).map_err(|e| e.to_string())?;
+    let exec = std::env::current_exe()
+        .map(|p| desktop_exec_quote(&p.to_string_lossy()))
+        .unwrap_or_else(|_| "tangles".to_string());
+    let entry: String = template
+        .lines()
+        .map(|line| match line.strip_prefix("Exec=") {
+            Some(_) => format!("Exec={} %u\n", exec),
+            None => format!("{}\n", line),
+        })
+        .collect();
+
+    let apps_dir = data_dir.join("applications");
+    let dest = apps_dir.join("tangles.desktop");
+    std::fs::create_dir_all(&apps_dir)
+        .and_then(|_| std::fs::write(&dest, entry))
+        .map_err(|e| format!("{}: {}", dest.display(), e))?;
+    let info = gio::DesktopAppInfo::from_filename(&dest)
+        .ok_or_else(|| format!("{} is not a valid desktop entry", dest.display()))?;
+    info.set_as_default_for_type("x-scheme-handler/tangle").map_err(|e| e.to_string())
+}
+
+/// Quote a path for a desktop entry `Exec` key if it needs it.
+fn desktop_exec_quote(path: &str) -> String {
+    if path.chars().all(|c| c.is_alphanumeric() || "/-_.+".contains(c)) {
+        return path.to_string();
+    }
+    let mut quoted = String::from("\"");
+    for c in path.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
 fn load_css() {
     let provider = gtk4::CssProvider::new();
     if let Some(css_path) = find_asset_path("style.css") {
@@ -452,10 +628,21 @@ fn build_menu_model() -> gio::Menu {
     browse_section.append(Some("Search Tangles..."), Some("app.search-notes"));
     browse_section.append(Some("All Tangles..."), Some("app.all-notes"));
     browse_section.append(Some("Tangle Map..."), Some("app.tangle-map"));
+    browse_section.append(Some("Analyse Links..."), Some("app.analyse-links"));
+    browse_section.append(Some("Trash..."), Some("app.trash"));
     menu.append_section(None, &browse_section);
 
+    let export_section = gio::Menu::new();
+    export_section.append(Some("Import Markdown Folder..."), Some("app.import-markdown"));
+    export_section.append(Some("Import Tomboy/Gnote Notes..."), Some("app.import-tomboy"));
+    export_section.append(Some("Export to Markdown..."), Some("app.export-markdown"));
+    export_section.append(Some("Export Link Graph..."), Some("app.export-graph"));
+    menu.append_section(None, &export_section);
+
     let prefs_section = gio::Menu::new();
     prefs_section.append(Some("Stay on Top"), Some("app.stay-on-top"));
+    prefs_section.append(Some("Reopen Tangles on Start"), Some("app.restore-session"));
+    prefs_section.append(Some("Open tangle:// Links with Tangles"), Some("app.register-uri-handler"));
     prefs_section.append(Some("Theme Settings..."), Some("app.theme-settings"));
     menu.append_section(None, &prefs_section);
 
@@ -483,7 +670,7 @@ fn register_actions(app: &Application, window: &ApplicationWindow, db: &database
     let db_clone = db.clone();
     let win_clone = window.clone();
     recent_action.connect_activate(move |_, _| {
-        show_note_list_dialog(&app_clone, &win_clone, &db_clone, NoteListMode::Recent);
+        show_note_list_dialog(&app_clone, &win_clone, &db_clone, NoteListMode::Recent, None);
     });
     app.add_action(&recent_action);
 
@@ -493,17 +680,41 @@ fn register_actions(app: &Application, window: &ApplicationWindow, db: &database
     let db_clone = db.clone();
     let win_clone = window.clone();
     search_action.connect_activate(move |_, _| {
-        show_note_list_dialog(&app_clone, &win_clone, &db_clone, NoteListMode::Search);
+        show_note_list_dialog(&app_clone, &win_clone, &db_clone, NoteListMode::Search, None);
     });
     app.add_action(&search_action);
 
+    // Search with a preset query (command line)
+    let search_for_action = gio::SimpleAction::new("search-for", Some(glib::VariantTy::STRING));
+    let app_clone = app.clone();
+    let db_clone = db.clone();
+    let win_clone = window.clone();
+    search_for_action.connect_activate(move |_, param| {
+        let query = param.and_then(|p| p.get::<String>());
+        show_note_list_dialog(&app_clone, &win_clone, &db_clone, NoteListMode::Search, query.as_deref());
+    });
+    app.add_action(&search_for_action);
+
+    // Open a tangle by title, creating it if missing (command line, tangle:// links)
+    let open_tangle_action = gio::SimpleAction::new("open-tangle", Some(glib::VariantTy::STRING));
+    let app_clone = app.clone();
+    let db_clone = db.clone();
+    open_tangle_action.connect_activate(move |_, param| {
+        let Some(title) = param.and_then(|p| p.get::<String>()) else { return };
+        let title = title.trim();
+        if !title.is_empty() {
+            rich_editor::open_tangle_note(&db_clone, &app_clone, title);
+        }
+    });
+    app.add_action(&open_tangle_action);
+
     // All Notes
     let all_notes_action = gio::SimpleAction::new("all-notes", None);
     let app_clone = app.clone();
     let db_clone = db.clone();
     let win_clone = window.clone();
     all_notes_action.connect_activate(move |_, _| {
-        show_note_list_dialog(&app_clone, &win_clone, &db_clone, NoteListMode::All);
+        show_note_list_dialog(&app_clone, &win_clone, &db_clone, NoteListMode::All, None);
     });
     app.add_action(&all_notes_action);
 
@@ -529,6 +740,30 @@ fn register_actions(app: &Application, window: &ApplicationWindow, db: &database
     });
     app.add_action(&stay_on_top_action);
 
+    // Reopen the previous session's tangles on start
+    let restore_session_action = gio::SimpleAction::new_stateful(
+        "restore-session",
+        None,
+        &restore_session_enabled(db).to_variant(),
+    );
+    let db_for_restore = db.clone();
+    restore_session_action.connect_activate(move |action, _| {
+        let current = action.state().and_then(|v| v.get::<bool>()).unwrap_or(true);
+        let new_val = !current;
+        action.set_state(&new_val.to_variant());
+        let _ = db_for_restore.set_setting(SETTING_RESTORE_SESSION, if new_val { "true" } else { "false" });
+    });
+    app.add_action(&restore_session_action);
+
+    // Register as the system handler for tangle:// links
+    let register_uri_action = gio::SimpleAction::new("register-uri-handler", None);
+    register_uri_action.connect_activate(|_, _| {
+        if let Err(e) = register_uri_handler() {
+            eprintln!("Error registering tangle:// handler: {}", e);
+        }
+    });
+    app.add_action(&register_uri_action);
+
     // Theme Settings (global theme editor)
     let theme_settings_action = gio::SimpleAction::new("theme-settings", None);
     let db_for_theme = db.clone();
@@ -548,15 +783,91 @@ fn register_actions(app: &Application, window: &ApplicationWindow, db: &database
     let db_for_map = db.clone();
     let win_for_map = window.clone();
     tangle_map_action.connect_activate(move |_, _| {
-        crate::tangle_map::show_tangle_map(&app_for_map, &win_for_map, &db_for_map);
+        crate::tangle_map::show_tangle_map(&app_for_map, &win_for_map, &db_for_map, None, &[]);
     });
     app.add_action(&tangle_map_action);
 
+    // Link analysis: hubs, orphans, broken links, clusters, paths
+    let analyse_action = gio::SimpleAction::new("analyse-links", None);
+    let app_for_analysis = app.clone();
+    let db_for_analysis = db.clone();
+    let win_for_analysis = window.clone();
+    analyse_action.connect_activate(move |_, _| {
+        crate::analysis_window::show_analysis_dialog(&app_for_analysis, &win_for_analysis, &db_for_analysis);
+    });
+    app.add_action(&analyse_action);
+
+    // Markdown import
+    let import_md_action = gio::SimpleAction::new("import-markdown", None);
+    let db_for_import = db.clone();
+    let win_for_import = window.clone();
+    import_md_action.connect_activate(move |_, _| {
+        crate::dialogs::show_import_dialog(
+            &win_for_import,
+            &db_for_import,
+            "Import Markdown Folder",
+            "Every .md file becomes a tangle named after the file. [[Wikilinks]] and links to other .md files become tangle links.",
+            None,
+            crate::markdown::import_folder,
+        );
+    });
+    app.add_action(&import_md_action);
+
+    // Tomboy / Gnote import
+    let import_tomboy_action = gio::SimpleAction::new("import-tomboy", None);
+    let db_for_tomboy = db.clone();
+    let win_for_tomboy = window.clone();
+    import_tomboy_action.connect_activate(move |_, _| {
+        crate::dialogs::show_import_dialog(
+            &win_for_tomboy,
+            &db_for_tomboy,
+            "Import Tomboy/Gnote Notes",
+            "Choose a Tomboy or Gnote notes folder. Each .note file becomes a tangle with its formatting, internal links and dates.",
+            crate::tomboy::default_notes_dir(),
+            crate::tomboy::import_notes_dir,
+        );
+    });
+    app.add_action(&import_tomboy_action);
+
+    // Markdown export (all tangles)
+    let export_md_action = gio::SimpleAction::new("export-markdown", None);
+    let db_for_export = db.clone();
+    let win_for_export = window.clone();
+    export_md_action.connect_activate(move |_, _| {
+        crate::dialogs::show_markdown_export_dialog(&win_for_export, &db_for_export, None);
+    });
+    app.add_action(&export_md_action);
+
+    // Link graph export (DOT, GraphML, JSON)
+    let export_graph_action = gio::SimpleAction::new("export-graph", None);
+    let db_for_graph = db.clone();
+    let win_for_graph = window.clone();
+    export_graph_action.connect_activate(move |_, _| {
+        crate::dialogs::show_link_graph_export_dialog(&win_for_graph, &db_for_graph);
+    });
+    app.add_action(&export_graph_action);
+
+    // Trash
+    let trash_action = gio::SimpleAction::new("trash", None);
+    let db_for_trash = db.clone();
+    let win_for_trash = window.clone();
+    trash_action.connect_activate(move |_, _| {
+        show_trash_dialog(&win_for_trash, &db_for_trash);
+    });
+    app.add_action(&trash_action);
+
     // Quit
     let quit_action = gio::SimpleAction::new("quit", None);
     let app_clone = app.clone();
     quit_action.connect_activate(move |_, _| {
         note_window::set_app_quitting(true);
+        // Close note windows first so each one saves its geometry and stays
+        // marked visible for the next session
+        for win in app_clone.windows() {
+            if win.has_css_class("note-window") {
+                win.close();
+            }
+        }
         app_clone.quit();
     });
     app.add_action(&quit_action);
@@ -574,6 +885,7 @@ fn show_note_list_dialog(
     parent: &ApplicationWindow,
     db: &database::Database,
     mode: NoteListMode,
+    initial_query: Option<&str>,
 ) {
     let dialog = Window::builder()
         .title(match mode {
@@ -600,6 +912,7 @@ fn show_note_list_dialog(
 
     let search_entry = Entry::builder()
         .placeholder_text("Search...")
+        .tooltip_text("Words, \"exact phrase\", prefix*, -exclude, OR")
         .margin_bottom(4)
         .css_classes(["note-list-search"])
         .build();
@@ -666,7 +979,7 @@ fn show_note_list_dialog(
         glib::timeout_add_local(std::time::Duration::from_millis(30), move || {
             match rx.try_recv() {
                 Ok(notes) => {
-                    populate_note_list(&list_box_init, &notes, &db_for_pop);
+                    populate_note_list(&list_box_init, &notes, &HashMap::new(), &db_for_pop);
                     glib::ControlFlow::Break
                 }
                 Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
@@ -695,22 +1008,27 @@ fn show_note_list_dialog(
                 let db_pop = db.clone();
                 let lb = list_box.clone();
                 let q = query.clone();
-                let (tx, rx) = std::sync::mpsc::channel::<Vec<database::Note>>();
+                let (tx, rx) = std::sync::mpsc::channel::<(Vec<database::Note>, HashMap<i64, String>)>();
                 std::thread::spawn(move || {
                     let results = if q.is_empty() {
-                        match mode {
+                        let notes = match mode {
                             NoteListMode::Recent => db_bg.get_recent_notes(10).unwrap_or_default(),
                             _ => db_bg.get_all_notes().unwrap_or_default(),
-                        }
+                        };
+                        (notes, HashMap::new())
                     } else {
-                        db_bg.search_notes(&q).unwrap_or_default()
+                        let hits = db_bg.search_notes_ranked(&q).unwrap_or_default();
+                        let snippets = hits.iter()
+                            .filter_map(|h| Some((h.note.id?, h.snippet.clone())))
+                            .collect();
+                        (hits.into_iter().map(|h| h.note).collect(), snippets)
                     };
                     let _ = tx.send(results);
                 });
                 glib::timeout_add_local(std::time::Duration::from_millis(30), move || {
                     match rx.try_recv() {
-                        Ok(results) => {
-                            populate_note_list(&lb, &results, &db_pop);
+                        Ok((results, snippets)) => {
+                            populate_note_list(&lb, &results, &snippets, &db_pop);
                             glib::ControlFlow::Break
                         }
                         Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
@@ -722,9 +1040,21 @@ fn show_note_list_dialog(
         );
         *search_timer.borrow_mut() = Some(source_id);
     });
+
+    if let Some(query) = initial_query {
+        search_entry.set_text(query);
+        search_entry.set_position(-1);
+    }
 }
 
-fn populate_note_list(list_box: &ListBox, notes: &[database::Note], db: &database::Database) {
+/// Fill the note list. `snippets` maps note ids to FTS excerpts shown in place
+/// of the plain preview (search results only).
+fn populate_note_list(
+    list_box: &ListBox,
+    notes: &[database::Note],
+    snippets: &HashMap<i64, String>,
+    db: &database::Database,
+) {
     while let Some(child) = list_box.first_child() {
         list_box.remove(&child);
     }
@@ -737,6 +1067,7 @@ fn populate_note_list(list_box: &ListBox, notes: &[database::Note], db: &databas
         b_starred.cmp(&a_starred)
     });
     let notes = sorted;
+    let accent = theme::global_accent(db);
 
     if notes.is_empty() {
         let empty = Label::builder()
@@ -781,8 +1112,7 @@ fn populate_note_list(list_box: &ListBox, notes: &[database::Note], db: &databas
             .css_classes(["note-row-title"])
             .build();
 
-        let preview_text = note
-            .content
+        let preview_text = database::html_to_plain_text(&note.content)
             .chars()
             .take(80)
             .collect::<String>()
@@ -793,6 +1123,11 @@ fn populate_note_list(list_box: &ListBox, notes: &[database::Note], db: &databas
             .css_classes(["note-row-preview"])
             .ellipsize(gtk4::pango::EllipsizeMode::End)
             .build();
+        if let Some(snippet) = note.id.and_then(|id| snippets.get(&id)) {
+            if !snippet.trim().is_empty() {
+                preview.set_markup(&theme::highlight_markup(snippet, &accent));
+            }
+        }
 
         let timestamp = format_timestamp(&note.updated_at);
         let time_label = Label::builder()
@@ -809,7 +1144,7 @@ fn populate_note_list(list_box: &ListBox, notes: &[database::Note], db: &databas
         if let Some(note_id) = note.id {
             let delete_btn = Button::builder()
                 .label("x")
-                .tooltip_text("Delete note")
+                .tooltip_text("Move to trash")
                 .css_classes(["note-delete-button"])
                 .valign(gtk4::Align::Center)
                 .build();
@@ -821,6 +1156,13 @@ fn populate_note_list(list_box: &ListBox, notes: &[database::Note], db: &databas
                     eprintln!("Error deleting note: {}", e);
                     return;
                 }
+                // A trashed tangle shouldn't stay open
+                if let Some(app) = btn.root()
+                    .and_then(|r| r.downcast::<Window>().ok())
+                    .and_then(|w| w.application())
+                {
+                    close_note_window(&app, note_id);
+                }
                 if let Some(row) = btn.ancestor(ListBoxRow::static_type()) {
                     let row = row.downcast::<ListBoxRow>().unwrap();
                     list_box_ref.remove(&row);
@@ -839,6 +1181,279 @@ fn populate_note_list(list_box: &ListBox, notes: &[database::Note], db: &databas
     }
 }
 
+fn restore_session_enabled(db: &database::Database) -> bool {
+    db.get_setting(SETTING_RESTORE_SESSION).map(|v| v != "false").unwrap_or(true)
+}
+
+fn trash_retention_days(db: &database::Database) -> i64 {
+    db.get_setting(SETTING_TRASH_RETENTION_DAYS)
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS)
+}
+
+/// Purge the tangles that have outlived the retention period on a background
+/// thread, then call `done` with how many went.
+fn purge_expired_trash(db: &database::Database, done: impl FnOnce(usize) + 'static) {
+    let retention_days = trash_retention_days(db);
+    if retention_days <= 0 {
+        return;
+    }
+    let db = db.clone();
+    let (tx, rx) = std::sync::mpsc::channel::<usize>();
+    std::thread::spawn(move || {
+        match db.purge_expired_trash(retention_days) {
+            Ok(purged) => { let _ = tx.send(purged); }
+            Err(e) => eprintln!("Error purging trash: {}", e),
+        }
+    });
+    let mut done = Some(done);
+    glib::timeout_add_local(std::time::Duration::from_millis(50), move || {
+        match rx.try_recv() {
+            Ok(purged) => {
+                if let Some(done) = done.take() {
+                    done(purged);
+                }
+                glib::ControlFlow::Break
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+            Err(_) => glib::ControlFlow::Break,
+        }
+    });
+}
+
+/// Close the open window for a note, if any.
+fn close_note_window(app: &Application, note_id: i64) {
+    let target_class = format!("note-{}", note_id);
+    for win in app.windows() {
+        if win.has_css_class("note-window") && win.has_css_class(&target_class) {
+            win.close();
+        }
+    }
+}
+
+/// Trash dialog: restore or permanently delete trashed tangles, empty the
+/// trash, and set how long tangles are kept before being purged automatically.
+fn show_trash_dialog(parent: &ApplicationWindow, db: &database::Database) {
+    let dialog = Window::builder()
+        .title("Trash")
+        .default_width(420)
+        .default_height(480)
+        .transient_for(parent)
+        .modal(false)
+        .build();
+    dialog.add_css_class("note-list-dialog");
+
+    let vbox = Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(6)
+        .margin_top(8)
+        .margin_bottom(8)
+        .margin_start(8)
+        .margin_end(8)
+        .build();
+
+    let list_box = ListBox::builder()
+        .selection_mode(gtk4::SelectionMode::None)
+        .build();
+    list_box.add_css_class("boxed-list");
+
+    let scrolled = ScrolledWindow::builder()
+        .child(&list_box)
+        .vexpand(true)
+        .hexpand(true)
+        .min_content_height(300)
+        .build();
+
+    // Retention setting + Empty Trash
+    let bottom = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .build();
+    let retention_label = Label::builder()
+        .label("Purge after (days, 0 = never):")
+        .css_classes(["dim-label"])
+        .build();
+    let retention_spin = gtk4::SpinButton::with_range(0.0, 3650.0, 1.0);
+    retention_spin.set_value(trash_retention_days(db) as f64);
+    // Apply a new retention period once the spin button settles
+    let db_for_retention = db.clone();
+    let list_for_retention = list_box.clone();
+    let retention_timer: std::rc::Rc<std::cell::RefCell<Option<glib::SourceId>>> =
+        std::rc::Rc::new(std::cell::RefCell::new(None));
+    retention_spin.connect_value_changed(move |spin| {
+        let _ = db_for_retention.set_setting(
+            SETTING_TRASH_RETENTION_DAYS,
+            &(spin.value() as i64).to_string(),
+        );
+        if let Some(id) = retention_timer.borrow_mut().take() {
+            id.remove();
+        }
+        let db = db_for_retention.clone();
+        let list_box = list_for_retention.clone();
+        let timer_ref = retention_timer.clone();
+        let source_id = glib::timeout_add_local_once(std::time::Duration::from_secs(1), move || {
+            timer_ref.borrow_mut().take();
+            let db_reload = db.clone();
+            purge_expired_trash(&db, move |purged| {
+                if purged > 0 {
+                    load_trash_list(&list_box, &db_reload);
+                }
+            });
+        });
+        *retention_timer.borrow_mut() = Some(source_id);
+    });
+    let spacer = Box::builder().hexpand(true).build();
+    let empty_btn = Button::builder()
+        .label("Empty Trash")
+        .css_classes(["close-button"])
+        .build();
+    bottom.append(&retention_label);
+    bottom.append(&retention_spin);
+    bottom.append(&spacer);
+    bottom.append(&empty_btn);
+
+    vbox.append(&scrolled);
+    vbox.append(&bottom);
+    dialog.set_child(Some(&vbox));
+    dialog.present();
+
+    let db_for_empty = db.clone();
+    let list_for_empty = list_box.clone();
+    empty_btn.connect_clicked(move |_| {
+        if let Err(e) = db_for_empty.empty_trash() {
+            eprintln!("Error emptying trash: {}", e);
+        }
+        populate_trash_list(&list_for_empty, &[], &db_for_empty);
+    });
+
+    load_trash_list(&list_box, db);
+}
+
+/// Fill the trash list from the database, read on a background thread.
+fn load_trash_list(list_box: &ListBox, db: &database::Database) {
+    let db_init = db.clone();
+    let db_pop = db.clone();
+    let list_box = list_box.clone();
+    let (tx, rx) = std::sync::mpsc::channel::<Vec<database::TrashedNote>>();
+    std::thread::spawn(move || {
+        let _ = tx.send(db_init.get_trashed_notes().unwrap_or_default());
+    });
+    glib::timeout_add_local(std::time::Duration::from_millis(30), move || {
+        match rx.try_recv() {
+            Ok(trashed) => {
+                populate_trash_list(&list_box, &trashed, &db_pop);
+                glib::ControlFlow::Break
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+            Err(_) => glib::ControlFlow::Break,
+        }
+    });
+}
+
+fn populate_trash_list(list_box: &ListBox, trashed: &[database::TrashedNote], db: &database::Database) {
+    while let Some(child) = list_box.first_child() {
+        list_box.remove(&child);
+    }
+
+    if trashed.is_empty() {
+        let empty = Label::builder()
+            .label("Trash is empty")
+            .css_classes(["dim-label"])
+            .margin_top(20)
+            .margin_bottom(20)
+            .build();
+        let row = ListBoxRow::new();
+        row.set_child(Some(&empty));
+        row.set_activatable(false);
+        list_box.append(&row);
+        return;
+    }
+
+    for item in trashed {
+        let Some(note_id) = item.note.id else { continue };
+        let outer_box = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(8)
+            .css_classes(["note-row"])
+            .build();
+
+        let info_box = Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(2)
+            .hexpand(true)
+            .build();
+        info_box.append(&Label::builder()
+            .label(&item.note.title)
+            .xalign(0.0)
+            .css_classes(["note-row-title"])
+            .build());
+        let preview_text = database::html_to_plain_text(&item.note.content)
+            .chars()
+            .take(80)
+            .collect::<String>()
+            .replace('\n', " ");
+        info_box.append(&Label::builder()
+            .label(&preview_text)
+            .xalign(0.0)
+            .css_classes(["note-row-preview"])
+            .ellipsize(gtk4::pango::EllipsizeMode::End)
+            .build());
+        info_box.append(&Label::builder()
+            .label(format!("Deleted {}", format_timestamp(&item.deleted_at)))
+            .xalign(0.0)
+            .css_classes(["note-row-timestamp"])
+            .build());
+        outer_box.append(&info_box);
+
+        let restore_btn = Button::builder()
+            .label("\u{21ba}")
+            .tooltip_text("Restore")
+            .css_classes(["note-delete-button"])
+            .valign(gtk4::Align::Center)
+            .build();
+        let purge_btn = Button::builder()
+            .label("x")
+            .tooltip_text("Delete forever")
+            .css_classes(["note-delete-button"])
+            .valign(gtk4::Align::Center)
+            .build();
+
+        let list_box_ref = list_box.clone();
+        let db_for_restore = db.clone();
+        restore_btn.connect_clicked(move |btn| {
+            if let Err(e) = db_for_restore.restore_note(note_id) {
+                eprintln!("Error restoring note: {}", e);
+                return;
+            }
+            if let Some(row) = btn.ancestor(ListBoxRow::static_type()) {
+                let row = row.downcast::<ListBoxRow>().unwrap();
+                list_box_ref.remove(&row);
+            }
+        });
+
+        let list_box_ref = list_box.clone();
+        let db_for_purge = db.clone();
+        purge_btn.connect_clicked(move |btn| {
+            if let Err(e) = db_for_purge.purge_note(note_id) {
+                eprintln!("Error deleting note: {}", e);
+                return;
+            }
+            if let Some(row) = btn.ancestor(ListBoxRow::static_type()) {
+                let row = row.downcast::<ListBoxRow>().unwrap();
+                list_box_ref.remove(&row);
+            }
+        });
+
+        outer_box.append(&restore_btn);
+        outer_box.append(&purge_btn);
+
+        let row = ListBoxRow::new();
+        row.set_child(Some(&outer_box));
+        row.set_activatable(false);
+        list_box.append(&row);
+    }
+}
+
 fn format_timestamp(rfc3339: &str) -> String {
     if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(rfc3339) {
         dt.format("%b %d, %Y  %H:%M").to_string()
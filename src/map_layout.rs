@@ -0,0 +1,416 @@
+//! Force-directed layout for the tangle map.
+//!
+//! Fruchterman-Reingold forces with Barnes-Hut (quadtree) approximated
+//! repulsion, so a step costs O(n log n) instead of O(n²). `LayoutWorker`
+//! runs the simulation on its own thread and streams position snapshots
+//! back to the GTK main loop.
+
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::time::{Duration, Instant};
+
+/// Opening angle for Barnes-Hut: cells smaller than `THETA × distance`
+/// are treated as a single mass. Higher is faster and less exact.
+const THETA: f64 = 0.9;
+/// Quadtree depth at which coincident nodes share a leaf instead of splitting forever.
+const MAX_DEPTH: usize = 24;
+/// Per-step temperature decay; the layout settles below `MIN_TEMPERATURE`.
+const COOLING: f64 = 0.97;
+const MIN_TEMPERATURE: f64 = 0.5;
+/// Pull toward the centre so disconnected groups don't drift away.
+const GRAVITY: f64 = 0.05;
+/// Minimum time between snapshots sent to the UI, and per step, so small
+/// maps still visibly animate.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(30);
+const MIN_STEP_TIME: Duration = Duration::from_millis(8);
+
+/// Map centre used when laying out a fresh map.
+pub const CENTER: (f64, f64) = (400.0, 300.0);
+
+/// Area the layout spreads `n` nodes over: at least the default map
+/// canvas, growing with the node count so large maps don't collapse.
+fn layout_area(n: usize) -> f64 {
+    (800.0 * 600.0_f64).max(n as f64 * 150.0 * 60.0)
+}
+
+/// Deterministic starting point for node `idx` of `n` without a saved
+/// position: a sunflower spiral around `CENTER` sized to the layout area.
+pub fn initial_position(idx: usize, n: usize) -> (f64, f64) {
+    let radius = layout_area(n).sqrt() / 2.0;
+    let r = radius * ((idx as f64 + 0.5) / n.max(1) as f64).sqrt();
+    let angle = idx as f64 * 2.399_963_229_728_653; // golden angle
+    (CENTER.0 + r * angle.cos(), CENTER.1 + r * angle.sin())
+}
+
+pub struct ForceLayout {
+    positions: Vec<(f64, f64)>,
+    /// Nodes that exert force but never move (saved map positions)
+    fixed: Vec<bool>,
+    /// Nodes held by the user while dragging
+    pinned: Vec<bool>,
+    edges: Vec<(usize, usize)>,
+    k: f64,
+    temperature: f64,
+    initial_temperature: f64,
+}
+
+impl ForceLayout {
+    pub fn new(positions: Vec<(f64, f64)>, fixed: Vec<bool>, edges: Vec<(usize, usize)>) -> Self {
+        let n = positions.len();
+        let area = layout_area(n);
+        let initial_temperature = area.sqrt() / 10.0;
+        ForceLayout {
+            pinned: vec![false; n],
+            fixed,
+            positions,
+            edges,
+            k: (area / n.max(1) as f64).sqrt(),
+            temperature: initial_temperature,
+            initial_temperature,
+        }
+    }
+
+    pub fn positions(&self) -> &[(f64, f64)] {
+        &self.positions
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.temperature < MIN_TEMPERATURE || self.positions.len() < 2
+    }
+
+    /// Hold node `idx` at `(x, y)` and let its neighbours react.
+    pub fn pin(&mut self, idx: usize, x: f64, y: f64) {
+        if idx < self.positions.len() {
+            self.positions[idx] = (x, y);
+            self.pinned[idx] = true;
+            self.reheat(0.15);
+        }
+    }
+
+    pub fn release(&mut self, idx: usize) {
+        if idx < self.pinned.len() {
+            self.pinned[idx] = false;
+            self.reheat(0.15);
+        }
+    }
+
+    pub fn add_edge(&mut self, source: usize, target: usize) {
+        if source < self.positions.len() && target < self.positions.len() {
+            self.edges.push((source, target));
+            self.reheat(0.3);
+        }
+    }
+
+    pub fn remove_edge(&mut self, source: usize, target: usize) {
+        if let Some(pos) = self.edges.iter().position(|&e| e == (source, target)) {
+            self.edges.swap_remove(pos);
+            self.reheat(0.3);
+        }
+    }
+
+    /// Raise the temperature to at least `fraction` of the starting value.
+    pub fn reheat(&mut self, fraction: f64) {
+        self.temperature = self.temperature.max(self.initial_temperature * fraction);
+    }
+
+    /// One simulation step: repulsion between all nodes (approximated),
+    /// attraction along edges, weak gravity, moves capped by the temperature.
+    pub fn step(&mut self) {
+        let n = self.positions.len();
+        if n < 2 {
+            return;
+        }
+        let k2 = self.k * self.k;
+        let tree = QuadTree::build(&self.positions);
+        let mut disp: Vec<(f64, f64)> = (0..n)
+            .map(|i| {
+                if self.is_movable(i) {
+                    tree.repulsion(&self.positions, i, k2)
+                } else {
+                    (0.0, 0.0)
+                }
+            })
+            .collect();
+
+        for &(s, t) in &self.edges {
+            if s == t || s >= n || t >= n {
+                continue;
+            }
+            let dx = self.positions[s].0 - self.positions[t].0;
+            let dy = self.positions[s].1 - self.positions[t].1;
+            let dist = (dx * dx + dy * dy).sqrt().max(1.0);
+            let force = dist * dist / self.k;
+            let (fx, fy) = (dx / dist * force, dy / dist * force);
+            disp[s].0 -= fx;
+            disp[s].1 -= fy;
+            disp[t].0 += fx;
+            disp[t].1 += fy;
+        }
+
+        for (i, d) in disp.iter().enumerate() {
+            if !self.is_movable(i) {
+                continue;
+            }
+            let (x, y) = self.positions[i];
+            let dx = d.0 - (x - CENTER.0) * GRAVITY;
+            let dy = d.1 - (y - CENTER.1) * GRAVITY;
+            let mag = (dx * dx + dy * dy).sqrt();
+            if mag > 0.0 {
+                let step = mag.min(self.temperature);
+                self.positions[i] = (x + dx / mag * step, y + dy / mag * step);
+            }
+        }
+        self.temperature *= COOLING;
+    }
+
+    fn is_movable(&self, idx: usize) -> bool {
+        !self.fixed[idx] && !self.pinned[idx]
+    }
+}
+
+// ── Barnes-Hut quadtree ────────────────────────────────────────────
+
+struct Cell {
+    cx: f64,
+    cy: f64,
+    half: f64,
+    mass: f64,
+    mass_x: f64,
+    mass_y: f64,
+    /// Indices of the four child cells, once split
+    children: Option<[usize; 4]>,
+    /// Bodies held by a leaf (more than one only at `MAX_DEPTH`)
+    bodies: Vec<usize>,
+}
+
+impl Cell {
+    fn new(cx: f64, cy: f64, half: f64) -> Self {
+        Cell { cx, cy, half, mass: 0.0, mass_x: 0.0, mass_y: 0.0, children: None, bodies: Vec::new() }
+    }
+
+    fn quadrant(&self, (x, y): (f64, f64)) -> usize {
+        (if x >= self.cx { 1 } else { 0 }) + (if y >= self.cy { 2 } else { 0 })
+    }
+}
+
+struct QuadTree {
+    cells: Vec<Cell>,
+}
+
+impl QuadTree {
+    fn build(positions: &[(f64, f64)]) -> Self {
+        let (mut min_x, mut min_y) = (f64::MAX, f64::MAX);
+        let (mut max_x, mut max_y) = (f64::MIN, f64::MIN);
+        for &(x, y) in positions {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+        let half = ((max_x - min_x).max(max_y - min_y) / 2.0).max(1.0) + 1.0;
+        let mut tree = QuadTree {
+            cells: vec![Cell::new((min_x + max_x) / 2.0, (min_y + max_y) / 2.0, half)],
+        };
+        for i in 0..positions.len() {
+            tree.insert(positions, i);
+        }
+        tree
+    }
+
+    fn insert(&mut self, positions: &[(f64, f64)], body: usize) {
+        let p = positions[body];
+        let mut cell = 0;
+        let mut depth = 0;
+        loop {
+            self.add_mass(cell, p);
+            if self.cells[cell].children.is_none() {
+                if self.cells[cell].bodies.is_empty() || depth >= MAX_DEPTH {
+                    self.cells[cell].bodies.push(body);
+                    return;
+                }
+                // Occupied leaf: split it and move the resident down a level
+                self.split(cell);
+                for resident in std::mem::take(&mut self.cells[cell].bodies) {
+                    let rp = positions[resident];
+                    let child = self.child_for(cell, rp);
+                    self.add_mass(child, rp);
+                    self.cells[child].bodies.push(resident);
+                }
+            }
+            cell = self.child_for(cell, p);
+            depth += 1;
+        }
+    }
+
+    fn add_mass(&mut self, cell: usize, (x, y): (f64, f64)) {
+        let c = &mut self.cells[cell];
+        c.mass += 1.0;
+        c.mass_x += x;
+        c.mass_y += y;
+    }
+
+    fn child_for(&self, cell: usize, p: (f64, f64)) -> usize {
+        let c = &self.cells[cell];
+        c.children.map_or(cell, |children| children[c.quadrant(p)])
+    }
+
+    fn split(&mut self, cell: usize) {
+        let (cx, cy, half) = (self.cells[cell].cx, self.cells[cell].cy, self.cells[cell].half / 2.0);
+        let first = self.cells.len();
+        self.cells.push(Cell::new(cx - half, cy - half, half));
+        self.cells.push(Cell::new(cx + half, cy - half, half));
+        self.cells.push(Cell::new(cx - half, cy + half, half));
+        self.cells.push(Cell::new(cx + half, cy + half, half));
+        self.cells[cell].children = Some([first, first + 1, first + 2, first + 3]);
+    }
+
+    /// Approximate repulsive displacement on `body` (force k²/d from every other node).
+    fn repulsion(&self, positions: &[(f64, f64)], body: usize, k2: f64) -> (f64, f64) {
+        let (x, y) = positions[body];
+        let (mut fx, mut fy) = (0.0, 0.0);
+        let mut push = |dx: f64, dy: f64, mass: f64| {
+            let d2 = (dx * dx + dy * dy).max(1.0);
+            let d = d2.sqrt();
+            let force = mass * k2 / d;
+            fx += dx / d * force;
+            fy += dy / d * force;
+        };
+        let mut stack = vec![0usize];
+        while let Some(idx) = stack.pop() {
+            let cell = &self.cells[idx];
+            if cell.mass == 0.0 {
+                continue;
+            }
+            match cell.children {
+                Some(children) => {
+                    let (mx, my) = (cell.mass_x / cell.mass, cell.mass_y / cell.mass);
+                    let (dx, dy) = (x - mx, y - my);
+                    let dist = (dx * dx + dy * dy).sqrt();
+                    let inside = (x - cell.cx).abs() <= cell.half && (y - cell.cy).abs() <= cell.half;
+                    if !inside && cell.half * 2.0 < THETA * dist {
+                        push(dx, dy, cell.mass);
+                    } else {
+                        stack.extend_from_slice(&children);
+                    }
+                }
+                None => {
+                    for &other in &cell.bodies {
+                        if other == body {
+                            continue;
+                        }
+                        let (ox, oy) = positions[other];
+                        let (mut dx, mut dy) = (x - ox, y - oy);
+                        if dx == 0.0 && dy == 0.0 {
+                            // Coincident nodes: separate them in a stable direction
+                            let angle = (body as f64 - other as f64) * 0.618;
+                            dx = angle.cos();
+                            dy = angle.sin();
+                        }
+                        push(dx, dy, 1.0);
+                    }
+                }
+            }
+        }
+        (fx, fy)
+    }
+}
+
+// ── Worker thread ──────────────────────────────────────────────────
+
+pub enum LayoutCommand {
+    Pin(usize, f64, f64),
+    Release(usize),
+    AddEdge(usize, usize),
+    RemoveEdge(usize, usize),
+}
+
+pub enum LayoutPoll {
+    /// Nothing new since the last poll
+    Pending,
+    /// Latest positions, indexed like the nodes given to the layout
+    Update(Vec<(f64, f64)>),
+    /// The worker has settled and exited
+    Finished,
+}
+
+/// A layout running on a background thread. Dropping the worker stops it.
+pub struct LayoutWorker {
+    commands: Sender<LayoutCommand>,
+    updates: Receiver<Vec<(f64, f64)>>,
+}
+
+impl LayoutWorker {
+    /// Start simulating. A `live` worker keeps running after it settles,
+    /// waking up whenever it receives a command; otherwise it exits once settled.
+    pub fn spawn(mut layout: ForceLayout, live: bool) -> Self {
+        let (cmd_tx, cmd_rx) = mpsc::channel::<LayoutCommand>();
+        let (pos_tx, pos_rx) = mpsc::channel::<Vec<(f64, f64)>>();
+        std::thread::spawn(move || {
+            let apply = |layout: &mut ForceLayout, cmd: LayoutCommand| match cmd {
+                LayoutCommand::Pin(i, x, y) => layout.pin(i, x, y),
+                LayoutCommand::Release(i) => layout.release(i),
+                LayoutCommand::AddEdge(s, t) => layout.add_edge(s, t),
+                LayoutCommand::RemoveEdge(s, t) => layout.remove_edge(s, t),
+            };
+            let mut last_sent = Instant::now();
+            let mut dirty = false;
+            loop {
+                loop {
+                    match cmd_rx.try_recv() {
+                        Ok(cmd) => apply(&mut layout, cmd),
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => return,
+                    }
+                }
+                if layout.is_settled() {
+                    if dirty && pos_tx.send(layout.positions().to_vec()).is_err() {
+                        return;
+                    }
+                    dirty = false;
+                    if !live {
+                        return;
+                    }
+                    match cmd_rx.recv() {
+                        Ok(cmd) => apply(&mut layout, cmd),
+                        Err(_) => return,
+                    }
+                    continue;
+                }
+
+                let started = Instant::now();
+                layout.step();
+                dirty = true;
+                if last_sent.elapsed() >= SNAPSHOT_INTERVAL {
+                    if pos_tx.send(layout.positions().to_vec()).is_err() {
+                        return;
+                    }
+                    last_sent = Instant::now();
+                    dirty = false;
+                }
+                if let Some(rest) = MIN_STEP_TIME.checked_sub(started.elapsed()) {
+                    std::thread::sleep(rest);
+                }
+            }
+        });
+        LayoutWorker { commands: cmd_tx, updates: pos_rx }
+    }
+
+    pub fn send(&self, command: LayoutCommand) {
+        let _ = self.commands.send(command);
+    }
+
+    /// Most recent snapshot since the last call (older ones are skipped).
+    pub fn poll(&self) -> LayoutPoll {
+        let mut latest = None;
+        loop {
+            match self.updates.try_recv() {
+                Ok(positions) => latest = Some(positions),
+                Err(TryRecvError::Empty) => {
+                    return latest.map_or(LayoutPoll::Pending, LayoutPoll::Update);
+                }
+                Err(TryRecvError::Disconnected) => {
+                    return latest.map_or(LayoutPoll::Finished, LayoutPoll::Update);
+                }
+            }
+        }
+    }
+}
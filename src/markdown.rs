@@ -0,0 +1,1251 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::database::{Database, Note};
+use crate::html::{escape_html, escape_html_attr, tokenize_html, HtmlToken};
+
+/// How `tangle://` links are written in exported Markdown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkStyle {
+    /// `[[Title]]` / `[[Title|text]]` (Obsidian, Logseq, ...)
+    Wiki,
+    /// `[text](Title.md)`
+    Relative,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub link_style: LinkStyle,
+    /// Copy referenced images into an `images/` folder next to the notes
+    pub copy_images: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct ExportReport {
+    pub notes: usize,
+    pub images: usize,
+    pub errors: Vec<String>,
+}
+
+/// Convert stored note HTML to CommonMark. Images keep their original paths
+/// and relative links point at `note_file_name(title)`.
+pub fn html_to_markdown(html: &str, link_style: LinkStyle) -> String {
+    convert(html, link_style, &|title| note_file_name(title), &mut |src| src.to_string())
+}
+
+/// File name (with `.md`) used for a note title: path separators and
+/// characters most file systems reject are replaced with `-`.
+pub fn note_file_name(title: &str) -> String {
+    let cleaned: String = title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c if c.is_control() => '-',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim().trim_end_matches('.').trim();
+    if cleaned.is_empty() {
+        "Untitled.md".to_string()
+    } else {
+        format!("{}.md", cleaned)
+    }
+}
+
+/// Write each note to `dir` as a Markdown file. Links between exported notes
+/// point at the files actually written (titles that map to the same file name
+/// get a numeric suffix).
+pub fn export_notes(notes: &[Note], dir: &Path, options: &ExportOptions) -> std::io::Result<ExportReport> {
+    std::fs::create_dir_all(dir)?;
+    let mut report = ExportReport::default();
+
+    let mut file_names: HashMap<String, String> = HashMap::new();
+    let mut used: std::collections::HashSet<String> = std::collections::HashSet::new();
+    for note in notes {
+        let base = note_file_name(&note.title);
+        let mut name = base.clone();
+        let mut n = 2;
+        while !used.insert(name.to_lowercase()) {
+            name = format!("{} ({}).md", base.trim_end_matches(".md"), n);
+            n += 1;
+        }
+        file_names.insert(note.title.clone(), name);
+    }
+
+    let images_dir = dir.join("images");
+    let mut copied: HashMap<String, String> = HashMap::new();
+    let mut image_names: std::collections::HashSet<String> = std::collections::HashSet::new();
+
+    for note in notes {
+        let mut image_for = |src: &str| -> String {
+            if !options.copy_images {
+                return src.to_string();
+            }
+            if let Some(rel) = copied.get(src) {
+                return rel.clone();
+            }
+            let source = Path::new(src);
+            let file_name = source
+                .file_name()
+                .map(|f| f.to_string_lossy().to_string())
+                .unwrap_or_else(|| "image".to_string());
+            let mut name = file_name.clone();
+            let mut n = 2;
+            while image_names.contains(&name) {
+                name = match file_name.rsplit_once('.') {
+                    Some((stem, ext)) => format!("{}-{}.{}", stem, n, ext),
+                    None => format!("{}-{}", file_name, n),
+                };
+                n += 1;
+            }
+            let result = std::fs::create_dir_all(&images_dir)
+                .and_then(|_| std::fs::copy(source, images_dir.join(&name)));
+            match result {
+                Ok(_) => {
+                    image_names.insert(name.clone());
+                    report.images += 1;
+                    let rel = format!("images/{}", name);
+                    copied.insert(src.to_string(), rel.clone());
+                    rel
+                }
+                Err(e) => {
+                    report.errors.push(format!("{}: could not copy image {}: {}", note.title, src, e));
+                    src.to_string()
+                }
+            }
+        };
+        let file_for = |title: &str| {
+            file_names.get(title).cloned().unwrap_or_else(|| note_file_name(title))
+        };
+        let markdown = convert(&note.content, options.link_style, &file_for, &mut image_for);
+        let path: PathBuf = dir.join(&file_names[&note.title]);
+        match std::fs::write(&path, markdown) {
+            Ok(()) => report.notes += 1,
+            Err(e) => report.errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+    Ok(report)
+}
+
+/// Export every tangle (trash excluded) to `dir`.
+pub fn export_database(db: &Database, dir: &Path, options: &ExportOptions) -> std::io::Result<ExportReport> {
+    let notes = db.get_all_notes().map_err(std::io::Error::other)?;
+    export_notes(&notes, dir, options)
+}
+
+// ── HTML → Markdown ────────────────────────────────────────────────
+
+#[derive(PartialEq)]
+enum Block {
+    None,
+    Paragraph,
+    Heading(usize),
+    ListItem,
+    Code,
+}
+
+struct MdWriter<'a> {
+    out: String,
+    line: String,
+    block: Block,
+    /// One entry per open list: None for bullets, Some(counter) for numbered
+    lists: Vec<Option<u32>>,
+    code_lines: Vec<String>,
+    /// Emphasis markers waiting for the first non-space character
+    pending_open: String,
+    /// Open inline tags and the marker that closes each (None if nothing to write)
+    inline_stack: Vec<(String, Option<String>)>,
+    /// Open anchors: (href, position in `line` where the link text starts)
+    links: Vec<(String, usize)>,
+    /// Position in `line` where an open inline `<code>` span's text starts
+    code_span: Option<usize>,
+    link_style: LinkStyle,
+    file_for: &'a dyn Fn(&str) -> String,
+    image_for: &'a mut dyn FnMut(&str) -> String,
+}
+
+fn convert(
+    html: &str,
+    link_style: LinkStyle,
+    file_for: &dyn Fn(&str) -> String,
+    image_for: &mut dyn FnMut(&str) -> String,
+) -> String {
+    let mut w = MdWriter {
+        out: String::new(),
+        line: String::new(),
+        block: Block::None,
+        lists: Vec::new(),
+        code_lines: Vec::new(),
+        pending_open: String::new(),
+        inline_stack: Vec::new(),
+        links: Vec::new(),
+        code_span: None,
+        link_style,
+        file_for,
+        image_for,
+    };
+
+    for token in tokenize_html(html) {
+        match token {
+            HtmlToken::StartTag(name, attrs) => {
+                let attr = |key: &str| attrs.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+                match name.as_str() {
+                    "p" => w.start_block(Block::Paragraph),
+                    "h1" | "h2" | "h3" | "h4" => {
+                        let level = name[1..].parse().unwrap_or(1);
+                        w.start_block(Block::Heading(level));
+                    }
+                    // A nested list ends its parent item's line first, at the parent's depth
+                    "ul" => {
+                        w.end_block();
+                        w.flush_code();
+                        w.lists.push(None);
+                    }
+                    "ol" => {
+                        w.end_block();
+                        w.flush_code();
+                        w.lists.push(Some(0));
+                    }
+                    "li" => w.start_block(Block::ListItem),
+                    "pre" => w.start_block(Block::Code),
+                    "code" if w.block != Block::Code && w.code_span.is_none() => {
+                        w.ensure_block();
+                        w.flush_pending();
+                        w.code_span = Some(w.line.len());
+                    }
+                    "b" | "strong" => w.open_inline(&name, "**", "**"),
+                    "i" | "em" => w.open_inline(&name, "*", "*"),
+                    "s" | "strike" | "del" => w.open_inline(&name, "~~", "~~"),
+                    "u" => w.open_inline(&name, "<u>", "</u>"),
+                    "span" => match attr("style") {
+                        // Colors have no Markdown form; keep them as inline HTML
+                        Some(style) if w.block != Block::Code => {
+                            let open = format!("<span style=\"{}\">", style.replace('"', "&quot;"));
+                            w.open_inline(&name, &open, "</span>");
+                        }
+                        _ => w.inline_stack.push((name.clone(), None)),
+                    },
+                    "a" => {
+                        w.ensure_block();
+                        w.flush_pending();
+                        let href = attr("href").unwrap_or_default();
+                        w.links.push((href, w.line.len()));
+                    }
+                    "img" => {
+                        if let Some(src) = attr("src").filter(|s| !s.is_empty()) {
+                            w.ensure_block();
+                            w.flush_pending();
+                            let target = (w.image_for)(&src);
+                            let alt = attr("alt").unwrap_or_else(|| "image".to_string());
+                            w.line.push_str(&format!("![{}]({})", escape_md(&alt), link_destination(&target)));
+                        }
+                    }
+                    "br" => {
+                        if w.block == Block::Code {
+                            w.line.push('\n');
+                        } else {
+                            w.line.push_str("\\\n");
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            HtmlToken::EndTag(name) => match name.as_str() {
+                "p" | "h1" | "h2" | "h3" | "h4" | "li" | "pre" => w.end_block(),
+                "ul" | "ol" => {
+                    w.end_block();
+                    w.lists.pop();
+                    if w.lists.is_empty() {
+                        w.out.push('\n');
+                    }
+                }
+                "a" => w.close_link(),
+                "code" if w.code_span.is_some() => w.close_code_span(),
+                _ => w.close_inline(&name),
+            },
+            HtmlToken::Text(text) => {
+                if w.block == Block::Code {
+                    w.line.push_str(&text);
+                    continue;
+                }
+                if w.block == Block::None {
+                    if text.trim().is_empty() {
+                        continue;
+                    }
+                    w.ensure_block();
+                }
+                let text = text.replace('\n', " ");
+                if w.code_span.is_some() {
+                    // Code spans are literal: no escaping
+                    w.line.push_str(&text);
+                } else if !w.pending_open.is_empty() {
+                    let trimmed = text.trim_start();
+                    if trimmed.is_empty() {
+                        w.line.push_str(&text);
+                        continue;
+                    }
+                    w.line.push_str(&text[..text.len() - trimmed.len()]);
+                    w.flush_pending();
+                    w.line.push_str(&escape_md(trimmed));
+                } else {
+                    w.line.push_str(&escape_md(&text));
+                }
+            }
+        }
+    }
+    w.end_block();
+    w.flush_code();
+
+    let mut out = w.out.trim_end().to_string();
+    out.push('\n');
+    out
+}
+
+impl MdWriter<'_> {
+    fn start_block(&mut self, block: Block) {
+        self.end_block();
+        if block != Block::Code {
+            self.flush_code();
+        }
+        self.block = block;
+    }
+
+    /// Text outside any block element becomes a paragraph.
+    fn ensure_block(&mut self) {
+        if self.block == Block::None {
+            self.start_block(Block::Paragraph);
+        }
+    }
+
+    fn end_block(&mut self) {
+        // Unclosed inline tags end with their block
+        while let Some((name, _)) = self.inline_stack.last().cloned() {
+            self.close_inline(&name);
+        }
+        if self.code_span.is_some() {
+            self.close_code_span();
+        }
+        while !self.links.is_empty() {
+            self.close_link();
+        }
+        self.pending_open.clear();
+        let line = std::mem::take(&mut self.line);
+        match std::mem::replace(&mut self.block, Block::None) {
+            Block::None => {}
+            Block::Code => self.code_lines.push(line),
+            Block::Paragraph => {
+                let text = line.trim();
+                if !text.is_empty() {
+                    self.out.push_str(&escape_line_start(text));
+                    self.out.push_str("\n\n");
+                }
+            }
+            Block::Heading(level) => {
+                let text = line.trim();
+                if !text.is_empty() {
+                    self.out.push_str(&"#".repeat(level));
+                    self.out.push(' ');
+                    self.out.push_str(text);
+                    self.out.push_str("\n\n");
+                }
+            }
+            Block::ListItem => {
+                let depth = self.lists.len().max(1) - 1;
+                let marker = match self.lists.last_mut() {
+                    Some(Some(counter)) => {
+                        *counter += 1;
+                        format!("{}.", counter)
+                    }
+                    _ => "-".to_string(),
+                };
+                self.out.push_str(&"    ".repeat(depth));
+                self.out.push_str(&marker);
+                self.out.push(' ');
+                self.out.push_str(line.trim());
+                self.out.push('\n');
+            }
+        }
+    }
+
+    /// Consecutive `<pre>` lines from the editor become one fenced block.
+    fn flush_code(&mut self) {
+        if self.code_lines.is_empty() {
+            return;
+        }
+        let body = std::mem::take(&mut self.code_lines).join("\n");
+        let longest_run = body
+            .split(|c| c != '`')
+            .map(str::len)
+            .max()
+            .unwrap_or(0);
+        let fence = "`".repeat(longest_run.max(2) + 1);
+        self.out.push_str(&format!("{}\n{}\n{}\n\n", fence, body, fence));
+    }
+
+    fn open_inline(&mut self, name: &str, open: &str, close: &str) {
+        self.ensure_block();
+        // Markers would show literally inside a code span
+        if self.code_span.is_some() {
+            self.inline_stack.push((name.to_string(), None));
+            return;
+        }
+        self.pending_open.push_str(open);
+        self.inline_stack.push((name.to_string(), Some(close.to_string())));
+    }
+
+    fn flush_pending(&mut self) {
+        let pending = std::mem::take(&mut self.pending_open);
+        self.line.push_str(&pending);
+    }
+
+    fn close_inline(&mut self, name: &str) {
+        let Some(pos) = self.inline_stack.iter().rposition(|(n, _)| n == name) else {
+            return;
+        };
+        let (_, close) = self.inline_stack.remove(pos);
+        let Some(close) = close else { return };
+        // Markers opened but never followed by text are dropped
+        let open_len = self.pending_open.len();
+        if open_len > 0 {
+            if let Some(open) = self.matching_pending_open(&close) {
+                self.pending_open.truncate(open_len - open.len());
+                return;
+            }
+        }
+        // Emphasis can't end on whitespace, so the marker goes before it
+        let trimmed_len = self.line.trim_end().len();
+        let trailing = self.line.split_off(trimmed_len);
+        self.line.push_str(&close);
+        self.line.push_str(&trailing);
+    }
+
+    fn matching_pending_open(&self, close: &str) -> Option<String> {
+        let open = match close {
+            "</span>" => {
+                let start = self.pending_open.rfind("<span")?;
+                return Some(self.pending_open[start..].to_string());
+            }
+            "</u>" => "<u>".to_string(),
+            other => other.to_string(),
+        };
+        self.pending_open.ends_with(&open).then_some(open)
+    }
+
+    /// Fence the code span's text with one more backtick than its longest
+    /// run, padded when it starts or ends with one.
+    fn close_code_span(&mut self) {
+        let Some(start) = self.code_span.take() else { return };
+        let code = self.line.split_off(start.min(self.line.len()));
+        if code.is_empty() {
+            return;
+        }
+        let longest_run = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+        let fence = "`".repeat(longest_run + 1);
+        let pad = if code.starts_with('`') || code.ends_with('`') { " " } else { "" };
+        self.line.push_str(&format!("{}{}{}{}{}", fence, pad, code, pad, fence));
+    }
+
+    fn close_link(&mut self) {
+        let Some((href, start)) = self.links.pop() else { return };
+        let start = start.min(self.line.len());
+        let text = self.line.split_off(start);
+        let link = if let Some(title) = href.strip_prefix("tangle://") {
+            // `]]`, `|` and `#` end or split a wikilink and can't be escaped
+            // inside one, so such titles get a relative link instead
+            let wiki_safe = !title.contains("]]") && !title.contains(['|', '#']);
+            match self.link_style {
+                LinkStyle::Wiki if wiki_safe => {
+                    if text.is_empty() || text == escape_md(title) {
+                        format!("[[{}]]", title)
+                    } else {
+                        format!("[[{}|{}]]", title, text)
+                    }
+                }
+                _ => {
+                    let label = if text.is_empty() { escape_md(title) } else { text };
+                    format!("[{}]({})", label, link_destination(&(self.file_for)(title)))
+                }
+            }
+        } else if href.is_empty() {
+            text
+        } else if text.is_empty() || text == escape_md(&href) {
+            format!("<{}>", href)
+        } else {
+            format!("[{}]({})", text, link_destination(&href))
+        };
+        self.line.push_str(&link);
+    }
+}
+
+/// Backslash-escape characters that would otherwise start Markdown syntax.
+fn escape_md(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '~' | '|') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// A paragraph beginning with `#`, `-`, `+` or `1.` would turn into a heading or list.
+fn escape_line_start(text: &str) -> String {
+    if text.starts_with(['#', '-', '+', '=']) {
+        return format!("\\{}", text);
+    }
+    let digits = text.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 && text[digits..].starts_with(['.', ')']) {
+        return format!("{}\\{}", &text[..digits], &text[digits..]);
+    }
+    text.to_string()
+}
+
+/// Link destinations with spaces or parentheses are wrapped in `<...>`.
+fn link_destination(target: &str) -> String {
+    // A `#` in a file name would read as a fragment
+    let target = target.replace('#', "%23");
+    if target.contains([' ', '(', ')']) {
+        format!("<{}>", target.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        target
+    }
+}
+
+// ── Markdown → HTML (import) ───────────────────────────────────────
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "bmp", "webp", "svg", "tiff", "ico"];
+
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    /// Import files whose title is already taken as "Title (imported)"
+    /// instead of skipping them
+    pub rename_collisions: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// Titles of the tangles created
+    pub imported: Vec<String>,
+    pub images: usize,
+    /// Files whose title matched an existing tangle (or another file)
+    pub collisions: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+/// Convert Markdown into the HTML subset the editor loads. Each source line
+/// becomes its own block, as in the editor; `resolve_title` maps wikilink
+/// and `.md` link targets to tangle titles and `resolve_image` maps image
+/// references to local file paths (None leaves the alt text). Inline code
+/// spans come in as plain text, since the editor's code style is per line.
+pub fn markdown_to_html(
+    markdown: &str,
+    resolve_title: &dyn Fn(&str) -> String,
+    resolve_image: &mut dyn FnMut(&str) -> Option<String>,
+) -> String {
+    let mut ctx = InlineCtx { resolve_title, resolve_image };
+    let mut html = String::new();
+    let mut list: Option<&'static str> = None;
+    let mut fence: Option<String> = None;
+    let mut blank_run = 0;
+    let mut prev_paragraph = false;
+
+    let close_list = |html: &mut String, list: &mut Option<&'static str>| {
+        if let Some(tag) = list.take() {
+            html.push_str(&format!("</{}>\n", tag));
+        }
+    };
+
+    for line in strip_front_matter(markdown).lines() {
+        let trimmed = line.trim_start();
+
+        if let Some(ref f) = fence {
+            if trimmed.starts_with(f.as_str()) && trimmed.trim_end().chars().all(|c| c == f.chars().next().unwrap_or('`')) {
+                fence = None;
+            } else {
+                html.push_str(&format!("<pre><code>{}</code></pre>\n", escape_html(line)));
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            close_list(&mut html, &mut list);
+            let marker = trimmed.chars().next().unwrap_or('`');
+            let len = trimmed.chars().take_while(|&c| c == marker).count();
+            fence = Some(marker.to_string().repeat(len));
+            prev_paragraph = false;
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            close_list(&mut html, &mut list);
+            blank_run += 1;
+            continue;
+        }
+        // One blank line separates blocks; each extra one is an empty line
+        if prev_paragraph {
+            for _ in 1..blank_run {
+                html.push_str("<p></p>\n");
+            }
+        }
+        blank_run = 0;
+        prev_paragraph = false;
+
+        if let Some((level, text)) = parse_heading(trimmed) {
+            close_list(&mut html, &mut list);
+            let level = level.min(4);
+            html.push_str(&format!("<h{}>{}</h{}>\n", level, ctx.inline(text.trim()), level));
+            continue;
+        }
+        if is_thematic_break(trimmed) {
+            close_list(&mut html, &mut list);
+            continue;
+        }
+        if let Some((kind, text)) = parse_list_item(trimmed) {
+            if list != Some(kind) {
+                close_list(&mut html, &mut list);
+                html.push_str(&format!("<{}>\n", kind));
+                list = Some(kind);
+            }
+            let text = if let Some(rest) = text.strip_prefix("[ ] ") {
+                format!("\u{2610} {}", rest)
+            } else if let Some(rest) = text.strip_prefix("[x] ").or_else(|| text.strip_prefix("[X] ")) {
+                format!("\u{2611} {}", rest)
+            } else {
+                text.to_string()
+            };
+            html.push_str(&format!("<li>{}</li>\n", ctx.inline(&text)));
+            continue;
+        }
+        close_list(&mut html, &mut list);
+        if let Some(quote) = trimmed.strip_prefix('>') {
+            let quote = quote.trim();
+            if !quote.is_empty() {
+                html.push_str(&format!("<p><i>{}</i></p>\n", ctx.inline(quote)));
+            }
+            continue;
+        }
+        html.push_str(&format!("<p>{}</p>\n", ctx.inline(line.trim())));
+        prev_paragraph = true;
+    }
+    close_list(&mut html, &mut list);
+    html
+}
+
+/// Import every `.md` file under `dir` (hidden folders such as `.obsidian`
+/// are skipped) as a tangle titled after the file name. Wikilinks and links
+/// to other `.md` files become tangle links; images are copied into the
+/// Tangles data folder.
+pub fn import_folder(db: &Database, dir: &Path, options: &ImportOptions) -> std::io::Result<ImportReport> {
+    let mut report = ImportReport::default();
+    let mut md_files: Vec<PathBuf> = Vec::new();
+    let mut by_name: HashMap<String, PathBuf> = HashMap::new();
+    collect_files(dir, &mut md_files, &mut by_name)?;
+    md_files.sort();
+
+    // Decide each file's title up front so links between files resolve
+    let mut titles: HashMap<String, String> = HashMap::new();
+    let mut planned: Vec<(PathBuf, String)> = Vec::new();
+    let mut taken: std::collections::HashSet<String> = std::collections::HashSet::new();
+    for path in &md_files {
+        let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        if stem.trim().is_empty() {
+            continue;
+        }
+        let Some(title) = plan_import_title(db, &mut taken, &stem, options, &mut report) else {
+            continue;
+        };
+        titles.entry(stem.to_lowercase()).or_insert_with(|| title.clone());
+        planned.push((path.clone(), title));
+    }
+
+    let images_dir = match dirs::data_dir() {
+        Some(d) => d.join("tangles").join("images"),
+        None => return Err(std::io::Error::other("could not determine data directory")),
+    };
+    let mut copied: HashMap<PathBuf, String> = HashMap::new();
+
+    for (path, title) in planned {
+        let markdown = match std::fs::read_to_string(&path) {
+            Ok(m) => m,
+            Err(e) => {
+                report.errors.push(format!("{}: {}", path.display(), e));
+                continue;
+            }
+        };
+        let base = path.parent().unwrap_or(dir).to_path_buf();
+        let resolve_title = |target: &str| {
+            titles.get(&target.to_lowercase()).cloned().unwrap_or_else(|| target.to_string())
+        };
+        let mut resolve_image = |src: &str| -> Option<String> {
+            if src.contains("://") {
+                return None;
+            }
+            let candidates = [base.join(src), dir.join(src)];
+            let source = candidates.iter().find(|p| p.is_file()).cloned().or_else(|| {
+                let name = Path::new(src).file_name()?.to_string_lossy().to_lowercase();
+                by_name.get(&name).cloned()
+            })?;
+            if let Some(dest) = copied.get(&source) {
+                return Some(dest.clone());
+            }
+            let file_name = source.file_name()?.to_string_lossy().to_string();
+            let mut dest = images_dir.join(&file_name);
+            let mut n = 2;
+            while dest.exists() {
+                dest = images_dir.join(format!("{}-{}", n, file_name));
+                n += 1;
+            }
+            match std::fs::create_dir_all(&images_dir).and_then(|_| std::fs::copy(&source, &dest)) {
+                Ok(_) => {
+                    report.images += 1;
+                    let dest = dest.to_string_lossy().to_string();
+                    copied.insert(source, dest.clone());
+                    Some(dest)
+                }
+                Err(e) => {
+                    report.errors.push(format!("{}: could not copy image {}: {}", title, source.display(), e));
+                    None
+                }
+            }
+        };
+        let content = markdown_to_html(&markdown, &resolve_title, &mut resolve_image);
+
+        let metadata = std::fs::metadata(&path).ok();
+        let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+        let created = metadata.as_ref().and_then(|m| m.created().ok()).or(modified);
+        let to_rfc3339 = |t: Option<std::time::SystemTime>| {
+            t.map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339())
+                .unwrap_or_else(|| chrono::Utc::now().to_rfc3339())
+        };
+        let note = Note {
+            id: None,
+            title: title.clone(),
+            content,
+            created_at: to_rfc3339(created),
+            updated_at: to_rfc3339(modified),
+            position_x: 100.0,
+            position_y: 100.0,
+            is_visible: false,
+            always_on_top: false,
+            width: 500,
+            height: 400,
+            theme_bg: None,
+            theme_fg: None,
+            theme_accent: None,
+            custom_colors: None,
+            chromeless: false,
+            star_color: None,
+        };
+        match db.create_note(&note) {
+            Ok(_) => report.imported.push(title),
+            Err(e) => report.errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+    Ok(report)
+}
+
+/// Pick the title an imported note will get. A title already used by a tangle
+/// or an earlier file in the same import is recorded as a collision, then
+/// either skipped (None) or renamed to "Title (imported)".
+pub(crate) fn plan_import_title(
+    db: &Database,
+    taken: &mut std::collections::HashSet<String>,
+    title: &str,
+    options: &ImportOptions,
+    report: &mut ImportReport,
+) -> Option<String> {
+    let exists = |taken: &std::collections::HashSet<String>, t: &str| {
+        taken.contains(&t.to_lowercase()) || matches!(db.get_note_by_title(t), Ok(Some(_)))
+    };
+    let mut planned = title.to_string();
+    if exists(taken, &planned) {
+        report.collisions.push(title.to_string());
+        if !options.rename_collisions {
+            return None;
+        }
+        planned = format!("{} (imported)", title);
+        let mut n = 2;
+        while exists(taken, &planned) {
+            planned = format!("{} (imported {})", title, n);
+            n += 1;
+        }
+    }
+    taken.insert(planned.to_lowercase());
+    Some(planned)
+}
+
+fn collect_files(dir: &Path, md_files: &mut Vec<PathBuf>, by_name: &mut HashMap<String, PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        if name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(&path, md_files, by_name)?;
+        } else if name.to_lowercase().ends_with(".md") {
+            md_files.push(path);
+        } else {
+            by_name.entry(name.to_lowercase()).or_insert(path);
+        }
+    }
+    Ok(())
+}
+
+fn strip_front_matter(markdown: &str) -> &str {
+    let Some(rest) = markdown.strip_prefix("---\n").or_else(|| markdown.strip_prefix("---\r\n")) else {
+        return markdown;
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        offset += line.len();
+        if line.trim_end() == "---" || line.trim_end() == "..." {
+            return &rest[offset..];
+        }
+    }
+    markdown
+}
+
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if rest.is_empty() {
+        return Some((level, ""));
+    }
+    rest.starts_with([' ', '\t'])
+        .then(|| (level, rest.trim_end().trim_end_matches('#')))
+}
+
+fn is_thematic_break(line: &str) -> bool {
+    let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && ["-", "*", "_"].iter().any(|m| compact.chars().all(|c| c.to_string() == *m))
+}
+
+fn parse_list_item(line: &str) -> Option<(&'static str, &str)> {
+    if let Some(rest) = line.strip_prefix(['-', '*', '+']) {
+        if rest.starts_with([' ', '\t']) {
+            return Some(("ul", rest.trim_start()));
+        }
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 && digits <= 9 {
+        let rest = &line[digits..];
+        if let Some(rest) = rest.strip_prefix(['.', ')']) {
+            if rest.starts_with([' ', '\t']) {
+                return Some(("ol", rest.trim_start()));
+            }
+        }
+    }
+    None
+}
+
+struct InlineCtx<'a> {
+    resolve_title: &'a dyn Fn(&str) -> String,
+    resolve_image: &'a mut dyn FnMut(&str) -> Option<String>,
+}
+
+impl InlineCtx<'_> {
+    fn inline(&mut self, text: &str) -> String {
+        let mut out = String::new();
+        let mut rest = text;
+        let mut prev: Option<char> = None;
+
+        'scan: while let Some(c) = rest.chars().next() {
+            if c == '\\' {
+                if let Some(next) = rest[1..].chars().next().filter(|n| n.is_ascii_punctuation()) {
+                    out.push_str(&escape_html(&next.to_string()));
+                    rest = &rest[1 + next.len_utf8()..];
+                    prev = Some(next);
+                    continue;
+                }
+            }
+            if let Some(inner) = rest.strip_prefix("![[").and_then(|r| r.find("]]").map(|e| &r[..e])) {
+                out.push_str(&self.embed(inner));
+                rest = &rest[3 + inner.len() + 2..];
+                continue;
+            }
+            if let Some(inner) = rest.strip_prefix("[[").and_then(|r| r.find("]]").map(|e| &r[..e])) {
+                out.push_str(&self.wikilink(inner));
+                rest = &rest[2 + inner.len() + 2..];
+                continue;
+            }
+            if rest.starts_with("![") {
+                if let Some((alt, dest, len)) = parse_inline_link(&rest[1..]) {
+                    out.push_str(&self.image(&percent_decode(dest), alt, None));
+                    rest = &rest[1 + len..];
+                    continue;
+                }
+            }
+            if c == '[' {
+                if let Some((label, dest, len)) = parse_inline_link(rest) {
+                    out.push_str(&self.link(label, dest));
+                    rest = &rest[len..];
+                    continue;
+                }
+            }
+            if c == '`' {
+                let ticks = rest.chars().take_while(|&c| c == '`').count();
+                let delim = &rest[..ticks];
+                if let Some(end) = rest[ticks..].find(delim) {
+                    // The editor's code tag marks whole lines, so an inline
+                    // span keeps only its literal text
+                    out.push_str(&escape_html(rest[ticks..ticks + end].trim()));
+                    rest = &rest[ticks + end + ticks..];
+                    continue;
+                }
+            }
+            if c == '<' {
+                if let Some(end) = rest.find('>') {
+                    let tag = &rest[..=end];
+                    let inner = &tag[1..tag.len() - 1];
+                    // Markup the exporter writes for underline and colors
+                    let passthrough = matches!(tag, "<u>" | "</u>" | "</span>")
+                        || (tag.starts_with("<span style=\"") && !inner.contains(['<', '>']));
+                    if passthrough {
+                        out.push_str(tag);
+                        rest = &rest[end + 1..];
+                        continue;
+                    }
+                    if inner.starts_with("http://") || inner.starts_with("https://") || inner.starts_with("mailto:") {
+                        out.push_str(&format!("<a href=\"{}\">{}</a>", escape_html_attr(inner), escape_html(inner)));
+                        rest = &rest[end + 1..];
+                        continue;
+                    }
+                }
+            }
+            for (delim, tag) in [("**", "b"), ("__", "b"), ("~~", "s"), ("*", "i"), ("_", "i")] {
+                if !rest.starts_with(delim) {
+                    continue;
+                }
+                // `snake_case` words aren't emphasis
+                if delim.starts_with('_') && prev.is_some_and(|p| p.is_alphanumeric()) {
+                    continue;
+                }
+                if let Some(end) = find_closing(rest, delim) {
+                    let inner = &rest[delim.len()..end];
+                    out.push_str(&format!("<{}>{}</{}>", tag, self.inline(inner), tag));
+                    rest = &rest[end + delim.len()..];
+                    prev = inner.chars().last();
+                    continue 'scan;
+                }
+            }
+            out.push_str(&escape_html(&c.to_string()));
+            rest = &rest[c.len_utf8()..];
+            prev = Some(c);
+        }
+        out
+    }
+
+    fn wikilink(&mut self, inner: &str) -> String {
+        let (target, alias) = match inner.split_once('|') {
+            Some((t, a)) => (t, Some(a.trim())),
+            None => (inner, None),
+        };
+        let target = target.split(['#', '^']).next().unwrap_or("").trim();
+        let target = target.rsplit('/').next().unwrap_or(target);
+        let target = target.strip_suffix(".md").unwrap_or(target);
+        if target.is_empty() {
+            return escape_html(alias.unwrap_or(inner));
+        }
+        let title = (self.resolve_title)(target);
+        let label = alias.filter(|a| !a.is_empty()).unwrap_or(&title).to_string();
+        tangle_anchor(&title, &escape_html(&label))
+    }
+
+    /// `![[file]]`: an image, or a link to the embedded note
+    fn embed(&mut self, inner: &str) -> String {
+        let (target, size) = match inner.split_once('|') {
+            Some((t, s)) => (t.trim(), s.split('x').next().and_then(|w| w.trim().parse().ok())),
+            None => (inner.trim(), None),
+        };
+        let is_image = target
+            .rsplit_once('.')
+            .is_some_and(|(_, ext)| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+        if is_image {
+            self.image(target, target, size)
+        } else {
+            self.wikilink(inner)
+        }
+    }
+
+    fn image(&mut self, src: &str, alt: &str, width: Option<i32>) -> String {
+        match (self.resolve_image)(src) {
+            Some(path) => format!(
+                "<img src=\"{}\" width=\"{}\" alt=\"image\"/>",
+                escape_html_attr(&path),
+                width.unwrap_or(300),
+            ),
+            None => escape_html(if alt.is_empty() { src } else { alt }),
+        }
+    }
+
+    fn link(&mut self, label: &str, dest: &str) -> String {
+        let label_html = self.inline(label);
+        let decoded = percent_decode(dest);
+        if let Some(title) = decoded.strip_prefix("tangle://") {
+            return tangle_anchor(title, &label_html);
+        }
+        let is_local = !decoded.contains("://") && !decoded.starts_with("mailto:");
+        if is_local {
+            // Drop the fragment before decoding, so `%23` stays in the name
+            let path = percent_decode(dest.split('#').next().unwrap_or(""));
+            if let Some(stem) = path.strip_suffix(".md") {
+                let stem = stem.rsplit('/').next().unwrap_or(stem);
+                return tangle_anchor(&(self.resolve_title)(stem), &label_html);
+            }
+        }
+        format!("<a href=\"{}\">{}</a>", escape_html_attr(dest), label_html)
+    }
+}
+
+fn tangle_anchor(title: &str, label_html: &str) -> String {
+    format!("<a href=\"tangle://{}\" class=\"tangle\">{}</a>", escape_html_attr(title), label_html)
+}
+
+/// Parse `[label](destination "title")` at the start of `s`.
+/// Returns the label, destination and number of bytes consumed.
+fn parse_inline_link(s: &str) -> Option<(&str, &str, usize)> {
+    let mut depth = 0;
+    let mut label_end = None;
+    for (i, c) in s.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    label_end = Some(i);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let label_end = label_end?;
+    let after = s[label_end + 1..].strip_prefix('(')?;
+    let dest_start = label_end + 2;
+    let (dest, close) = if let Some(inner) = after.strip_prefix('<') {
+        let end = inner.find('>')?;
+        let close = after[end + 2..].find(')')? + end + 2;
+        (&inner[..end], close)
+    } else {
+        let mut parens = 0;
+        let mut close = None;
+        for (i, c) in after.char_indices() {
+            match c {
+                '(' => parens += 1,
+                ')' if parens == 0 => {
+                    close = Some(i);
+                    break;
+                }
+                ')' => parens -= 1,
+                _ => {}
+            }
+        }
+        let close = close?;
+        let dest = after[..close].trim();
+        // Drop an optional "title"
+        let dest = match dest.find(" \"") {
+            Some(space) if dest.ends_with('"') => &dest[..space],
+            _ => dest,
+        };
+        (dest, close)
+    };
+    Some((&s[1..label_end], dest, dest_start + close + 1))
+}
+
+/// Find the closing emphasis delimiter: preceded by a non-space character
+/// and, for single `*`/`_`, not part of a doubled delimiter.
+fn find_closing(s: &str, delim: &str) -> Option<usize> {
+    let body = &s[delim.len()..];
+    if body.starts_with(char::is_whitespace) || body.is_empty() {
+        return None;
+    }
+    let mut search = delim.len();
+    while let Some(pos) = s[search..].find(delim) {
+        let at = search + pos;
+        let before = s[..at].chars().last();
+        let after = s[at + delim.len()..].chars().next();
+        let doubled = delim.len() == 1 && (after == delim.chars().next() || before == delim.chars().next());
+        let intraword = delim == "_" && after.is_some_and(|a| a.is_alphanumeric());
+        if at > delim.len() && before.is_some_and(|b| !b.is_whitespace()) && !doubled && !intraword {
+            return Some(at);
+        }
+        search = at + delim.len();
+    }
+    None
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len()
+            && bytes[i + 1].is_ascii_hexdigit() && bytes[i + 2].is_ascii_hexdigit()
+        {
+            if let Ok(b) = u8::from_str_radix(&s[i + 1..i + 3], 16) {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_markdown(html: &str) -> String {
+        html_to_markdown(html, LinkStyle::Wiki)
+    }
+
+    fn to_html(markdown: &str) -> String {
+        markdown_to_html(markdown, &|t| t.to_string(), &mut |_| None)
+    }
+
+    #[test]
+    fn nested_lists_keep_their_levels() {
+        assert_eq!(
+            to_markdown("<ul><li>One<ul><li>Inner <b>bold</b></li><li>Inner 2</li></ul></li><li>Two</li></ul>"),
+            "- One\n    - Inner **bold**\n    - Inner 2\n- Two\n",
+        );
+        assert_eq!(
+            to_markdown("<ol><li>A<ol><li>A1</li><li>A2</li></ol></li><li>B</li></ol>"),
+            "1. A\n    1. A1\n    2. A2\n2. B\n",
+        );
+    }
+
+    #[test]
+    fn inline_code_is_fenced_and_literal() {
+        assert_eq!(to_markdown("<p>Run <code>cargo *test*</code> now</p>"), "Run `cargo *test*` now\n");
+        assert_eq!(to_markdown("<p>Use <code>a`b</code></p>"), "Use ``a`b``\n");
+        assert_eq!(to_markdown("<p><code>`x</code></p>"), "`` `x ``\n");
+        assert_eq!(to_markdown("<p><code><b>raw</b></code></p>"), "`raw`\n");
+    }
+
+    #[test]
+    fn blocks_and_escapes() {
+        assert_eq!(
+            to_markdown("<h2>Title</h2>\n<p>1. not a list, *not* emphasis</p>\n<pre><code>let x = 1;</code></pre>\n"),
+            "## Title\n\n1\\. not a list, \\*not\\* emphasis\n\n```\nlet x = 1;\n```\n",
+        );
+        assert_eq!(to_markdown("<p><i>a</i><s> </s>b</p>"), "*a* b\n");
+    }
+
+    #[test]
+    fn tangle_links_by_style() {
+        let html = "<p><a href=\"tangle://Tom &amp; Jerry\" class=\"tangle\">Tom &amp; Jerry</a> and \
+                    <a href=\"tangle://Other\" class=\"tangle\">that one</a></p>";
+        assert_eq!(to_markdown(html), "[[Tom & Jerry]] and [[Other|that one]]\n");
+        assert_eq!(
+            html_to_markdown(html, LinkStyle::Relative),
+            "[Tom & Jerry](<Tom & Jerry.md>) and [that one](Other.md)\n",
+        );
+        let html = "<p><a href=\"tangle://A|B\" class=\"tangle\">A|B</a> \
+                    <a href=\"tangle://C#1\" class=\"tangle\">c</a> \
+                    <a href=\"tangle://[[D]]\" class=\"tangle\">[[D]]</a></p>";
+        assert_eq!(
+            to_markdown(html),
+            "[A\\|B](A-B.md) [c](C%231.md) [\\[\\[D\\]\\]]([[D]].md)\n",
+        );
+        assert_eq!(
+            crate::database::extract_tangle_targets(&to_html(&to_markdown(html))),
+            ["A-B", "C#1", "[[D]]"],
+        );
+    }
+
+    #[test]
+    fn markdown_blocks_to_html() {
+        assert_eq!(
+            to_html("# Head\n\n- one\n- two\n\n1. first\n\n```\n<b>\n```\nPlain `co*de*` text"),
+            concat!(
+                "<h1>Head</h1>\n",
+                "<ul>\n<li>one</li>\n<li>two</li>\n</ul>\n",
+                "<ol>\n<li>first</li>\n</ol>\n",
+                "<pre><code>&lt;b&gt;</code></pre>\n",
+                "<p>Plain co*de* text</p>\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn code_spans_import_as_literal_text() {
+        assert_eq!(to_html("Run `cargo *test*` or ``a`b``"), "<p>Run cargo *test* or a`b</p>\n");
+        assert_eq!(to_html("`<b>[[Plan]]</b>`"), "<p>&lt;b&gt;[[Plan]]&lt;/b&gt;</p>\n");
+        // Exporting again escapes the text instead of restoring the span
+        assert_eq!(to_markdown(&to_html("Run `cargo *test*`")), "Run cargo \\*test\\*\n");
+    }
+
+    #[test]
+    fn wikilinks_escape_titles_in_href() {
+        assert_eq!(
+            to_html("See [[Say \"hi\" & <wave>]] and [[Notes/Plan.md#Goals|the plan]]"),
+            concat!(
+                "<p>See <a href=\"tangle://Say &quot;hi&quot; &amp; &lt;wave&gt;\" class=\"tangle\">",
+                "Say &quot;hi&quot; &amp; &lt;wave&gt;</a> and ",
+                "<a href=\"tangle://Plan\" class=\"tangle\">the plan</a></p>\n",
+            ),
+        );
+        // The stored href decodes back to the title
+        let html = to_html("[[A \"quoted\" title]]");
+        assert_eq!(crate::database::extract_tangle_targets(&html), ["A \"quoted\" title"]);
+    }
+
+    #[test]
+    fn markdown_links_to_tangles() {
+        let resolve = |t: &str| format!("{} (tangle)", t);
+        assert_eq!(
+            markdown_to_html("[x](<My Note.md>) [y](tangle://Q%22s) [z](https://e.com/?a=1&b=\"2\")", &resolve, &mut |_| None),
+            concat!(
+                "<p><a href=\"tangle://My Note (tangle)\" class=\"tangle\">x</a> ",
+                "<a href=\"tangle://Q&quot;s\" class=\"tangle\">y</a> ",
+                "<a href=\"https://e.com/?a=1&amp;b=&quot;2&quot;\">z</a></p>\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn import_titles_avoid_collisions() {
+        let db = Database::new(Path::new(":memory:")).unwrap();
+        let now = chrono::Utc::now().to_rfc3339();
+        db.create_note(&Note {
+            id: None,
+            title: "Taken".to_string(),
+            content: String::new(),
+            created_at: now.clone(),
+            updated_at: now,
+            position_x: 0.0,
+            position_y: 0.0,
+            is_visible: false,
+            always_on_top: false,
+            width: 400,
+            height: 300,
+            theme_bg: None,
+            theme_fg: None,
+            theme_accent: None,
+            custom_colors: None,
+            chromeless: false,
+            star_color: None,
+        })
+        .unwrap();
+
+        let mut taken = std::collections::HashSet::new();
+        let mut report = ImportReport::default();
+        let rename = ImportOptions { rename_collisions: true };
+        let skip = ImportOptions { rename_collisions: false };
+
+        assert_eq!(plan_import_title(&db, &mut taken, "Fresh", &skip, &mut report).as_deref(), Some("Fresh"));
+        // Earlier files in the same import count, case-insensitively
+        assert_eq!(plan_import_title(&db, &mut taken, "FRESH", &skip, &mut report), None);
+        assert_eq!(plan_import_title(&db, &mut taken, "Taken", &rename, &mut report).as_deref(), Some("Taken (imported)"));
+        assert_eq!(plan_import_title(&db, &mut taken, "Taken", &rename, &mut report).as_deref(), Some("Taken (imported 2)"));
+        assert_eq!(report.collisions, ["FRESH", "Taken", "Taken"]);
+    }
+}
@@ -1,11 +1,12 @@
 use gtk4::prelude::*;
-use gtk4::{glib, ApplicationWindow, Button, Entry, Label};
+use gtk4::{gio, glib, ApplicationWindow, Button, Entry, Label};
 use gtk4::gdk::prelude::ToplevelExt;
 use std::cell::RefCell;
+use std::collections::HashSet;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
-use crate::database::{Database, Note};
+use crate::database::{ContentEdit, Database, Note, NoteRevision};
 use crate::rich_editor::RichEditor;
 
 static APP_QUITTING: AtomicBool = AtomicBool::new(false);
@@ -147,6 +148,12 @@ impl NoteWindow {
             .css_classes(["pin-button"])
             .build();
 
+        let history_btn = Button::builder()
+            .label("\u{1f558}")
+            .tooltip_text("Version history")
+            .css_classes(["history-button"])
+            .build();
+
         // Create editor early so we can grab its hamburger button for the title bar
         let editor = RichEditor::new(db.clone(), app.clone(), &note.title);
         editor.set_content(&note.content);
@@ -157,6 +164,7 @@ impl NoteWindow {
         title_box.append(&star_btn);
         title_box.append(&chromeless_btn);
         title_box.append(&palette_btn);
+        title_box.append(&history_btn);
         title_box.append(&always_on_top_btn);
         main_box.append(&title_box);
 
@@ -173,8 +181,8 @@ impl NoteWindow {
 
         // Backlinks pane
         let backlinks_box = gtk4::Box::builder()
-            .orientation(gtk4::Orientation::Horizontal)
-            .spacing(6)
+            .orientation(gtk4::Orientation::Vertical)
+            .spacing(4)
             .css_classes(["backlinks-pane"])
             .build();
         backlinks_box.set_visible(false);
@@ -244,11 +252,15 @@ impl NoteWindow {
         let note_class_for_save = Rc::new(RefCell::new(note_class.clone()));
 
         let is_pinned: Rc<RefCell<bool>> = Rc::new(RefCell::new(note.always_on_top));
+        // Last title written to the DB; a different entry title means a rename
+        let saved_title: Rc<RefCell<String>> = Rc::new(RefCell::new(note.title.clone()));
 
         let do_save = {
             let note_id = note_id.clone();
             let note_template = note_template.clone();
             let db = db.clone();
+            let app = app.clone();
+            let saved_title = saved_title.clone();
             let title_entry = title_entry.clone();
             let editor_ref = editor_ref.clone();
             let window_ref = window.clone();
@@ -264,13 +276,17 @@ impl NoteWindow {
             let is_pinned = is_pinned.clone();
 
             Rc::new(move || {
-                let title = title_entry.text().to_string();
-                let content = editor_ref.get_content();
-
                 let current_id = *note_id.borrow();
-                if current_id.is_none() && title == "New Tangle" && content.is_empty() {
+                if current_id.is_none() && title_entry.text() == "New Tangle" && editor_ref.get_content().is_empty() {
                     return;
                 }
+                // A half-typed title isn't a rename: that waits for Enter, focus
+                // leaving the title or the window closing (`commit_title` below)
+                let title = match current_id {
+                    Some(_) => saved_title.borrow().clone(),
+                    None => resolve_title_change(&app, &db, &title_entry, &saved_title, current_id),
+                };
+                let content = editor_ref.get_content();
 
                 let mut save_note = (*note_template).clone();
                 save_note.id = current_id;
@@ -294,11 +310,24 @@ impl NoteWindow {
                 save_note.always_on_top = *is_pinned.borrow();
                 save_note.is_visible = true;
 
-                if current_id.is_some() {
+                if let Some(id) = current_id {
                     let db = db.clone();
+                    let (tx, rx) = std::sync::mpsc::channel::<String>();
                     std::thread::spawn(move || {
-                        if let Err(e) = db.update_note(&save_note) {
-                            eprintln!("Error updating note: {}", e);
+                        match db.update_note(&save_note) {
+                            Ok(()) => { let _ = tx.send(save_note.title); }
+                            Err(e) => eprintln!("Error updating note: {}", e),
+                        }
+                    });
+                    let app_changed = app.clone();
+                    glib::timeout_add_local(std::time::Duration::from_millis(50), move || {
+                        match rx.try_recv() {
+                            Ok(saved) => {
+                                crate::dbus_service::emit_note_changed(&app_changed, id, &saved);
+                                glib::ControlFlow::Break
+                            }
+                            Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+                            Err(_) => glib::ControlFlow::Break,
                         }
                     });
                 } else {
@@ -306,6 +335,8 @@ impl NoteWindow {
                     let note_id_ref = note_id.clone();
                     let note_class_ref2 = note_class_ref.clone();
                     let win_ref = win_for_class.clone();
+                    let app_changed = app.clone();
+                    let created_title = title.clone();
                     let (tx, rx) = std::sync::mpsc::channel::<i64>();
                     std::thread::spawn(move || {
                         match db_bg.create_note(&save_note) {
@@ -322,6 +353,7 @@ impl NoteWindow {
                                 win_ref.remove_css_class(&old_class);
                                 win_ref.add_css_class(&new_class);
                                 *note_class_ref2.borrow_mut() = new_class;
+                                crate::dbus_service::emit_note_changed(&app_changed, id, &created_title);
                                 glib::ControlFlow::Break
                             }
                             Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
@@ -370,6 +402,99 @@ impl NoteWindow {
             });
         }
 
+        // History button — browse revisions, diff against the editor, restore
+        {
+            let note_id_h = note_id.clone();
+            let db_h = db.clone();
+            let editor_h = editor_ref.clone();
+            let title_h = title_entry.clone();
+            let saved_title_h = saved_title.clone();
+            let app_h = app.clone();
+            let win_for_history = window.clone();
+            let prev_history_win: Rc<RefCell<Option<gtk4::Window>>> = Rc::new(RefCell::new(None));
+            history_btn.connect_clicked(move |_| {
+                let Some(id) = *note_id_h.borrow() else { return };
+                if let Some(old) = prev_history_win.borrow_mut().take() {
+                    old.close();
+                }
+                let editor_cur = editor_h.clone();
+                let current: Rc<dyn Fn() -> String> = Rc::new(move || editor_cur.get_content());
+                let db_r = db_h.clone();
+                let editor_r = editor_h.clone();
+                let title_r = title_h.clone();
+                let saved_title_r = saved_title_h.clone();
+                let app_r = app_h.clone();
+                let on_restore: Rc<dyn Fn(&NoteRevision)> = Rc::new(move |rev| {
+                    // Keep the state being replaced so a restore can itself be undone
+                    let current = editor_r.get_content();
+                    if let Err(e) = db_r.create_revision(id, &title_r.text(), &current) {
+                        eprintln!("Error saving revision: {}", e);
+                    }
+                    editor_r.set_content(&rev.content);
+                    // The old title goes through the same rename as typing it,
+                    // so links and open windows follow
+                    if rev.title != title_r.text() {
+                        title_r.set_text(&rev.title);
+                        resolve_title_change(&app_r, &db_r, &title_r, &saved_title_r, Some(id));
+                    }
+                });
+                let history_win = crate::history::show_history_window(
+                    &win_for_history, &db_h, id, current, on_restore,
+                );
+                *prev_history_win.borrow_mut() = Some(history_win.clone());
+                history_win.present();
+            });
+        }
+
+        // Per-note actions (editor context menu)
+        let note_actions = gio::SimpleActionGroup::new();
+        {
+            let export_action = gio::SimpleAction::new("export-markdown", None);
+            let note_id_x = note_id.clone();
+            let db_x = db.clone();
+            let win_x = window.clone();
+            let do_save_x = do_save.clone();
+            export_action.connect_activate(move |_, _| {
+                // Export what's on screen, not the last autosave
+                do_save_x();
+                if let Some(id) = *note_id_x.borrow() {
+                    crate::dialogs::show_markdown_export_dialog(&win_x, &db_x, Some(id));
+                }
+            });
+            note_actions.add_action(&export_action);
+
+            // Local graph: the tangle map focused on this tangle
+            let graph_action = gio::SimpleAction::new("local-graph", None);
+            let note_id_g = note_id.clone();
+            let db_g = db.clone();
+            let win_g = window.clone();
+            let app_g = app.clone();
+            let do_save_g = do_save.clone();
+            graph_action.connect_activate(move |_, _| {
+                // Include links added since the last autosave
+                do_save_g();
+                if let Some(id) = *note_id_g.borrow() {
+                    crate::tangle_map::show_tangle_map(&app_g, &win_g, &db_g, Some(id), &[]);
+                }
+            });
+            note_actions.add_action(&graph_action);
+
+            // Append HTML to the editor and save (D-Bus AppendToNote on an open tangle)
+            let append_action = gio::SimpleAction::new("append-html", Some(glib::VariantTy::STRING));
+            let editor_a = editor_ref.clone();
+            let do_save_a = do_save.clone();
+            append_action.connect_activate(move |_, param| {
+                let Some(html) = param.and_then(|p| p.get::<String>()) else { return };
+                let mut content = editor_a.get_content();
+                content.push_str(&html);
+                editor_a.set_content(&content);
+                do_save_a();
+            });
+            note_actions.add_action(&append_action);
+
+            window.insert_action_group("note", Some(&note_actions));
+        }
+
         // Star button handler
         {
             let star_c = star_color_rc.clone();
@@ -499,6 +624,28 @@ impl NoteWindow {
             schedule_ref2();
         });
 
+        // Rename once the title is committed, then save under it
+        let commit_title = {
+            let app = app.clone();
+            let db = db.clone();
+            let title_entry = title_entry.clone();
+            let saved_title = saved_title.clone();
+            let note_id = note_id.clone();
+            let do_save = do_save.clone();
+            Rc::new(move || {
+                let Some(id) = *note_id.borrow() else { return };
+                if title_entry.text() != *saved_title.borrow() {
+                    resolve_title_change(&app, &db, &title_entry, &saved_title, Some(id));
+                    do_save();
+                }
+            })
+        };
+        let commit_enter = commit_title.clone();
+        title_entry.connect_activate(move |_| commit_enter());
+        let title_focus = gtk4::EventControllerFocus::new();
+        title_focus.connect_leave(move |_| commit_title());
+        title_entry.add_controller(title_focus);
+
         // Periodic geometry save (catches moves/resizes without content edits)
         let do_save_geo = do_save.clone();
         let win_alive = window.clone();
@@ -514,17 +661,21 @@ impl NoteWindow {
         // Backlinks refresh with dedup tracking
         let db_bl = db.clone();
         let app_bl = app.clone();
-        let title_bl = title_entry.clone();
+        let note_id_bl = note_id.clone();
+        let accent_bl = theme_accent.clone();
         let bl_box = backlinks_box.clone();
         let bl_poll_id: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
         let bl_poll_ref = bl_poll_id.clone();
+        // Origin tangles whose link paragraphs are folded away
+        let bl_collapsed: Rc<RefCell<HashSet<i64>>> = Rc::new(RefCell::new(HashSet::new()));
         let refresh_backlinks = Rc::new(move || {
             // Cancel any in-flight poll (ignore error if source already completed)
             if let Some(id) = bl_poll_ref.borrow_mut().take() {
                 unsafe { glib::ffi::g_source_remove(id.as_raw()); }
             }
             let poll_ref = bl_poll_ref.clone();
-            let source_id = refresh_backlinks_pane(&bl_box, &db_bl, &title_bl.text(), &app_bl);
+            let accent = accent_bl.borrow().clone().unwrap_or_else(|| crate::theme::global_accent(&db_bl));
+            let source_id = refresh_backlinks_pane(&bl_box, &db_bl, *note_id_bl.borrow(), accent, &app_bl, &bl_collapsed);
             *poll_ref.borrow_mut() = source_id;
         });
 
@@ -534,6 +685,28 @@ impl NoteWindow {
             refresh_init();
         });
 
+        // Refreshed whenever another tangle changes (see `emit_note_changed`).
+        // Autosaves elsewhere arrive in bursts, so they share one refresh a
+        // second later
+        {
+            let refresh_action = gio::SimpleAction::new("refresh-backlinks", None);
+            let refresh = refresh_backlinks.clone();
+            let pending: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+            refresh_action.connect_activate(move |_, _| {
+                if pending.borrow().is_some() {
+                    return;
+                }
+                let refresh = refresh.clone();
+                let pending_done = pending.clone();
+                let source_id = glib::timeout_add_local_once(std::time::Duration::from_secs(1), move || {
+                    *pending_done.borrow_mut() = None;
+                    refresh();
+                });
+                *pending.borrow_mut() = Some(source_id);
+            });
+            note_actions.add_action(&refresh_action);
+        }
+
         // Periodic backlinks refresh (every 15 seconds)
         let refresh_periodic = refresh_backlinks.clone();
         let win_bl = window.clone();
@@ -551,6 +724,8 @@ impl NoteWindow {
             let note_id = note_id.clone();
             let note_template = note_template.clone();
             let db = db.clone();
+            let app = app.clone();
+            let saved_title = saved_title.clone();
             let title_entry = title_entry.clone();
             let editor_ref = editor_ref.clone();
             let theme_bg = theme_bg.clone();
@@ -563,12 +738,12 @@ impl NoteWindow {
             let is_pinned = is_pinned.clone();
 
             Rc::new(move |visible: bool| {
-                let title = title_entry.text().to_string();
-                let content = editor_ref.get_content();
                 let current_id = *note_id.borrow();
-                if current_id.is_none() && title == "New Tangle" && content.is_empty() {
+                if current_id.is_none() && title_entry.text() == "New Tangle" && editor_ref.get_content().is_empty() {
                     return;
                 }
+                let title = resolve_title_change(&app, &db, &title_entry, &saved_title, current_id);
+                let content = editor_ref.get_content();
                 let mut save_note = (*note_template).clone();
                 save_note.id = current_id;
                 save_note.title = title;
@@ -587,16 +762,52 @@ impl NoteWindow {
                 save_note.star_color = star_color_rc.borrow().clone();
                 save_note.always_on_top = *is_pinned.borrow();
                 save_note.is_visible = visible;
-                if current_id.is_some() {
-                    if let Err(e) = db.update_note(&save_note) {
-                        eprintln!("Error updating note: {}", e);
+                if let Some(id) = current_id {
+                    match db.update_note(&save_note) {
+                        Ok(()) => crate::dbus_service::emit_note_changed(&app, id, &save_note.title),
+                        Err(e) => eprintln!("Error updating note: {}", e),
+                    }
+                    // Closing a tangle always leaves a revision of its final state
+                    if let Err(e) = db.create_revision(id, &save_note.title, &save_note.content) {
+                        eprintln!("Error saving revision: {}", e);
+                    }
+                } else {
+                    match db.create_note(&save_note) {
+                        Ok(id) => crate::dbus_service::emit_note_changed(&app, id, &save_note.title),
+                        Err(e) => eprintln!("Error creating note: {}", e),
                     }
-                } else if let Err(e) = db.create_note(&save_note) {
-                    eprintln!("Error creating note: {}", e);
                 }
             })
         };
 
+        // Apply a `ContentEdit` made elsewhere (tangle map, backlinks pane):
+        // save what's on screen, edit it in the database, and show the result
+        {
+            let edit_action = gio::SimpleAction::new("edit-content", Some(glib::VariantTy::STRING));
+            let editor_e = editor_ref.clone();
+            let note_id_e = note_id.clone();
+            let title_e = title_entry.clone();
+            let db_e = db.clone();
+            let app_e = app.clone();
+            let do_sync_e = do_save_sync.clone();
+            edit_action.connect_activate(move |_, param| {
+                let Some(json) = param.and_then(|p| p.get::<String>()) else { return };
+                let Ok(edit) = serde_json::from_str::<ContentEdit>(&json) else { return };
+                // Leaves a revision of the state being edited
+                do_sync_e(true);
+                let Some(id) = *note_id_e.borrow() else { return };
+                match db_e.apply_content_edit(id, &edit) {
+                    Ok(Some(content)) => {
+                        editor_e.set_content(&content);
+                        crate::dbus_service::emit_note_changed(&app_e, id, &title_e.text());
+                    }
+                    Ok(None) => {}
+                    Err(e) => eprintln!("Error editing tangle: {}", e),
+                }
+            });
+            note_actions.add_action(&edit_action);
+        }
+
         // Close — save synchronously with is_visible=false
         let do_sync_close = do_save_sync.clone();
         let cached_geo_btn = cached_geo.clone();
@@ -789,6 +1000,71 @@ pub fn editor_ref_buffer(window: &ApplicationWindow) -> Option<gtk4::TextBuffer>
     None
 }
 
+/// The title to save. A changed title is renamed in the database first,
+/// which rewrites the stored links to it; `saved_title` and the links in open
+/// windows only follow once that succeeded. If the rename fails the entry
+/// goes back to the old title; a title already used by another tangle is
+/// flagged on the entry and not saved.
+fn resolve_title_change(
+    app: &gtk4::Application,
+    db: &Database,
+    title_entry: &Entry,
+    saved_title: &RefCell<String>,
+    note_id: Option<i64>,
+) -> String {
+    let title = title_entry.text().to_string();
+    let old_title = saved_title.borrow().clone();
+    let id = match note_id {
+        Some(id) if title != old_title => id,
+        _ => {
+            title_entry.remove_css_class("error");
+            title_entry.set_tooltip_text(None);
+            *saved_title.borrow_mut() = title.clone();
+            return title;
+        }
+    };
+    if title.trim().is_empty() {
+        return old_title;
+    }
+    match db.get_note_by_title(&title) {
+        Ok(Some(other)) if other.id != Some(id) => {
+            title_entry.add_css_class("error");
+            title_entry.set_tooltip_text(Some("Another tangle already has this title"));
+            return old_title;
+        }
+        Err(e) => {
+            eprintln!("Error checking title '{}': {}", title, e);
+            return old_title;
+        }
+        _ => {}
+    }
+    title_entry.remove_css_class("error");
+    title_entry.set_tooltip_text(None);
+    if let Err(e) = db.rename_note(id, &title) {
+        eprintln!("Error renaming tangle '{}': {}", old_title, e);
+        title_entry.set_text(&old_title);
+        return old_title;
+    }
+    *saved_title.borrow_mut() = title.clone();
+    rename_links_in_open_windows(app, &old_title, &title);
+    title
+}
+
+/// Point `tangle::` spans at a renamed note in every open note window,
+/// so unsaved buffers don't write the old title back on their next save.
+fn rename_links_in_open_windows(app: &gtk4::Application, old_title: &str, new_title: &str) {
+    for win in app.windows() {
+        if !win.has_css_class("note-window") {
+            continue;
+        }
+        if let Ok(app_win) = win.downcast::<ApplicationWindow>() {
+            if let Some(buf) = editor_ref_buffer(&app_win) {
+                crate::rich_editor::rename_tangle_tags(&buf, old_title, new_title);
+            }
+        }
+    }
+}
+
 fn query_wmctrl_geometry(win_title: &str) -> Option<(i32, i32, i32, i32)> {
     let output = std::process::Command::new("wmctrl")
         .args(["-l", "-G"])
@@ -822,73 +1098,139 @@ fn set_window_above(window: &ApplicationWindow, above: bool) {
         .spawn();
 }
 
+/// An origin tangle in the backlinks pane: id, title and the paragraphs
+/// that link here.
+struct Backlink {
+    id: i64,
+    title: String,
+    contexts: Vec<String>,
+}
+
 fn refresh_backlinks_pane(
     backlinks_box: &gtk4::Box,
     db: &Database,
-    title: &str,
+    note_id: Option<i64>,
+    accent: String,
     app: &gtk4::Application,
+    collapsed: &Rc<RefCell<HashSet<i64>>>,
 ) -> Option<glib::SourceId> {
-    if title.is_empty() || title == "New Tangle" {
+    let Some(note_id) = note_id else {
         backlinks_box.set_visible(false);
         return None;
-    }
+    };
 
     // DB query on background thread, UI update on main thread via channel
     let db_bg = db.clone();
-    let title = title.to_string();
-    let (tx, rx) = std::sync::mpsc::channel::<Vec<String>>();
+    let (tx, rx) = std::sync::mpsc::channel::<(String, Vec<Backlink>, Vec<(i64, String)>)>();
 
     std::thread::spawn(move || {
-        let linking_notes = db_bg.get_notes_linking_to(&title).unwrap_or_default();
+        let Ok(Some(note)) = db_bg.get_note(note_id) else {
+            let _ = tx.send((String::new(), Vec::new(), Vec::new()));
+            return;
+        };
+        let linking_notes = db_bg.get_backlinks(note_id).unwrap_or_default();
         // Dedup with HashSet
-        let mut seen = std::collections::HashSet::new();
-        let titles: Vec<String> = linking_notes.iter()
+        let mut seen = HashSet::new();
+        let backlinks: Vec<Backlink> = linking_notes.into_iter()
             .filter_map(|n| {
-                if seen.insert(n.title.clone()) {
-                    Some(n.title.clone())
-                } else {
-                    None
+                let id = n.id?;
+                if !seen.insert(id) {
+                    return None;
                 }
+                let contexts = crate::database::tangle_link_contexts(&n.content, &note.title);
+                Some(Backlink { id, title: n.title, contexts })
             })
             .collect();
-        let _ = tx.send(titles);
+        // Tangles that name this one in their text but don't link to it
+        let mentions: Vec<(i64, String)> = db_bg
+            .get_unlinked_mentions(note_id, &note.title)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|n| Some((n.id?, n.title)))
+            .collect();
+        let _ = tx.send((note.title, backlinks, mentions));
     });
 
     let bl_box = backlinks_box.clone();
     let db = db.clone();
     let app = app.clone();
+    let collapsed = collapsed.clone();
     let source_id = glib::timeout_add_local(std::time::Duration::from_millis(50), move || {
         match rx.try_recv() {
-            Ok(titles) => {
+            Ok((note_title, backlinks, mentions)) => {
                 while let Some(child) = bl_box.first_child() {
                     bl_box.remove(&child);
                 }
 
-                if titles.is_empty() {
+                if backlinks.is_empty() && mentions.is_empty() {
                     bl_box.set_visible(false);
                     return glib::ControlFlow::Break;
                 }
 
                 bl_box.set_visible(true);
 
-                let label = Label::builder()
-                    .label("Origin Tangles:")
-                    .css_classes(["backlinks-label"])
-                    .build();
-                bl_box.append(&label);
+                if !backlinks.is_empty() {
+                    let label = Label::builder()
+                        .label("Origin Tangles:")
+                        .xalign(0.0)
+                        .css_classes(["backlinks-label"])
+                        .build();
+                    bl_box.append(&label);
 
-                for note_title in &titles {
-                    let btn = Button::builder()
-                        .label(note_title)
-                        .css_classes(["backlink-btn"])
+                    let sources = gtk4::Box::new(gtk4::Orientation::Vertical, 4);
+                    for backlink in &backlinks {
+                        sources.append(&backlink_source(backlink, &accent, &db, &app, &collapsed));
+                    }
+                    let scrolled = gtk4::ScrolledWindow::builder()
+                        .child(&sources)
+                        .hscrollbar_policy(gtk4::PolicyType::Never)
+                        .max_content_height(180)
+                        .propagate_natural_height(true)
                         .build();
-                    let db_ref = db.clone();
-                    let app_ref = app.clone();
-                    let nt = note_title.clone();
-                    btn.connect_clicked(move |_| {
-                        crate::rich_editor::open_tangle_note(&db_ref, &app_ref, &nt);
-                    });
-                    bl_box.append(&btn);
+                    bl_box.append(&scrolled);
+                }
+
+                if !mentions.is_empty() {
+                    let row = gtk4::Box::new(gtk4::Orientation::Horizontal, 6);
+                    let label = Label::builder()
+                        .label("Unlinked Mentions:")
+                        .css_classes(["backlinks-label"])
+                        .build();
+                    row.append(&label);
+                    for (source_id, source_title) in &mentions {
+                        let pair = gtk4::Box::builder()
+                            .orientation(gtk4::Orientation::Horizontal)
+                            .css_classes(["linked"])
+                            .build();
+                        let open_btn = Button::builder()
+                            .label(source_title)
+                            .css_classes(["backlink-btn", "unlinked-mention"])
+                            .build();
+                        let db_ref = db.clone();
+                        let app_ref = app.clone();
+                        let st = source_title.clone();
+                        open_btn.connect_clicked(move |_| {
+                            crate::rich_editor::open_tangle_note(&db_ref, &app_ref, &st);
+                        });
+                        let link_btn = Button::builder()
+                            .label("Link it")
+                            .tooltip_text(format!("Turn the mention in \"{}\" into a link", source_title))
+                            .css_classes(["backlink-btn", "link-it-btn"])
+                            .build();
+                        let db_ref = db.clone();
+                        let app_ref = app.clone();
+                        let source_id = *source_id;
+                        let edit = ContentEdit::LinkMention { title: note_title.clone() };
+                        // The pane refreshes once the edit is saved and
+                        // announced through `emit_note_changed`
+                        link_btn.connect_clicked(move |_| {
+                            apply_content_edit(&app_ref, &db_ref, source_id, &edit);
+                        });
+                        pair.append(&open_btn);
+                        pair.append(&link_btn);
+                        row.append(&pair);
+                    }
+                    bl_box.append(&row);
                 }
                 glib::ControlFlow::Break
             }
@@ -899,6 +1241,106 @@ fn refresh_backlinks_pane(
     Some(source_id)
 }
 
+/// One origin tangle: a button that opens it, and below it the paragraphs
+/// that link here with the link text highlighted. The arrow folds the
+/// paragraphs away; that choice survives refreshes of the pane.
+fn backlink_source(
+    backlink: &Backlink,
+    accent: &str,
+    db: &Database,
+    app: &gtk4::Application,
+    collapsed: &Rc<RefCell<HashSet<i64>>>,
+) -> gtk4::Box {
+    let expanded = !collapsed.borrow().contains(&backlink.id);
+    let arrow = |expanded: bool| if expanded { "pan-down-symbolic" } else { "pan-end-symbolic" };
+
+    let header = gtk4::Box::new(gtk4::Orientation::Horizontal, 2);
+    let toggle = Button::builder()
+        .icon_name(arrow(expanded))
+        .css_classes(["flat", "backlink-toggle"])
+        .tooltip_text("Show or hide where it links here")
+        .sensitive(!backlink.contexts.is_empty())
+        .build();
+    let btn = Button::builder()
+        .label(&backlink.title)
+        .css_classes(["backlink-btn"])
+        .build();
+    let db_ref = db.clone();
+    let app_ref = app.clone();
+    let title = backlink.title.clone();
+    btn.connect_clicked(move |_| {
+        crate::rich_editor::open_tangle_note(&db_ref, &app_ref, &title);
+    });
+    header.append(&toggle);
+    header.append(&btn);
+
+    let contexts = gtk4::Box::new(gtk4::Orientation::Vertical, 2);
+    for context in &backlink.contexts {
+        let label = Label::builder()
+            .use_markup(true)
+            .label(crate::theme::highlight_markup(context, accent))
+            .xalign(0.0)
+            .wrap(true)
+            .wrap_mode(gtk4::pango::WrapMode::WordChar)
+            .css_classes(["backlink-context"])
+            .build();
+        contexts.append(&label);
+    }
+    let revealer = gtk4::Revealer::builder()
+        .child(&contexts)
+        .reveal_child(expanded && !backlink.contexts.is_empty())
+        .build();
+
+    let collapsed = collapsed.clone();
+    let id = backlink.id;
+    let revealer_t = revealer.clone();
+    toggle.connect_clicked(move |toggle| {
+        let expanded = !revealer_t.reveals_child();
+        revealer_t.set_reveal_child(expanded);
+        toggle.set_icon_name(arrow(expanded));
+        if expanded {
+            collapsed.borrow_mut().remove(&id);
+        } else {
+            collapsed.borrow_mut().insert(id);
+        }
+    });
+
+    let block = gtk4::Box::new(gtk4::Orientation::Vertical, 0);
+    block.append(&header);
+    block.append(&revealer);
+    block
+}
+
+/// Apply `edit` to a tangle's content: through its window when it's open, so
+/// the editor shows the change and keeps it, otherwise in the database after
+/// saving a revision.
+pub fn apply_content_edit(app: &gtk4::Application, db: &Database, id: i64, edit: &ContentEdit) {
+    let class = format!("note-{}", id);
+    if let Some(win) = app.windows().into_iter().find(|w| w.has_css_class(&class)) {
+        let param = serde_json::to_string(edit).unwrap_or_default().to_variant();
+        if let Err(e) = win.activate_action("note.edit-content", Some(&param)) {
+            eprintln!("Error editing tangle: {}", e);
+        }
+        return;
+    }
+    let note = match db.get_note(id) {
+        Ok(Some(note)) => note,
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("Error editing tangle: {}", e);
+            return;
+        }
+    };
+    let result = db
+        .create_revision(id, &note.title, &note.content)
+        .and_then(|_| db.apply_content_edit(id, edit));
+    match result {
+        Ok(Some(_)) => crate::dbus_service::emit_note_changed(app, id, &note.title),
+        Ok(None) => {}
+        Err(e) => eprintln!("Error editing tangle: {}", e),
+    }
+}
+
 fn determine_edge(x: f64, y: f64, w: f64, h: f64, margin: f64) -> Option<gtk4::gdk::SurfaceEdge> {
     let left = x < margin;
     let right = x > w - margin;
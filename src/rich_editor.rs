@@ -4,11 +4,7 @@ use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
-use html5ever::tokenizer::{
-    BufferQueue, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
-};
-use html5ever::tendril::StrTendril;
-
+use crate::html::{escape_html, escape_html_attr, tokenize_html, HtmlToken};
 use crate::pickers;
 use crate::database::{Database, Note};
 
@@ -41,31 +37,7 @@ impl RichEditor {
         let table = buffer.tag_table();
         let own_title: Rc<RefCell<String>> = Rc::new(RefCell::new(title.to_string()));
 
-        // Pre-create formatting tags
-        let bold = TextTag::builder().name("bold").weight(700).build();
-        let italic = TextTag::builder().name("italic").style(gtk4::pango::Style::Italic).build();
-        let underline = TextTag::builder().name("underline").underline(gtk4::pango::Underline::Single).build();
-        let strikethrough = TextTag::builder().name("strikethrough").strikethrough(true).build();
-
-        let h1 = TextTag::builder().name("h1").scale(2.0).weight(700).build();
-        let h2 = TextTag::builder().name("h2").scale(1.5).weight(700).build();
-        let h3 = TextTag::builder().name("h3").scale(1.25).weight(700).build();
-        let h4 = TextTag::builder().name("h4").scale(1.1).weight(700).build();
-
-        let bullet_list = TextTag::builder().name("bullet-list").left_margin(24).build();
-        let numbered_list = TextTag::builder().name("numbered-list").left_margin(24).build();
-        let code = TextTag::builder()
-            .name("code")
-            .family("monospace")
-            .background("#2d2d2d")
-            .foreground("#e0e0e0")
-            .left_margin(16)
-            .right_margin(16)
-            .build();
-
-        for tag in [&bold, &italic, &underline, &strikethrough, &h1, &h2, &h3, &h4, &bullet_list, &numbered_list, &code] {
-            table.add(tag);
-        }
+        add_format_tags(&table);
 
         let widget = Box::builder()
             .orientation(gtk4::Orientation::Vertical)
@@ -328,6 +300,9 @@ impl RichEditor {
         });
         text_view.add_controller(key_controller);
 
+        // Typing [[ opens title completion; set up before the Enter handler so it sees Enter first
+        setup_wikilink_completion(&text_view, &buffer, &db, &inhibit_changed);
+
         // Enter key handler for list continuation — CAPTURE phase to intercept before default handler
         let enter_controller = gtk4::EventControllerKey::new();
         enter_controller.set_propagation_phase(gtk4::PropagationPhase::Capture);
@@ -342,7 +317,7 @@ impl RichEditor {
         });
         text_view.add_controller(enter_controller);
 
-        // Hover tooltips for tangle/web links
+        // Hover tooltips for web links; tangle links get a preview popover instead
         let tooltip_motion = gtk4::EventControllerMotion::new();
         let tv_tooltip = text_view.clone();
         tooltip_motion.connect_motion(move |_, x, y| {
@@ -350,11 +325,6 @@ impl RichEditor {
             if let Some(iter) = tv_tooltip.iter_at_location(bx, by) {
                 for tag in iter.tags() {
                     if let Some(name) = tag.name() {
-                        let name = name.to_string();
-                        if let Some(title) = name.strip_prefix("tangle::") {
-                            tv_tooltip.set_tooltip_text(Some(&format!("Tangle: {}", title)));
-                            return;
-                        }
                         if let Some(url) = name.strip_prefix("link::") {
                             tv_tooltip.set_tooltip_text(Some(&format!("Link: {}", url)));
                             return;
@@ -366,6 +336,8 @@ impl RichEditor {
         });
         text_view.add_controller(tooltip_motion);
 
+        setup_tangle_preview(&text_view, &db, &app);
+
         // Click handler for web links — opens in system browser
         let link_click = gtk4::GestureClick::builder().button(1).build();
         link_click.set_propagation_phase(gtk4::PropagationPhase::Capture);
@@ -578,7 +550,7 @@ impl RichEditor {
                 inhibit_toggle.set(true);
                 buf_toggle.set_text("");
                 if !html.is_empty() {
-                    deserialize_html(&buf_toggle, &tv_toggle, &html, &im_toggle);
+                    deserialize_html(&buf_toggle, &tv_toggle, &html, &im_toggle, None);
                 }
                 inhibit_toggle.set(false);
                 source_scrolled_ref.set_visible(false);
@@ -643,7 +615,7 @@ impl RichEditor {
         self.inhibit_changed.set(true);
         self.buffer.set_text("");
         if !html.is_empty() {
-            deserialize_html(&self.buffer, &self.text_view, html, &self.image_map);
+            deserialize_html(&self.buffer, &self.text_view, html, &self.image_map, None);
         }
         self.inhibit_changed.set(false);
     }
@@ -742,6 +714,317 @@ fn get_or_create_tag(table: &gtk4::TextTagTable, name: &str) -> TextTag {
     tag
 }
 
+/// The editor's named formatting tags, so deserialized HTML renders styled.
+fn add_format_tags(table: &gtk4::TextTagTable) {
+    let bold = TextTag::builder().name("bold").weight(700).build();
+    let italic = TextTag::builder().name("italic").style(gtk4::pango::Style::Italic).build();
+    let underline = TextTag::builder().name("underline").underline(gtk4::pango::Underline::Single).build();
+    let strikethrough = TextTag::builder().name("strikethrough").strikethrough(true).build();
+
+    let h1 = TextTag::builder().name("h1").scale(2.0).weight(700).build();
+    let h2 = TextTag::builder().name("h2").scale(1.5).weight(700).build();
+    let h3 = TextTag::builder().name("h3").scale(1.25).weight(700).build();
+    let h4 = TextTag::builder().name("h4").scale(1.1).weight(700).build();
+
+    let bullet_list = TextTag::builder().name("bullet-list").left_margin(24).build();
+    let numbered_list = TextTag::builder().name("numbered-list").left_margin(24).build();
+    let code = TextTag::builder()
+        .name("code")
+        .family("monospace")
+        .background("#2d2d2d")
+        .foreground("#e0e0e0")
+        .left_margin(16)
+        .right_margin(16)
+        .build();
+
+    for tag in [&bold, &italic, &underline, &strikethrough, &h1, &h2, &h3, &h4, &bullet_list, &numbered_list, &code] {
+        table.add(tag);
+    }
+}
+
+// ── Tangle link previews ───────────────────────────────────────────
+
+/// How long the pointer rests on a tangle link before its preview opens,
+/// and how long the preview survives the pointer leaving it.
+const PREVIEW_SHOW_DELAY_MS: u64 = 500;
+const PREVIEW_HIDE_DELAY_MS: u64 = 300;
+/// Blocks (paragraphs, headings, list items) shown in a preview.
+const PREVIEW_BLOCKS: usize = 6;
+/// Widest an image is drawn in a preview.
+const PREVIEW_IMAGE_WIDTH: i32 = 240;
+
+/// Title of the `tangle::` span under widget coordinates (`x`, `y`).
+fn tangle_title_at(text_view: &TextView, x: f64, y: f64) -> Option<String> {
+    let (bx, by) = text_view.window_to_buffer_coords(gtk4::TextWindowType::Widget, x as i32, y as i32);
+    let iter = text_view.iter_at_location(bx, by)?;
+    iter.tags()
+        .into_iter()
+        .find_map(|tag| tag.name()?.strip_prefix("tangle::").map(str::to_string))
+}
+
+/// Resting on a tangle link opens a read-only preview of that tangle under
+/// the pointer. It stays open while the pointer is on the link or the
+/// preview itself, so its Open button can be reached.
+fn setup_tangle_preview(text_view: &TextView, db: &Database, app: &gtk4::Application) {
+    let shown: Rc<RefCell<Option<(String, gtk4::Popover)>>> = Rc::new(RefCell::new(None));
+    let show_timer: Rc<RefCell<Option<(String, glib::SourceId)>>> = Rc::new(RefCell::new(None));
+    let hide_timer: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+    // Pointer events inside the popover also reach the text view under it
+    let over_popover = Rc::new(Cell::new(false));
+
+    let cancel_hide: Rc<dyn Fn()> = {
+        let hide_timer = hide_timer.clone();
+        Rc::new(move || {
+            if let Some(id) = hide_timer.borrow_mut().take() {
+                id.remove();
+            }
+        })
+    };
+    let schedule_hide: Rc<dyn Fn()> = {
+        let hide_timer = hide_timer.clone();
+        let shown = shown.clone();
+        let over_popover = over_popover.clone();
+        Rc::new(move || {
+            if over_popover.get() || shown.borrow().is_none() || hide_timer.borrow().is_some() {
+                return;
+            }
+            let shown = shown.clone();
+            let timer_ref = hide_timer.clone();
+            let id = glib::timeout_add_local_once(std::time::Duration::from_millis(PREVIEW_HIDE_DELAY_MS), move || {
+                timer_ref.borrow_mut().take();
+                if let Some((_, popover)) = shown.borrow_mut().take() {
+                    popover.popdown();
+                }
+            });
+            *hide_timer.borrow_mut() = Some(id);
+        })
+    };
+    let cancel_show = {
+        let show_timer = show_timer.clone();
+        move || {
+            if let Some((_, id)) = show_timer.borrow_mut().take() {
+                id.remove();
+            }
+        }
+    };
+
+    let motion = gtk4::EventControllerMotion::new();
+    let tv = text_view.clone();
+    let db = db.clone();
+    let app = app.clone();
+    let shown_m = shown.clone();
+    let show_timer_m = show_timer.clone();
+    let cancel_hide_m = cancel_hide.clone();
+    let schedule_hide_m = schedule_hide.clone();
+    let cancel_show_m = cancel_show.clone();
+    let over_popover_m = over_popover.clone();
+    motion.connect_motion(move |_, x, y| {
+        if over_popover_m.get() {
+            return;
+        }
+        let Some(title) = tangle_title_at(&tv, x, y) else {
+            cancel_show_m();
+            schedule_hide_m();
+            return;
+        };
+        if shown_m.borrow().as_ref().is_some_and(|(t, _)| *t == title) {
+            cancel_hide_m();
+            return;
+        }
+        if show_timer_m.borrow().as_ref().is_some_and(|(t, _)| *t == title) {
+            return;
+        }
+        cancel_show_m();
+
+        let tv = tv.clone();
+        let db = db.clone();
+        let app = app.clone();
+        let shown = shown_m.clone();
+        let timer_ref = show_timer_m.clone();
+        let cancel_hide = cancel_hide_m.clone();
+        let schedule_hide = schedule_hide_m.clone();
+        let title_t = title.clone();
+        let over_popover = over_popover_m.clone();
+        let id = glib::timeout_add_local_once(std::time::Duration::from_millis(PREVIEW_SHOW_DELAY_MS), move || {
+            timer_ref.borrow_mut().take();
+            cancel_hide();
+            if let Some((_, old)) = shown.borrow_mut().take() {
+                old.popdown();
+            }
+            let popover = gtk4::Popover::builder()
+                .autohide(false)
+                .has_arrow(true)
+                .css_classes(["tangle-preview"])
+                .build();
+            popover.set_parent(&tv);
+            popover.set_pointing_to(Some(&gtk4::gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+            popover.set_child(Some(&build_tangle_preview(&db, &app, &title_t, &popover)));
+            popover.connect_closed(|p| p.unparent());
+
+            // Keep it open while the pointer is over it
+            let hover = gtk4::EventControllerMotion::new();
+            let cancel_hide_p = cancel_hide.clone();
+            let over_enter = over_popover.clone();
+            hover.connect_enter(move |_, _, _| {
+                over_enter.set(true);
+                cancel_hide_p();
+            });
+            let schedule_hide_p = schedule_hide.clone();
+            let over_leave = over_popover.clone();
+            hover.connect_leave(move |_| {
+                over_leave.set(false);
+                schedule_hide_p();
+            });
+            let over_closed = over_popover.clone();
+            popover.connect_closed(move |_| over_closed.set(false));
+            popover.add_controller(hover);
+
+            popover.popup();
+            *shown.borrow_mut() = Some((title_t, popover));
+        });
+        *show_timer_m.borrow_mut() = Some((title, id));
+    });
+    motion.connect_leave(move |_| {
+        cancel_show();
+        schedule_hide();
+    });
+    text_view.add_controller(motion);
+}
+
+/// The preview's contents: star and title, when it was last updated, its
+/// first few blocks with images, and a button to open it. The tangle is
+/// loaded in the background and filled in when it arrives.
+fn build_tangle_preview(db: &Database, app: &gtk4::Application, title: &str, popover: &gtk4::Popover) -> Box {
+    let vbox = Box::builder()
+        .orientation(gtk4::Orientation::Vertical)
+        .spacing(6)
+        .margin_top(6)
+        .margin_bottom(6)
+        .margin_start(6)
+        .margin_end(6)
+        .width_request(320)
+        .build();
+
+    let header = Box::new(gtk4::Orientation::Horizontal, 6);
+    header.append(
+        &Label::builder()
+            .use_markup(true)
+            .label(format!("<b>{}</b>", glib::markup_escape_text(title)))
+            .xalign(0.0)
+            .hexpand(true)
+            .ellipsize(gtk4::pango::EllipsizeMode::End)
+            .build(),
+    );
+    vbox.append(&header);
+    let body = Box::new(gtk4::Orientation::Vertical, 6);
+    vbox.append(&body);
+
+    let db_bg = db.clone();
+    let title_bg = title.to_string();
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let note = db_bg.get_note_by_title(&title_bg).unwrap_or_else(|e| {
+            eprintln!("Error loading tangle preview: {}", e);
+            None
+        });
+        let _ = tx.send(note);
+    });
+    glib::timeout_add_local(std::time::Duration::from_millis(30), move || {
+        match rx.try_recv() {
+            Ok(note) => {
+                fill_tangle_preview(&header, &body, note);
+                glib::ControlFlow::Break
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+            Err(_) => glib::ControlFlow::Break,
+        }
+    });
+
+    let open_btn = Button::builder()
+        .label("Open")
+        .halign(gtk4::Align::End)
+        .css_classes(["save-button"])
+        .build();
+    let db = db.clone();
+    let app = app.clone();
+    let title = title.to_string();
+    let popover = popover.clone();
+    open_btn.connect_clicked(move |_| {
+        popover.popdown();
+        open_tangle_note(&db, &app, &title);
+    });
+    vbox.append(&open_btn);
+    vbox
+}
+
+fn fill_tangle_preview(header: &Box, body: &Box, note: Option<Note>) {
+    let Some(note) = note else {
+        body.append(
+            &Label::builder()
+                .label("No tangle by this name yet. Opening the link creates it.")
+                .xalign(0.0)
+                .wrap(true)
+                .css_classes(["dim-label"])
+                .build(),
+        );
+        return;
+    };
+
+    if let Some(color) = note.star_color.as_deref() {
+        header.prepend(
+            &Label::builder()
+                .use_markup(true)
+                .label(format!("<span foreground=\"{}\">\u{2605}</span>", glib::markup_escape_text(color)))
+                .build(),
+        );
+    }
+
+    let updated = chrono::DateTime::parse_from_rfc3339(&note.updated_at)
+        .map(|dt| dt.with_timezone(&chrono::Local).format("Updated %b %d, %Y  %H:%M").to_string())
+        .unwrap_or_default();
+    body.append(&Label::builder().label(updated).xalign(0.0).css_classes(["dim-label"]).build());
+
+    let (html, truncated) = preview_html(&note.content);
+    let buffer = TextBuffer::new(None);
+    add_format_tags(&buffer.tag_table());
+    let preview_view = TextView::builder()
+        .buffer(&buffer)
+        .editable(false)
+        .cursor_visible(false)
+        .wrap_mode(gtk4::WrapMode::WordChar)
+        .css_classes(["tangle-preview-text"])
+        .build();
+    let image_map = Rc::new(RefCell::new(HashMap::new()));
+    deserialize_html(&buffer, &preview_view, &html, &image_map, Some(PREVIEW_IMAGE_WIDTH));
+    if truncated {
+        let mut end = buffer.end_iter();
+        buffer.insert(&mut end, "\n\u{2026}");
+    }
+    let scrolled = ScrolledWindow::builder()
+        .child(&preview_view)
+        .hscrollbar_policy(gtk4::PolicyType::Never)
+        .max_content_height(260)
+        .propagate_natural_height(true)
+        .build();
+    body.append(&scrolled);
+}
+
+/// The first `PREVIEW_BLOCKS` blocks of note HTML, and whether anything
+/// was cut.
+fn preview_html(html: &str) -> (String, bool) {
+    use once_cell::sync::Lazy;
+    use regex::Regex;
+
+    static BLOCK_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)</(p|h[1-4]|li|pre)>").unwrap());
+
+    // Serialized content has one block per line; keep lines up to the last wanted block end
+    let cut = BLOCK_RE.find_iter(html).nth(PREVIEW_BLOCKS - 1).map(|m| m.end());
+    match cut {
+        Some(end) if !html[end..].trim().is_empty() => (html[..end].to_string(), true),
+        _ => (html.to_string(), false),
+    }
+}
+
 // ── Headings ───────────────────────────────────────────────────────
 
 fn apply_heading(buffer: &TextBuffer, tag_name: &str) {
@@ -1272,11 +1555,38 @@ fn update_tangle_actions(
     }
     tangle_menu.append(Some("Link to Tangle"), Some("tangle.link-tangle"));
     tangle_menu.append(Some("Create Tangle"), Some("tangle.create-tangle"));
+    let note_menu = gtk4::gio::Menu::new();
+    note_menu.append(Some("Local Graph..."), Some("note.local-graph"));
+    note_menu.append(Some("Export as Markdown..."), Some("note.export-markdown"));
     let extra_menu = gtk4::gio::Menu::new();
     extra_menu.append_section(Some("Tangles"), &tangle_menu);
+    extra_menu.append_section(None, &note_menu);
     text_view.set_extra_menu(Some(&extra_menu));
 }
 
+/// Title named by a `tangle://Title` URI from outside the app (command line,
+/// another program). Percent-encoding is decoded, so `tangle://Meeting%20Notes`
+/// and `tangle://Meeting Notes` name the same tangle. Returns None for other
+/// schemes or an empty title.
+pub fn parse_tangle_uri(uri: &str) -> Option<String> {
+    let uri = uri.trim();
+    let scheme_end = uri.find(':')?;
+    if !uri[..scheme_end].eq_ignore_ascii_case("tangle") {
+        return None;
+    }
+    let rest = &uri[scheme_end + 1..];
+    let rest = rest.strip_prefix("//").unwrap_or(rest);
+    let title = glib::Uri::unescape_string(rest, None::<&str>)
+        .map(|t| t.to_string())
+        .unwrap_or_else(|| rest.to_string());
+    let title = title.trim();
+    if title.is_empty() {
+        None
+    } else {
+        Some(title.to_string())
+    }
+}
+
 /// Open a note by title (for tangle navigation).
 /// If the note is already open in a window, focus it and flash its border.
 pub fn open_tangle_note(db: &Database, app: &gtk4::Application, title: &str) {
@@ -1311,6 +1621,33 @@ pub fn open_tangle_note(db: &Database, app: &gtk4::Application, title: &str) {
     nw.present();
 }
 
+/// Retarget every `tangle::{old}` span in the buffer to `tangle::{new}`,
+/// leaving the visible text untouched. Used when a tangle is renamed.
+pub fn rename_tangle_tags(buffer: &TextBuffer, old_title: &str, new_title: &str) {
+    let table = buffer.tag_table();
+    let old_tag = match table.lookup(&format!("tangle::{}", old_title)) {
+        Some(t) => t,
+        None => return,
+    };
+
+    let mut ranges: Vec<(i32, i32)> = Vec::new();
+    let mut iter = buffer.start_iter();
+    loop {
+        if !iter.starts_tag(Some(&old_tag)) && !iter.forward_to_tag_toggle(Some(&old_tag)) {
+            break;
+        }
+        let start = iter.offset();
+        iter.forward_to_tag_toggle(Some(&old_tag));
+        ranges.push((start, iter.offset()));
+    }
+
+    let new_tag = get_or_create_tag(&table, &format!("tangle::{}", new_title));
+    for (start, end) in ranges {
+        buffer.apply_tag(&new_tag, &buffer.iter_at_offset(start), &buffer.iter_at_offset(end));
+    }
+    table.remove(&old_tag);
+}
+
 /// Briefly flash a highlight border on a window to draw attention.
 fn flash_window_border(window: &gtk4::Window) {
     window.add_css_class("tangle-flash");
@@ -1321,6 +1658,364 @@ fn flash_window_border(window: &gtk4::Window) {
     });
 }
 
+// ── [[ completion ─────────────────────────────────────────────────
+
+/// Most titles the completion popover lists.
+const WIKILINK_MAX_ROWS: usize = 8;
+/// A completion is abandoned once its query grows past this many characters.
+const WIKILINK_MAX_QUERY: usize = 100;
+
+/// A `[[` being completed: where it starts, every title to match against,
+/// and the titles behind the popover's rows, in order.
+struct WikilinkSession {
+    start: gtk4::TextMark,
+    titles: Vec<String>,
+    rows: Vec<String>,
+}
+
+struct WikilinkCompletion {
+    text_view: TextView,
+    buffer: TextBuffer,
+    db: Database,
+    popover: gtk4::Popover,
+    list: gtk4::ListBox,
+    session: RefCell<Option<WikilinkSession>>,
+    /// Titles from the last load, matched against while a fresh list loads
+    titles: RefCell<Vec<String>>,
+    /// Set while the completion edits the buffer itself
+    committing: Cell<bool>,
+    char_count: Cell<i32>,
+}
+
+impl WikilinkCompletion {
+    /// Text typed after the `[[`, or None when the cursor has left it.
+    fn query(&self) -> Option<String> {
+        let session = self.session.borrow();
+        let session = session.as_ref()?;
+        let start = self.buffer.iter_at_mark(&session.start);
+        let mut query_start = start;
+        query_start.forward_chars(2);
+        let cursor = self.buffer.iter_at_mark(&self.buffer.get_insert());
+        if self.buffer.text(&start, &query_start, false) != "[[" || cursor.offset() < query_start.offset() {
+            return None;
+        }
+        let query = self.buffer.text(&query_start, &cursor, false).to_string();
+        let abandoned = query.contains('\n') || query.contains("[[") || query.chars().count() > WIKILINK_MAX_QUERY;
+        (!abandoned).then_some(query)
+    }
+
+    /// Begin completing at `at` with the cached titles, and reload them in
+    /// the background so tangles made since show up.
+    fn start(self: &Rc<Self>, at: &TextIter) {
+        let start = self.buffer.create_mark(None, at, true);
+        let titles = self.titles.borrow().clone();
+        *self.session.borrow_mut() = Some(WikilinkSession { start: start.clone(), titles, rows: Vec::new() });
+
+        let db = self.db.clone();
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let titles = db.get_all_note_titles().unwrap_or_else(|e| {
+                eprintln!("Error loading titles for completion: {}", e);
+                Vec::new()
+            });
+            let _ = tx.send(titles);
+        });
+        let comp = self.clone();
+        glib::timeout_add_local(std::time::Duration::from_millis(30), move || {
+            match rx.try_recv() {
+                Ok(titles) => {
+                    *comp.titles.borrow_mut() = titles.clone();
+                    let same_session = comp.session.borrow().as_ref().is_some_and(|s| s.start == start);
+                    if same_session {
+                        if let Some(session) = comp.session.borrow_mut().as_mut() {
+                            session.titles = titles;
+                        }
+                        comp.update();
+                    }
+                    glib::ControlFlow::Break
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+                Err(_) => glib::ControlFlow::Break,
+            }
+        });
+    }
+
+    fn finish(&self) {
+        if let Some(session) = self.session.borrow_mut().take() {
+            self.buffer.delete_mark(&session.start);
+        }
+        self.popover.popdown();
+    }
+
+    /// Re-match after an edit or cursor move; a typed `]]` completes with
+    /// whatever is between the brackets.
+    fn update(self: &Rc<Self>) {
+        if self.session.borrow().is_none() {
+            return;
+        }
+        let Some(query) = self.query() else {
+            self.finish();
+            return;
+        };
+        if let Some(title) = query.strip_suffix("]]") {
+            // Called from the buffer's own signals, which mustn't edit it;
+            // a second pending commit finds the session gone
+            let comp = self.clone();
+            let title = title.to_string();
+            glib::idle_add_local_once(move || comp.commit(&title));
+            return;
+        }
+
+        while let Some(child) = self.list.first_child() {
+            self.list.remove(&child);
+        }
+        let rows = {
+            let session = self.session.borrow();
+            let titles = &session.as_ref().unwrap().titles;
+            let mut rows = fuzzy_matches(&query, titles, WIKILINK_MAX_ROWS);
+            let typed = query.trim();
+            if !typed.is_empty() && !titles.iter().any(|t| t == typed) {
+                rows.push(typed.to_string());
+            }
+            for title in &rows {
+                let text = if titles.contains(title) {
+                    title.clone()
+                } else {
+                    format!("Create \u{201c}{}\u{201d}", title)
+                };
+                let label = Label::builder()
+                    .label(text)
+                    .xalign(0.0)
+                    .margin_start(4)
+                    .margin_end(4)
+                    .margin_top(2)
+                    .margin_bottom(2)
+                    .ellipsize(gtk4::pango::EllipsizeMode::End)
+                    .build();
+                // Clicking a row mustn't take focus from the editor
+                let row = gtk4::ListBoxRow::builder().child(&label).focusable(false).build();
+                self.list.append(&row);
+            }
+            rows
+        };
+        if rows.is_empty() {
+            self.popover.popdown();
+        } else {
+            self.list.select_row(self.list.row_at_index(0).as_ref());
+            let cursor = self.buffer.iter_at_mark(&self.buffer.get_insert());
+            let rect = self.text_view.iter_location(&cursor);
+            let (x, y) = self.text_view.buffer_to_window_coords(gtk4::TextWindowType::Widget, rect.x(), rect.y());
+            self.popover.set_pointing_to(Some(&gtk4::gdk::Rectangle::new(x, y, 1, rect.height())));
+            self.popover.popup();
+        }
+        if let Some(session) = self.session.borrow_mut().as_mut() {
+            session.rows = rows;
+        }
+    }
+
+    /// Replace `[[query` with a tangle link to `title`, creating the tangle
+    /// when there is none by that name.
+    fn commit(&self, title: &str) {
+        let title = title.trim().to_string();
+        let Some((start_mark, existing)) = self
+            .session
+            .borrow()
+            .as_ref()
+            .map(|s| (s.start.clone(), s.titles.contains(&title)))
+        else {
+            return;
+        };
+        if title.is_empty() {
+            self.finish();
+            return;
+        }
+
+        self.committing.set(true);
+        let mut start = self.buffer.iter_at_mark(&start_mark);
+        let mut end = self.buffer.iter_at_mark(&self.buffer.get_insert());
+        self.buffer.delete(&mut start, &mut end);
+        let offset = start.offset();
+        self.buffer.insert(&mut start, &title);
+        let link_start = self.buffer.iter_at_offset(offset);
+        let tag = get_or_create_tag(&self.buffer.tag_table(), &format!("tangle::{}", title));
+        self.buffer.apply_tag(&tag, &link_start, &start);
+        self.committing.set(false);
+        self.finish();
+
+        if !existing {
+            ensure_tangle_note_exists(&self.db, &title);
+        }
+    }
+
+    fn move_selection(&self, step: i32) {
+        let count = self.session.borrow().as_ref().map_or(0, |s| s.rows.len() as i32);
+        if count == 0 {
+            return;
+        }
+        let current = self.list.selected_row().map_or(0, |r| r.index());
+        let next = (current + step).rem_euclid(count);
+        self.list.select_row(self.list.row_at_index(next).as_ref());
+    }
+
+    fn selected_title(&self) -> Option<String> {
+        let index = self.list.selected_row()?.index() as usize;
+        self.session.borrow().as_ref()?.rows.get(index).cloned()
+    }
+}
+
+/// Typing `[[` in the editor opens a popover of tangle titles that narrows
+/// (fuzzily) as you type. Enter, Tab or a click inserts the chosen title as
+/// a tangle link in place of `[[…`; Escape leaves the text as typed.
+fn setup_wikilink_completion(text_view: &TextView, buffer: &TextBuffer, db: &Database, inhibit_changed: &Rc<Cell<bool>>) {
+    let list = gtk4::ListBox::builder()
+        .selection_mode(gtk4::SelectionMode::Single)
+        .can_focus(false)
+        .build();
+    list.add_css_class("boxed-list");
+    let scrolled = ScrolledWindow::builder()
+        .child(&list)
+        .hscrollbar_policy(gtk4::PolicyType::Never)
+        .max_content_height(240)
+        .propagate_natural_height(true)
+        .width_request(240)
+        .build();
+    // No autohide, so typing carries on in the editor while it is open
+    let popover = gtk4::Popover::builder()
+        .autohide(false)
+        .has_arrow(false)
+        .position(gtk4::PositionType::Bottom)
+        .can_focus(false)
+        .child(&scrolled)
+        .build();
+    popover.set_parent(text_view);
+
+    let completion = Rc::new(WikilinkCompletion {
+        text_view: text_view.clone(),
+        buffer: buffer.clone(),
+        db: db.clone(),
+        popover,
+        list: list.clone(),
+        session: RefCell::new(None),
+        titles: RefCell::new(Vec::new()),
+        committing: Cell::new(false),
+        char_count: Cell::new(buffer.char_count()),
+    });
+
+    let comp = completion.clone();
+    let inhibit = inhibit_changed.clone();
+    buffer.connect_changed(move |buf| {
+        let grew = buf.char_count() > comp.char_count.get();
+        comp.char_count.set(buf.char_count());
+        if inhibit.get() || comp.committing.get() {
+            return;
+        }
+        if comp.session.borrow().is_some() {
+            comp.update();
+            return;
+        }
+        // Only typing (not deleting down to) a [[ starts a completion
+        let cursor = buf.iter_at_mark(&buf.get_insert());
+        let mut start = cursor;
+        if grew && start.backward_chars(2) && buf.text(&start, &cursor, false) == "[[" {
+            comp.start(&start);
+            comp.update();
+        }
+    });
+
+    let comp = completion.clone();
+    buffer.connect_mark_set(move |_, _, mark| {
+        if mark.name().as_deref() == Some("insert") && !comp.committing.get() && comp.session.borrow().is_some() {
+            comp.update();
+        }
+    });
+
+    let comp = completion.clone();
+    list.connect_row_activated(move |_, row| {
+        let title = comp.session.borrow().as_ref().and_then(|s| s.rows.get(row.index() as usize).cloned());
+        if let Some(title) = title {
+            comp.commit(&title);
+        }
+    });
+
+    let keys = gtk4::EventControllerKey::new();
+    keys.set_propagation_phase(gtk4::PropagationPhase::Capture);
+    let comp = completion.clone();
+    keys.connect_key_pressed(move |_, keyval, _, _| {
+        if comp.session.borrow().is_none() || !comp.popover.is_visible() {
+            return glib::Propagation::Proceed;
+        }
+        match keyval {
+            gtk4::gdk::Key::Down => comp.move_selection(1),
+            gtk4::gdk::Key::Up => comp.move_selection(-1),
+            gtk4::gdk::Key::Return | gtk4::gdk::Key::KP_Enter | gtk4::gdk::Key::Tab => {
+                match comp.selected_title() {
+                    Some(title) => comp.commit(&title),
+                    None => comp.finish(),
+                }
+            }
+            gtk4::gdk::Key::Escape => comp.finish(),
+            _ => return glib::Propagation::Proceed,
+        }
+        glib::Propagation::Stop
+    });
+    text_view.add_controller(keys);
+
+    let comp = completion;
+    let focus = gtk4::EventControllerFocus::new();
+    focus.connect_leave(move |_| comp.finish());
+    text_view.add_controller(focus);
+}
+
+/// The best `limit` titles for `query`: all of its characters in order,
+/// case-insensitively, preferring runs, word starts and shorter titles. An
+/// empty query lists titles in their given order.
+fn fuzzy_matches(query: &str, titles: &[String], limit: usize) -> Vec<String> {
+    let query: Vec<char> = query.trim().chars().map(fold_case).collect();
+    if query.is_empty() {
+        return titles.iter().take(limit).cloned().collect();
+    }
+    let mut scored: Vec<(i32, &String)> = titles
+        .iter()
+        .filter_map(|title| fuzzy_score(&query, title).map(|s| (s, title)))
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.len().cmp(&b.1.len())).then(a.1.cmp(b.1)));
+    scored.into_iter().take(limit).map(|(_, t)| t.clone()).collect()
+}
+
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// Score of the better of two alignments: each query character at its next
+/// occurrence, or at its next occurrence starting a word when there is one.
+/// `query` is already case-folded.
+fn fuzzy_score(query: &[char], title: &str) -> Option<i32> {
+    let chars: Vec<char> = title.chars().map(fold_case).collect();
+    let word_start = |i: usize| i == 0 || !chars[i - 1].is_alphanumeric();
+    let align = |prefer_word_starts: bool| -> Option<i32> {
+        let mut score = 0;
+        let mut from = 0;
+        let mut last: Option<usize> = None;
+        for &qc in query {
+            let next = |want_start: bool| (from..chars.len()).find(|&i| chars[i] == qc && (!want_start || word_start(i)));
+            let i = if prefer_word_starts { next(true).or_else(|| next(false)) } else { next(false) }?;
+            score += 1;
+            if last.is_some_and(|j| j + 1 == i) {
+                score += 5;
+            }
+            if word_start(i) {
+                score += 8;
+            }
+            last = Some(i);
+            from = i + 1;
+        }
+        Some(score * 4 - chars.len() as i32)
+    };
+    // The exact title always comes first
+    let exact = if chars.as_slice() == query { 100 } else { 0 };
+    align(false).max(align(true)).map(|score| score + exact)
+}
+
 // ── Auto-linking: scan buffer for note title matches ───────────────
 
 /// Compute title matches off-thread, then apply tags on main thread.
@@ -1834,69 +2529,18 @@ fn get_inline_tag_names(iter: &TextIter) -> Vec<String> {
         .collect()
 }
 
-fn escape_html(text: &str) -> String {
-    text.replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;")
-}
-
-fn escape_html_attr(text: &str) -> String {
-    escape_html(text)
-}
-
 // ── Deserialization: HTML → Buffer ─────────────────────────────────
 
-struct HtmlSink {
-    tokens: RefCell<Vec<HtmlToken>>,
-}
-
-#[derive(Debug)]
-enum HtmlToken {
-    StartTag(String, Vec<(String, String)>),
-    EndTag(String),
-    Text(String),
-}
-
-impl TokenSink for HtmlSink {
-    type Handle = ();
-
-    fn process_token(&self, token: Token, _line_number: u64) -> TokenSinkResult<()> {
-        match token {
-            Token::TagToken(tag) => {
-                let name = tag.name.to_string();
-                let attrs: Vec<(String, String)> = tag.attrs.iter()
-                    .map(|a| (a.name.local.to_string(), a.value.to_string()))
-                    .collect();
-                match tag.kind {
-                    TagKind::StartTag => self.tokens.borrow_mut().push(HtmlToken::StartTag(name, attrs)),
-                    TagKind::EndTag => self.tokens.borrow_mut().push(HtmlToken::EndTag(name)),
-                }
-            }
-            Token::CharacterTokens(s) => {
-                self.tokens.borrow_mut().push(HtmlToken::Text(s.to_string()));
-            }
-            _ => {}
-        }
-        TokenSinkResult::Continue
-    }
-}
-
+/// `max_image_width` caps how wide images are drawn (their stored width is
+/// kept in `image_map`); `None` draws them at that width.
 fn deserialize_html(
     buffer: &TextBuffer,
     text_view: &TextView,
     html: &str,
     image_map: &Rc<RefCell<HashMap<i32, ImageInfo>>>,
+    max_image_width: Option<i32>,
 ) {
-    // Tokenize
-    let sink = HtmlSink { tokens: RefCell::new(Vec::new()) };
-    let tokenizer = Tokenizer::new(sink, TokenizerOpts::default());
-    let mut queue = BufferQueue::default();
-    queue.push_back(StrTendril::from(html));
-    let _ = tokenizer.feed(&mut queue);
-    tokenizer.end();
-
-    let tokens = tokenizer.sink.tokens.into_inner();
+    let tokens = tokenize_html(html);
 
     // Process tokens
     let mut tag_stack: Vec<(String, Vec<(String, String)>, i32)> = Vec::new(); // (tag_name, attrs, start_offset)
@@ -1977,7 +2621,8 @@ fn deserialize_html(
 
                             let im_cb = image_map.clone();
                             let offset_cb = img_offset;
-                            let frame = pickers::build_resizable_picture(src, width, Some(std::boxed::Box::new(move |new_w| {
+                            let shown_width = max_image_width.map_or(width, |max| width.min(max));
+                            let frame = pickers::build_resizable_picture(src, shown_width, Some(std::boxed::Box::new(move |new_w| {
                                 if let Some(info) = im_cb.borrow_mut().get_mut(&offset_cb) {
                                     info.width = new_w;
                                 }
@@ -2189,3 +2834,55 @@ fn parse_style_color(style: &str) -> Option<String> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(list: &[&str]) -> Vec<String> {
+        list.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn fuzzy_score_needs_every_character_in_order() {
+        let query: Vec<char> = "gdn".chars().collect();
+        assert!(fuzzy_score(&query, "Garden").is_some());
+        assert!(fuzzy_score(&query, "Dang").is_none());
+        assert!(fuzzy_score(&"ÉTÉ".chars().map(fold_case).collect::<Vec<_>>(), "Été à Paris").is_some());
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_runs_and_word_starts() {
+        let query: Vec<char> = "rn".chars().collect();
+        // "Reading Notes" starts words; "Barn" only runs
+        assert!(fuzzy_score(&query, "Reading Notes") > fuzzy_score(&query, "Barn"));
+        let query: Vec<char> = "note".chars().collect();
+        assert!(fuzzy_score(&query, "Notebook") > fuzzy_score(&query, "No Theme"));
+        assert!(fuzzy_score(&query, "note") > fuzzy_score(&query, "Notebook"));
+    }
+
+    #[test]
+    fn fuzzy_matches_ranks_and_limits() {
+        let all = titles(&["Barn", "Reading Notes", "rn", "Unrelated"]);
+        assert_eq!(fuzzy_matches("RN", &all, 2), ["rn", "Reading Notes"]);
+        assert_eq!(fuzzy_matches("  ", &all, 3), ["Barn", "Reading Notes", "rn"]);
+        assert!(fuzzy_matches("xyz", &all, 8).is_empty());
+    }
+
+    #[test]
+    fn preview_keeps_the_first_blocks() {
+        let blocks: Vec<String> = (1..=PREVIEW_BLOCKS + 2).map(|i| format!("<p>Block {}</p>\n", i)).collect();
+        let (html, truncated) = preview_html(&blocks.concat());
+        assert!(truncated);
+        assert_eq!(html, blocks[..PREVIEW_BLOCKS].concat().trim_end());
+    }
+
+    #[test]
+    fn preview_of_a_short_tangle_is_all_of_it() {
+        let html = "<h2>Title</h2>\n<ul><li>One</li>\n<li>Two</li></ul>\n<p><img src=\"/tmp/a.png\"></p>\n";
+        assert_eq!(preview_html(html), (html.to_string(), false));
+
+        let exact: String = (1..=PREVIEW_BLOCKS).map(|i| format!("<p>{}</p>\n", i)).collect();
+        assert_eq!(preview_html(&exact), (exact.clone(), false));
+    }
+}
@@ -3,26 +3,102 @@ use gtk4::{glib, ApplicationWindow};
 use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::rc::Rc;
-use crate::database::Database;
+use crate::database::{ContentEdit, Database};
+use crate::link_graph::LinkGraph;
+use crate::map_layout::{self, ForceLayout, LayoutCommand, LayoutPoll, LayoutWorker};
+use crate::note_window::apply_content_edit;
 
+#[derive(Clone)]
 struct MapNode {
     note_id: i64,
     title: String,
     x: f64,
     y: f64,
-    vx: f64,
-    vy: f64,
     w: f64,
     h: f64,
     has_saved_pos: bool,
+    star_color: Option<String>,
+    /// Local YYYY-MM-DD dates, for the date-range filter
+    created: String,
+    updated: String,
+    /// Excluded by the map filter
+    hidden: bool,
 }
 
 struct MapEdge {
     source: usize,
     target: usize,
+    label: Option<String>,
 }
 
-pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db: &Database) {
+/// Ego-network view: the centre tangle and everything within `hops` links of
+/// it (either direction), laid out in rings by distance. Nodes missing from
+/// `positions` are hidden.
+struct FocusView {
+    center: usize,
+    positions: HashMap<usize, (f64, f64)>,
+    distance: HashMap<usize, u32>,
+}
+
+impl FocusView {
+    fn new(center: usize, hops: u32, nodes: &[MapNode], edges: &[MapEdge]) -> Self {
+        let mut neighbours: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+        for edge in edges {
+            if edge.source < nodes.len() && edge.target < nodes.len() {
+                neighbours[edge.source].push(edge.target);
+                neighbours[edge.target].push(edge.source);
+            }
+        }
+
+        // Breadth-first, one ring per hop; a ring lists its nodes grouped by parent
+        let mut distance: HashMap<usize, u32> = HashMap::new();
+        distance.insert(center, 0);
+        let mut rings: Vec<Vec<usize>> = vec![vec![center]];
+        for hop in 1..=hops {
+            let mut ring = Vec::new();
+            for &i in &rings[hop as usize - 1] {
+                for &j in &neighbours[i] {
+                    if let std::collections::hash_map::Entry::Vacant(e) = distance.entry(j) {
+                        e.insert(hop);
+                        ring.push(j);
+                    }
+                }
+            }
+            if ring.is_empty() {
+                break;
+            }
+            rings.push(ring);
+        }
+
+        // Each ring is at least wide enough to fit its labels side by side
+        let (cx, cy) = map_layout::CENTER;
+        let mut positions: HashMap<usize, (f64, f64)> = HashMap::new();
+        positions.insert(center, (cx, cy));
+        let mut radius = 0.0f64;
+        for ring in rings.iter().skip(1) {
+            let widest = ring.iter().map(|&i| nodes[i].w).fold(0.0, f64::max);
+            let circumference = ring.len() as f64 * (widest + 20.0);
+            radius = (radius + 160.0).max(circumference / std::f64::consts::TAU);
+            for (k, &i) in ring.iter().enumerate() {
+                let angle = k as f64 / ring.len() as f64 * std::f64::consts::TAU - std::f64::consts::FRAC_PI_2;
+                positions.insert(i, (cx + radius * angle.cos(), cy + radius * angle.sin()));
+            }
+        }
+
+        FocusView { center, positions, distance }
+    }
+}
+
+/// Open the tangle map; with `focus_note` it starts in focus mode centred on
+/// that tangle, and with a `path` of note ids it highlights that chain of
+/// links and zooms to it.
+pub fn show_tangle_map(
+    app: &gtk4::Application,
+    parent: &ApplicationWindow,
+    db: &Database,
+    focus_note: Option<i64>,
+    path: &[i64],
+) {
     let dialog = gtk4::Window::builder()
         .title("Tangle Map")
         .default_width(800)
@@ -33,102 +109,62 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
     dialog.add_css_class("note-list-dialog");
 
     // Extract graph data from DB
-    let all_notes = db.get_all_notes().unwrap_or_default();
-    let tangle_re = regex::Regex::new(r#"tangle://([^"<]+)"#).unwrap();
+    let graph = LinkGraph::load(db).unwrap_or_else(|e| {
+        eprintln!("Error loading link graph: {}", e);
+        LinkGraph { notes: Vec::new(), edges: Vec::new() }
+    });
+    let all_notes = &graph.notes;
+    let map_positions = db.get_map_positions().unwrap_or_default();
 
-    let mut title_to_idx: HashMap<String, usize> = HashMap::new();
+    let mut id_to_idx: HashMap<i64, usize> = HashMap::new();
     let mut nodes: Vec<MapNode> = Vec::new();
 
-    for note in &all_notes {
+    for note in all_notes {
         let idx = nodes.len();
-        title_to_idx.insert(note.title.clone(), idx);
-        let tw = (note.title.len() as f64 * 7.0).max(60.0);
-        let has_saved = note.position_x != 0.0 || note.position_y != 0.0;
+        if let Some(id) = note.id {
+            id_to_idx.insert(id, idx);
+        }
+        let mut tw = (note.title.len() as f64 * 7.0).max(60.0);
+        if note.star_color.is_some() {
+            tw += STAR_SPACE;
+        }
+        let saved = note.id.and_then(|id| map_positions.get(&id).copied());
+        let has_saved = saved.is_some();
+        let (x, y) = saved.unwrap_or_else(|| map_layout::initial_position(idx, all_notes.len()));
         nodes.push(MapNode {
             note_id: note.id.unwrap_or(0),
             title: note.title.clone(),
-            x: if has_saved { note.position_x } else { 400.0 + (idx as f64 * 37.0).sin() * 200.0 },
-            y: if has_saved { note.position_y } else { 300.0 + (idx as f64 * 23.0).cos() * 200.0 },
-            vx: 0.0,
-            vy: 0.0,
+            x,
+            y,
             w: tw + 20.0,
             h: 30.0,
             has_saved_pos: has_saved,
+            star_color: note.star_color.clone(),
+            created: local_date(&note.created_at),
+            updated: local_date(&note.updated_at),
+            hidden: false,
         });
     }
 
-    let mut edges: Vec<MapEdge> = Vec::new();
-    for note in &all_notes {
-        if let Some(src_idx) = title_to_idx.get(&note.title) {
-            for cap in tangle_re.captures_iter(&note.content) {
-                if let Some(target_title) = cap.get(1) {
-                    let target_title = target_title.as_str();
-                    if let Some(tgt_idx) = title_to_idx.get(target_title) {
-                        if src_idx != tgt_idx {
-                            edges.push(MapEdge { source: *src_idx, target: *tgt_idx });
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    // Force-directed layout (Fruchterman-Reingold) — only for nodes without saved positions
-    let needs_layout = nodes.iter().any(|n| !n.has_saved_pos);
-    if needs_layout && nodes.len() > 1 {
-        let area = 800.0 * 600.0;
-        let k = (area / nodes.len() as f64).sqrt();
-        let iterations = 100;
-
-        for iter in 0..iterations {
-            let temp = 10.0 * (1.0 - iter as f64 / iterations as f64);
-
-            let positions: Vec<(f64, f64)> = nodes.iter().map(|n| (n.x, n.y)).collect();
-            for i in 0..nodes.len() {
-                if nodes[i].has_saved_pos { continue; }
-                nodes[i].vx = 0.0;
-                nodes[i].vy = 0.0;
-                for j in 0..nodes.len() {
-                    if i == j { continue; }
-                    let dx = positions[i].0 - positions[j].0;
-                    let dy = positions[i].1 - positions[j].1;
-                    let dist = (dx * dx + dy * dy).sqrt().max(1.0);
-                    let force = k * k / dist;
-                    nodes[i].vx += dx / dist * force;
-                    nodes[i].vy += dy / dist * force;
-                }
-            }
+    let link_labels = db.get_link_labels().unwrap_or_default();
+    let edges: Vec<MapEdge> = graph
+        .edges
+        .iter()
+        .map(|&(source, target)| MapEdge {
+            source,
+            target,
+            label: link_labels
+                .get(&(nodes[source].note_id, nodes[target].note_id))
+                .cloned(),
+        })
+        .collect();
 
-            for edge in &edges {
-                if edge.source >= nodes.len() || edge.target >= nodes.len() {
-                    continue;
-                }
-                if nodes[edge.source].has_saved_pos && nodes[edge.target].has_saved_pos { continue; }
-                let dx = nodes[edge.source].x - nodes[edge.target].x;
-                let dy = nodes[edge.source].y - nodes[edge.target].y;
-                let dist = (dx * dx + dy * dy).sqrt().max(1.0);
-                let force = dist * dist / k;
-                let fx = dx / dist * force;
-                let fy = dy / dist * force;
-                if !nodes[edge.source].has_saved_pos {
-                    nodes[edge.source].vx -= fx;
-                    nodes[edge.source].vy -= fy;
-                }
-                if !nodes[edge.target].has_saved_pos {
-                    nodes[edge.target].vx += fx;
-                    nodes[edge.target].vy += fy;
-                }
-            }
-
-            for node in &mut nodes {
-                if node.has_saved_pos { continue; }
-                let mag = (node.vx * node.vx + node.vy * node.vy).sqrt().max(1.0);
-                node.x += node.vx / mag * temp.min(mag);
-                node.y += node.vy / mag * temp.min(mag);
-                node.x = node.x.clamp(50.0, 750.0);
-                node.y = node.y.clamp(50.0, 550.0);
-            }
-        }
+    let filter = Rc::new(RefCell::new(MapFilter::load(db)));
+    let hidden_count = apply_filter(&mut nodes, &edges, &filter.borrow());
+    // A highlighted path is shown whatever the filter says
+    let path: Vec<usize> = path.iter().filter_map(|id| id_to_idx.get(id).copied()).collect();
+    for &i in &path {
+        nodes[i].hidden = false;
     }
 
     let node_count = nodes.len();
@@ -145,12 +181,25 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
     let selected_nodes: Rc<RefCell<std::collections::HashSet<usize>>> = Rc::new(RefCell::new(std::collections::HashSet::new()));
     // Lasso rect in world coords: (x1, y1, x2, y2), None if not active
     let lasso_rect: Rc<Cell<Option<(f64, f64, f64, f64)>>> = Rc::new(Cell::new(None));
+    // Focus mode state; the centre is the last node pressed when it is switched on
+    let focus: Rc<RefCell<Option<FocusView>>> = Rc::new(RefCell::new(None));
+    let focus_hops = Rc::new(Cell::new(1u32));
+    let last_pressed: Rc<Cell<Option<usize>>> = Rc::new(Cell::new(None));
+    // Edge under the pointer, and the edge whose end is being dragged to a new target
+    let hovered_edge: Rc<Cell<Option<usize>>> = Rc::new(Cell::new(None));
+    let retarget_edge: Rc<Cell<Option<usize>>> = Rc::new(Cell::new(None));
+    let retarget_end: Rc<Cell<(f64, f64)>> = Rc::new(Cell::new((0.0, 0.0)));
+    // Shortest path from the analysis window, drawn until cleared
+    let path = Rc::new(RefCell::new(path));
 
     let drawing_area = gtk4::DrawingArea::builder()
         .hexpand(true)
         .vexpand(true)
         .build();
 
+    // Force layout on a worker thread (see the polling loop further down)
+    let layout_worker: Rc<RefCell<Option<LayoutWorker>>> = Rc::new(RefCell::new(None));
+
     // Draw
     let nodes_draw = nodes.clone();
     let edges_draw = edges.clone();
@@ -162,6 +211,11 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
     let link_drag_end_draw = link_drag_end.clone();
     let selected_draw = selected_nodes.clone();
     let lasso_draw = lasso_rect.clone();
+    let focus_draw = focus.clone();
+    let hovered_draw = hovered_edge.clone();
+    let retarget_draw = retarget_edge.clone();
+    let retarget_end_draw = retarget_end.clone();
+    let path_draw = path.clone();
     drawing_area.set_draw_func(move |_area, cr, w, h| {
         // Dark background
         cr.set_source_rgba(0.1, 0.1, 0.18, 1.0);
@@ -179,22 +233,11 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
         let nodes = nodes_draw.borrow();
         let edges = edges_draw.borrow();
         let query = search_draw.borrow().to_lowercase();
+        let focus = focus_draw.borrow();
+        let focus = focus.as_ref();
 
-        // Draw edges
-        cr.set_source_rgba(0.7, 0.53, 1.0, 0.4);
-        cr.set_line_width(1.5);
-        for edge in edges.iter() {
-            if edge.source >= nodes.len() || edge.target >= nodes.len() {
-                continue;
-            }
-            let s = &nodes[edge.source];
-            let t = &nodes[edge.target];
-            let mx = (s.x + t.x) / 2.0;
-            let my = (s.y + t.y) / 2.0 - 20.0;
-            cr.move_to(s.x, s.y);
-            cr.curve_to(mx, my, mx, my, t.x, t.y);
-            let _ = cr.stroke();
-        }
+        draw_edges(cr, &nodes, &edges, focus, hovered_draw.get());
+        draw_path_links(cr, &nodes, focus, &path_draw.borrow());
 
         // Draw link-drag preview line
         if let Some(src_idx) = link_drag_src_draw.get() {
@@ -212,59 +255,22 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
             }
         }
 
-        // Draw nodes
-        let sel = selected_draw.borrow();
-        for (i, node) in nodes.iter().enumerate() {
-            let x = node.x - node.w / 2.0;
-            let y = node.y - node.h / 2.0;
-
-            // Rounded rect
-            let radius = 6.0;
-            let nw = node.w.max(1.0);
-            let nh = node.h.max(1.0);
-            cr.new_sub_path();
-            cr.arc(x + nw - radius, y + radius, radius, -std::f64::consts::FRAC_PI_2, 0.0);
-            cr.arc(x + nw - radius, y + nh - radius, radius, 0.0, std::f64::consts::FRAC_PI_2);
-            cr.arc(x + radius, y + nh - radius, radius, std::f64::consts::FRAC_PI_2, std::f64::consts::PI);
-            cr.arc(x + radius, y + radius, radius, std::f64::consts::PI, 3.0 * std::f64::consts::FRAC_PI_2);
-            cr.close_path();
-
-            let highlighted = !query.is_empty() && node.title.to_lowercase().contains(&query);
-            let is_selected = sel.contains(&i);
-
-            cr.set_source_rgba(0.1, 0.1, 0.18, 0.9);
-            let _ = cr.fill_preserve();
-            if is_selected {
-                // Cyan border for selected nodes
-                cr.set_source_rgba(0.2, 0.8, 1.0, 0.9);
-                cr.set_line_width(3.0);
-            } else if highlighted {
-                cr.set_source_rgba(0.2, 1.0, 0.4, 0.9);
-                cr.set_line_width(3.0);
-            } else {
-                cr.set_source_rgba(0.7, 0.53, 1.0, 0.7);
-                cr.set_line_width(1.5);
-            }
-            let _ = cr.stroke();
-
-            // Title text
-            if is_selected {
-                cr.set_source_rgba(0.2, 0.8, 1.0, 1.0);
-            } else if highlighted {
-                cr.set_source_rgba(0.2, 1.0, 0.4, 1.0);
-            } else {
-                cr.set_source_rgba(0.88, 0.88, 0.88, 1.0);
+        // Draw retarget preview: from the source to the pointer
+        if let Some(edge) = retarget_draw.get().and_then(|i| edges.get(i)) {
+            if let Some((sx, sy)) = node_position(&nodes, focus, edge.source) {
+                let (ex, ey) = retarget_end_draw.get();
+                cr.set_source_rgba(1.0, 0.8, 0.2, 0.7);
+                cr.set_line_width(2.0);
+                cr.set_dash(&[6.0, 4.0], 0.0);
+                cr.move_to(sx, sy);
+                cr.line_to(ex, ey);
+                let _ = cr.stroke();
+                cr.set_dash(&[], 0.0);
             }
-            cr.set_font_size(11.0);
-            let (text_x, text_y) = if let Ok(extents) = cr.text_extents(&node.title) {
-                (x + (nw - extents.width()) / 2.0, y + nh / 2.0 + extents.height() / 2.0)
-            } else {
-                (x + 10.0, y + nh / 2.0 + 4.0)
-            };
-            cr.move_to(text_x, text_y);
-            let _ = cr.show_text(&node.title);
         }
-        drop(sel);
+
+        draw_nodes(cr, &nodes, focus, &query, &selected_draw.borrow());
+        draw_path_nodes(cr, &nodes, focus, &path_draw.borrow());
 
         // Draw lasso rectangle
         if let Some((x1, y1, x2, y2)) = lasso_draw.get() {
@@ -293,6 +299,9 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
         }
 
         let _ = cr.restore();
+
+        // Legend for the edge colours, in screen space
+        draw_focus_legend(cr, &nodes, focus);
     });
 
     // Scroll → zoom toward cursor
@@ -330,13 +339,16 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
     let zoom_ld = zoom.clone();
     let pan_ld_x = pan_x.clone();
     let pan_ld_y = pan_y.clone();
+    let focus_ld = focus.clone();
     link_drag_ctrl.connect_drag_begin(move |_gesture, x, y| {
         let z = zoom_ld.get();
-        if z == 0.0 { ld_src.set(None); return; }
+        // The focus view is for reading; links are made on the full map
+        if z == 0.0 || focus_ld.borrow().is_some() { ld_src.set(None); return; }
         let mx = (x - pan_ld_x.get()) / z;
         let my = (y - pan_ld_y.get()) / z;
         let nodes = nodes_ld.borrow();
         for (i, node) in nodes.iter().enumerate() {
+            if node.hidden { continue; }
             let nx = node.x - node.w / 2.0;
             let ny = node.y - node.h / 2.0;
             if mx >= nx && mx <= nx + node.w && my >= ny && my <= ny + node.h {
@@ -384,6 +396,7 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
     let db_link = db.clone();
     let app_link = app.clone();
     let da_lde = drawing_area.clone();
+    let lw_lde = layout_worker.clone();
     link_drag_ctrl.connect_drag_end(move |_, ox, oy| {
         let src_idx = match ld_src_e.get() {
             Some(i) => i,
@@ -403,7 +416,7 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
 
         // Hit-test for target node
         for (i, node) in nodes.iter().enumerate() {
-            if i == src_idx { continue; }
+            if i == src_idx || node.hidden { continue; }
             let nx = node.x - node.w / 2.0;
             let ny = node.y - node.h / 2.0;
             if end_x >= nx && end_x <= nx + node.w && end_y >= ny && end_y <= ny + node.h {
@@ -413,7 +426,10 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
                 drop(nodes);
 
                 // Add visual edge
-                edges_lde.borrow_mut().push(MapEdge { source: src_idx, target: i });
+                edges_lde.borrow_mut().push(MapEdge { source: src_idx, target: i, label: None });
+                if let Some(worker) = lw_lde.borrow().as_ref() {
+                    worker.send(LayoutCommand::AddEdge(src_idx, i));
+                }
 
                 // Append tangle link — inject into open editor if possible,
                 // otherwise write directly to DB.
@@ -487,18 +503,23 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
     let pan_nd_x = pan_x.clone();
     let pan_nd_y = pan_y.clone();
     let sel_nd = selected_nodes.clone();
+    let focus_nd = focus.clone();
+    let lp_nd = last_pressed.clone();
     node_drag_ctrl.connect_drag_begin(move |_gesture, x, y| {
         let z = zoom_nd.get();
-        if z == 0.0 { dn_begin.set(None); return; }
+        // Ring positions are not saved, so nodes stay put in focus mode (drag pans)
+        if z == 0.0 || focus_nd.borrow().is_some() { dn_begin.set(None); return; }
         let mx = (x - pan_nd_x.get()) / z;
         let my = (y - pan_nd_y.get()) / z;
         let nodes = nodes_nd.borrow();
         let sel = sel_nd.borrow();
         for (i, node) in nodes.iter().enumerate() {
+            if node.hidden { continue; }
             let nx = node.x - node.w / 2.0;
             let ny = node.y - node.h / 2.0;
             if mx >= nx && mx <= nx + node.w && my >= ny && my <= ny + node.h {
                 dn_begin.set(Some(i));
+                lp_nd.set(Some(i));
                 // If dragged node is in selection, move entire selection
                 // Otherwise just move this single node
                 let mut starts = Vec::new();
@@ -524,16 +545,22 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
     let nodes_nu = nodes.clone();
     let zoom_nu = zoom.clone();
     let da_nu = drawing_area.clone();
+    let lw_nu = layout_worker.clone();
     node_drag_ctrl.connect_drag_update(move |_, ox, oy| {
         if dn_update.get().is_none() { return; }
         let z = zoom_nu.get();
         if z == 0.0 { return; }
         let starts = dsp_update.borrow();
         let mut nodes = nodes_nu.borrow_mut();
+        let worker = lw_nu.borrow();
         for &(idx, sx, sy) in starts.iter() {
             if idx < nodes.len() {
                 nodes[idx].x = sx + ox / z;
                 nodes[idx].y = sy + oy / z;
+                // Hold dragged nodes in the running layout so neighbours follow
+                if let Some(worker) = worker.as_ref() {
+                    worker.send(LayoutCommand::Pin(idx, nodes[idx].x, nodes[idx].y));
+                }
             }
         }
         da_nu.queue_draw();
@@ -543,7 +570,13 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
     let dsp_end = drag_start_positions.clone();
     let nodes_save = nodes.clone();
     let db_save = db.clone();
+    let lw_end = layout_worker.clone();
     node_drag_ctrl.connect_drag_end(move |_, _, _| {
+        if let Some(worker) = lw_end.borrow().as_ref() {
+            for &(idx, _, _) in dsp_end.borrow().iter() {
+                worker.send(LayoutCommand::Release(idx));
+            }
+        }
         // Save all moved node positions to DB (position-only update, won't clobber content)
         if dn_end.get().is_some() {
             let nodes = nodes_save.borrow();
@@ -557,7 +590,7 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
             drop(starts);
             std::thread::spawn(move || {
                 for (id, x, y) in to_save {
-                    if let Err(e) = db.update_note_position(id, x, y) {
+                    if let Err(e) = db.update_note_map_position(id, x, y) {
                         eprintln!("Error saving node position: {}", e);
                     }
                 }
@@ -580,11 +613,12 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
     let pan_la_x = pan_x.clone();
     let pan_la_y = pan_y.clone();
     let sel_la = selected_nodes.clone();
+    let focus_la = focus.clone();
     lasso_ctrl.connect_drag_begin(move |gesture, x, y| {
         let state = gesture.current_event_state();
         let has_shift = state.contains(gtk4::gdk::ModifierType::SHIFT_MASK);
         let has_alt = state.contains(gtk4::gdk::ModifierType::ALT_MASK);
-        if !(has_shift && has_alt) {
+        if !(has_shift && has_alt) || focus_la.borrow().is_some() {
             la_begin.set(false);
             return;
         }
@@ -635,7 +669,7 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
             let mut sel = sel_le.borrow_mut();
             for (i, node) in nodes.iter().enumerate() {
                 // Select if node center is inside lasso
-                if node.x >= lx && node.x <= rx && node.y >= ly && node.y <= ry {
+                if !node.hidden && node.x >= lx && node.x <= rx && node.y >= ly && node.y <= ry {
                     sel.insert(i);
                 }
             }
@@ -663,15 +697,241 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
     let dn_pan_u = dragged_node.clone();
     let ld_pan_u = link_drag_src.clone();
     let la_pan_u = lasso_active.clone();
+    let rt_pan_u = retarget_edge.clone();
     drag_ctrl.connect_drag_update(move |_, ox, oy| {
         // Skip panning if another drag mode is active
-        if dn_pan_u.get().is_some() || ld_pan_u.get().is_some() || la_pan_u.get() { return; }
+        if dn_pan_u.get().is_some() || ld_pan_u.get().is_some() || la_pan_u.get() || rt_pan_u.get().is_some() { return; }
         pan_sx.set(start_px.get() + ox);
         pan_sy.set(start_py.get() + oy);
         da_d.queue_draw();
     });
     drawing_area.add_controller(drag_ctrl);
 
+    // Hover an edge → highlight it and show source → target in a tooltip
+    let motion_ctrl = gtk4::EventControllerMotion::new();
+    let nodes_hv = nodes.clone();
+    let edges_hv = edges.clone();
+    let focus_hv = focus.clone();
+    let zoom_hv = zoom.clone();
+    let pan_hv_x = pan_x.clone();
+    let pan_hv_y = pan_y.clone();
+    let hovered_hv = hovered_edge.clone();
+    let da_hv = drawing_area.clone();
+    motion_ctrl.connect_motion(move |_, x, y| {
+        let z = zoom_hv.get();
+        if z == 0.0 { return; }
+        let mx = (x - pan_hv_x.get()) / z;
+        let my = (y - pan_hv_y.get()) / z;
+        let nodes = nodes_hv.borrow();
+        let edges = edges_hv.borrow();
+        let focus = focus_hv.borrow();
+        let hit = if node_at(&nodes, focus.as_ref(), mx, my).is_some() {
+            None
+        } else {
+            edge_at(&nodes, &edges, focus.as_ref(), mx, my, EDGE_HIT_TOLERANCE / z)
+        };
+        if hit == hovered_hv.get() { return; }
+        hovered_hv.set(hit);
+        da_hv.set_tooltip_text(hit.map(|i| edge_description(&nodes, &edges[i])).as_deref());
+        da_hv.queue_draw();
+    });
+    let hovered_leave = hovered_edge.clone();
+    let da_leave = drawing_area.clone();
+    motion_ctrl.connect_leave(move |_| {
+        if hovered_leave.take().is_some() {
+            da_leave.set_tooltip_text(None);
+            da_leave.queue_draw();
+        }
+    });
+    drawing_area.add_controller(motion_ctrl);
+
+    // Right-click an edge → delete it or edit its label
+    {
+        let edge_click = gtk4::GestureClick::builder().button(3).build();
+        let prev_popover: Rc<RefCell<Option<gtk4::Popover>>> = Rc::new(RefCell::new(None));
+        let nodes_ec = nodes.clone();
+        let edges_ec = edges.clone();
+        let focus_ec = focus.clone();
+        let zoom_ec = zoom.clone();
+        let pan_ec_x = pan_x.clone();
+        let pan_ec_y = pan_y.clone();
+        let hovered_ec = hovered_edge.clone();
+        let lw_ec = layout_worker.clone();
+        let db_ec = db.clone();
+        let app_ec = app.clone();
+        let da_ec = drawing_area.clone();
+        edge_click.connect_pressed(move |_, n_press, x, y| {
+            if n_press != 1 { return; }
+            let z = zoom_ec.get();
+            if z == 0.0 { return; }
+            let mx = (x - pan_ec_x.get()) / z;
+            let my = (y - pan_ec_y.get()) / z;
+            let (pair, description, label) = {
+                let nodes = nodes_ec.borrow();
+                let edges = edges_ec.borrow();
+                let focus = focus_ec.borrow();
+                if node_at(&nodes, focus.as_ref(), mx, my).is_some() { return; }
+                let Some(i) = edge_at(&nodes, &edges, focus.as_ref(), mx, my, EDGE_HIT_TOLERANCE / z) else { return };
+                let edge = &edges[i];
+                ((edge.source, edge.target), edge_description(&nodes, edge), edge.label.clone().unwrap_or_default())
+            };
+
+            if let Some(old) = prev_popover.borrow_mut().take() {
+                old.unparent();
+            }
+            let popover = gtk4::Popover::new();
+            popover.set_parent(&da_ec);
+            popover.set_pointing_to(Some(&gtk4::gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+            let vbox = gtk4::Box::builder()
+                .orientation(gtk4::Orientation::Vertical)
+                .spacing(6)
+                .margin_top(6).margin_bottom(6).margin_start(6).margin_end(6)
+                .build();
+            vbox.append(&gtk4::Label::builder().label(&description).xalign(0.0).css_classes(["dim-label"]).build());
+            let label_entry = gtk4::Entry::builder()
+                .text(&label)
+                .placeholder_text("Link label")
+                .build();
+            vbox.append(&label_entry);
+            let delete_btn = gtk4::Button::with_label("Delete Link");
+            vbox.append(&delete_btn);
+            popover.set_child(Some(&vbox));
+
+            // Enter saves the label; an empty label removes it
+            let edges_lb = edges_ec.clone();
+            let nodes_lb = nodes_ec.clone();
+            let db_lb = db_ec.clone();
+            let da_lb = da_ec.clone();
+            let pop_lb = popover.clone();
+            label_entry.connect_activate(move |entry| {
+                let text = entry.text().trim().to_string();
+                let label = (!text.is_empty()).then_some(text);
+                let nodes = nodes_lb.borrow();
+                let mut edges = edges_lb.borrow_mut();
+                if let Some(edge) = edges.iter_mut().find(|e| (e.source, e.target) == pair) {
+                    let (source_id, target_id) = (nodes[pair.0].note_id, nodes[pair.1].note_id);
+                    if let Err(e) = db_lb.set_link_label(source_id, target_id, label.as_deref()) {
+                        eprintln!("Error saving link label: {}", e);
+                    }
+                    edge.label = label;
+                }
+                da_lb.queue_draw();
+                pop_lb.popdown();
+            });
+
+            let edges_del = edges_ec.clone();
+            let nodes_del = nodes_ec.clone();
+            let hovered_del = hovered_ec.clone();
+            let lw_del = lw_ec.clone();
+            let db_del = db_ec.clone();
+            let app_del = app_ec.clone();
+            let da_del = da_ec.clone();
+            let pop_del = popover.clone();
+            delete_btn.connect_clicked(move |_| {
+                let (source_id, target_title) = {
+                    let nodes = nodes_del.borrow();
+                    (nodes[pair.0].note_id, nodes[pair.1].title.clone())
+                };
+                let edit = ContentEdit::RewriteLink { target: target_title, new_target: None };
+                apply_content_edit(&app_del, &db_del, source_id, &edit);
+                edges_del.borrow_mut().retain(|e| (e.source, e.target) != pair);
+                if let Some(worker) = lw_del.borrow().as_ref() {
+                    worker.send(LayoutCommand::RemoveEdge(pair.0, pair.1));
+                }
+                hovered_del.set(None);
+                da_del.set_tooltip_text(None);
+                da_del.queue_draw();
+                pop_del.popdown();
+            });
+
+            *prev_popover.borrow_mut() = Some(popover.clone());
+            popover.popup();
+        });
+        drawing_area.add_controller(edge_click);
+    }
+
+    // Drag an edge's end (where it meets the target) onto another node → retarget the link
+    let retarget_ctrl = gtk4::GestureDrag::builder().button(1).build();
+    let rt_begin = retarget_edge.clone();
+    let rt_end_begin = retarget_end.clone();
+    let nodes_rb = nodes.clone();
+    let edges_rb = edges.clone();
+    let focus_rb = focus.clone();
+    let zoom_rb = zoom.clone();
+    let pan_rb_x = pan_x.clone();
+    let pan_rb_y = pan_y.clone();
+    let la_rb = lasso_active.clone();
+    retarget_ctrl.connect_drag_begin(move |_, x, y| {
+        rt_begin.set(None);
+        let z = zoom_rb.get();
+        if z == 0.0 || focus_rb.borrow().is_some() || la_rb.get() { return; }
+        let mx = (x - pan_rb_x.get()) / z;
+        let my = (y - pan_rb_y.get()) / z;
+        let nodes = nodes_rb.borrow();
+        if node_at(&nodes, None, mx, my).is_some() { return; }
+        let edges = edges_rb.borrow();
+        let tolerance = EDGE_HIT_TOLERANCE / z;
+        let hit = edges.iter().position(|edge| {
+            edge_end_handle(&nodes, edge)
+                .is_some_and(|(hx, hy)| (hx - mx).hypot(hy - my) <= tolerance)
+        });
+        if let Some(i) = hit {
+            rt_begin.set(Some(i));
+            rt_end_begin.set((mx, my));
+        }
+    });
+    let rt_update = retarget_edge.clone();
+    let rt_end_update = retarget_end.clone();
+    let zoom_ru = zoom.clone();
+    let pan_ru_x = pan_x.clone();
+    let pan_ru_y = pan_y.clone();
+    let da_ru = drawing_area.clone();
+    retarget_ctrl.connect_drag_update(move |gesture, ox, oy| {
+        if rt_update.get().is_none() { return; }
+        let z = zoom_ru.get();
+        if z == 0.0 { return; }
+        let Some((sx, sy)) = gesture.start_point() else { return };
+        rt_end_update.set(((sx + ox - pan_ru_x.get()) / z, (sy + oy - pan_ru_y.get()) / z));
+        da_ru.queue_draw();
+    });
+    let rt_finish = retarget_edge.clone();
+    let rt_end_finish = retarget_end.clone();
+    let nodes_rf = nodes.clone();
+    let edges_rf = edges.clone();
+    let lw_rf = layout_worker.clone();
+    let db_rf = db.clone();
+    let app_rf = app.clone();
+    let da_rf = drawing_area.clone();
+    retarget_ctrl.connect_drag_end(move |_, _, _| {
+        let Some(idx) = rt_finish.take() else { return };
+        da_rf.queue_draw();
+        let (ex, ey) = rt_end_finish.get();
+        let nodes = nodes_rf.borrow();
+        let mut edges = edges_rf.borrow_mut();
+        let Some(edge) = edges.get(idx) else { return };
+        let (source, old_target) = (edge.source, edge.target);
+        let Some(new_target) = node_at(&nodes, None, ex, ey) else { return };
+        if new_target == source || new_target == old_target { return; }
+
+        // The label moves with the link, in the same database transaction
+        let edit = ContentEdit::RewriteLink {
+            target: nodes[old_target].title.clone(),
+            new_target: Some(nodes[new_target].title.clone()),
+        };
+        apply_content_edit(&app_rf, &db_rf, nodes[source].note_id, &edit);
+        if let Some(worker) = lw_rf.borrow().as_ref() {
+            worker.send(LayoutCommand::RemoveEdge(source, old_target));
+            worker.send(LayoutCommand::AddEdge(source, new_target));
+        }
+        // Pointing at a tangle this one already links to merges the two links
+        if edges.iter().any(|e| e.source == source && e.target == new_target) {
+            edges.remove(idx);
+        } else {
+            edges[idx].target = new_target;
+        }
+    });
+    drawing_area.add_controller(retarget_ctrl);
+
     // Plain click on empty space → clear selection
     {
         let desel_click = gtk4::GestureClick::builder().button(1).build();
@@ -682,19 +942,15 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
         let pan_ds_y = pan_y.clone();
         let sel_ds = selected_nodes.clone();
         let da_ds = drawing_area.clone();
+        let focus_ds = focus.clone();
         desel_click.connect_pressed(move |_, n_press, x, y| {
             if n_press != 1 { return; }
             let z = zoom_ds.get();
             if z == 0.0 { return; }
             let mx = (x - pan_ds_x.get()) / z;
             let my = (y - pan_ds_y.get()) / z;
-            let nodes = nodes_ds.borrow();
-            for node in nodes.iter() {
-                let nx = node.x - node.w / 2.0;
-                let ny = node.y - node.h / 2.0;
-                if mx >= nx && mx <= nx + node.w && my >= ny && my <= ny + node.h {
-                    return; // Clicked a node, don't deselect
-                }
+            if node_at(&nodes_ds.borrow(), focus_ds.borrow().as_ref(), mx, my).is_some() {
+                return; // Clicked a node, don't deselect
             }
             sel_ds.borrow_mut().clear();
             da_ds.queue_draw();
@@ -712,6 +968,8 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
         let pan_sc_y = pan_y.clone();
         let sel_sc = selected_nodes.clone();
         let da_sc = drawing_area.clone();
+        let focus_sc = focus.clone();
+        let lp_sc = last_pressed.clone();
         sel_click.connect_pressed(move |gesture, n_press, x, y| {
             if n_press != 1 { return; }
             let state = gesture.current_event_state();
@@ -720,20 +978,16 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
             if z == 0.0 { return; }
             let mx = (x - pan_sc_x.get()) / z;
             let my = (y - pan_sc_y.get()) / z;
-            let nodes = nodes_sc.borrow();
-            for (i, node) in nodes.iter().enumerate() {
-                let nx = node.x - node.w / 2.0;
-                let ny = node.y - node.h / 2.0;
-                if mx >= nx && mx <= nx + node.w && my >= ny && my <= ny + node.h {
-                    let mut sel = sel_sc.borrow_mut();
-                    if sel.contains(&i) {
-                        sel.remove(&i);
-                    } else {
-                        sel.insert(i);
-                    }
-                    da_sc.queue_draw();
-                    return;
+            let hit = node_at(&nodes_sc.borrow(), focus_sc.borrow().as_ref(), mx, my);
+            if let Some(i) = hit {
+                let mut sel = sel_sc.borrow_mut();
+                if sel.contains(&i) {
+                    sel.remove(&i);
+                } else {
+                    sel.insert(i);
                 }
+                lp_sc.set(Some(i));
+                da_sc.queue_draw();
             }
         });
         drawing_area.add_controller(sel_click);
@@ -749,6 +1003,7 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
         let pan_cy = pan_y.clone();
         let db_click = db.clone();
         let app_click = app.clone();
+        let focus_c = focus.clone();
         dbl_click.connect_pressed(move |_, n_press, x, y| {
             if n_press != 2 { return; }
             let z = zoom_c.get();
@@ -756,20 +1011,181 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
             let mx = (x - pan_cx.get()) / z;
             let my = (y - pan_cy.get()) / z;
             let nodes = nodes_click.borrow();
-            for node in nodes.iter() {
-                let nx = node.x - node.w / 2.0;
-                let ny = node.y - node.h / 2.0;
-                if mx >= nx && mx <= nx + node.w && my >= ny && my <= ny + node.h {
-                    let title = node.title.clone();
-                    drop(nodes); // Release borrow before calling out
-                    crate::rich_editor::open_tangle_note(&db_click, &app_click, &title);
-                    return;
-                }
+            if let Some(i) = node_at(&nodes, focus_c.borrow().as_ref(), mx, my) {
+                let title = nodes[i].title.clone();
+                drop(nodes); // Release borrow before calling out
+                crate::rich_editor::open_tangle_note(&db_click, &app_click, &title);
             }
         });
         drawing_area.add_controller(dbl_click);
     }
 
+    // Apply layout snapshots from the worker; nodes being dragged keep the pointer position
+    let lw_poll = layout_worker.clone();
+    let nodes_poll = nodes.clone();
+    let dsp_poll = drag_start_positions.clone();
+    let da_poll = drawing_area.clone();
+    let dialog_poll = dialog.clone();
+    glib::timeout_add_local(std::time::Duration::from_millis(30), move || {
+        if !dialog_poll.is_visible() {
+            lw_poll.borrow_mut().take();
+            return glib::ControlFlow::Break;
+        }
+        let poll = match lw_poll.borrow().as_ref() {
+            Some(worker) => worker.poll(),
+            None => return glib::ControlFlow::Continue,
+        };
+        match poll {
+            LayoutPoll::Pending => {}
+            LayoutPoll::Update(positions) => {
+                let dragging = dsp_poll.borrow();
+                let mut nodes = nodes_poll.borrow_mut();
+                for (idx, (node, &(x, y))) in nodes.iter_mut().zip(positions.iter()).enumerate() {
+                    if !dragging.iter().any(|&(d, _, _)| d == idx) {
+                        node.x = x;
+                        node.y = y;
+                    }
+                }
+                da_poll.queue_draw();
+            }
+            LayoutPoll::Finished => {
+                lw_poll.borrow_mut().take();
+            }
+        }
+        glib::ControlFlow::Continue
+    });
+
+    // Lay out tangles that have never been placed; placed ones stay fixed
+    {
+        let nodes = nodes.borrow();
+        if nodes.len() > 1 && nodes.iter().any(|n| !n.has_saved_pos) {
+            let layout = build_layout(&nodes, &edges.borrow(), true);
+            *layout_worker.borrow_mut() = Some(LayoutWorker::spawn(layout, false));
+        }
+    }
+
+    // Live layout: keep simulating every node so the graph reacts to drags
+    let live_toggle = gtk4::CheckButton::builder()
+        .label("Live layout")
+        .tooltip_text("Keep the layout running; other tangles make room as you drag")
+        .build();
+    let lw_live = layout_worker.clone();
+    let nodes_live = nodes.clone();
+    let edges_live = edges.clone();
+    let db_live = db.clone();
+    live_toggle.connect_toggled(move |btn| {
+        if btn.is_active() {
+            let layout = build_layout(&nodes_live.borrow(), &edges_live.borrow(), false);
+            *lw_live.borrow_mut() = Some(LayoutWorker::spawn(layout, true));
+        } else {
+            lw_live.borrow_mut().take();
+            // Keep the arrangement the live layout produced
+            let mut nodes = nodes_live.borrow_mut();
+            for node in nodes.iter_mut() {
+                node.has_saved_pos = true;
+            }
+            save_map_positions(&db_live, &nodes);
+        }
+    });
+    let live_close = live_toggle.clone();
+    let nodes_close = nodes.clone();
+    let db_close = db.clone();
+    dialog.connect_close_request(move |_| {
+        if live_close.is_active() {
+            save_map_positions(&db_close, &nodes_close.borrow());
+        }
+        glib::Propagation::Proceed
+    });
+
+    // Focus mode: enter/leave, remembering the full-map view to return to
+    let saved_view: Rc<Cell<Option<(f64, f64, f64)>>> = Rc::new(Cell::new(None));
+    let set_focus: Rc<dyn Fn(Option<usize>)> = {
+        let focus = focus.clone();
+        let hops = focus_hops.clone();
+        let nodes = nodes.clone();
+        let edges = edges.clone();
+        let zoom = zoom.clone();
+        let pan_x = pan_x.clone();
+        let pan_y = pan_y.clone();
+        let saved_view = saved_view.clone();
+        let da = drawing_area.clone();
+        Rc::new(move |center: Option<usize>| {
+            let was_focused = focus.borrow().is_some();
+            *focus.borrow_mut() = center.map(|c| FocusView::new(c, hops.get(), &nodes.borrow(), &edges.borrow()));
+            if center.is_some() {
+                if !was_focused {
+                    saved_view.set(Some((zoom.get(), pan_x.get(), pan_y.get())));
+                    zoom.set(1.0);
+                }
+                // Put the centre tangle in the middle of the view
+                let (w, h) = if da.width() > 0 {
+                    (da.width() as f64, da.height() as f64)
+                } else {
+                    (800.0, 560.0)
+                };
+                let z = zoom.get();
+                pan_x.set(w / 2.0 - map_layout::CENTER.0 * z);
+                pan_y.set(h / 2.0 - map_layout::CENTER.1 * z);
+            } else if let Some((z, px, py)) = saved_view.take() {
+                zoom.set(z);
+                pan_x.set(px);
+                pan_y.set(py);
+            }
+            da.queue_draw();
+        })
+    };
+
+    // Focus mode: click a neighbour to re-centre on it. Deferred by the
+    // double-click time so a double-click still opens the tangle.
+    if node_count > 0 {
+        let focus_click = gtk4::GestureClick::builder().button(1).build();
+        focus_click.set_propagation_phase(gtk4::PropagationPhase::Bubble);
+        let pending: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+        let nodes_fc = nodes.clone();
+        let zoom_fc = zoom.clone();
+        let pan_fc_x = pan_x.clone();
+        let pan_fc_y = pan_y.clone();
+        let focus_fc = focus.clone();
+        let lp_fc = last_pressed.clone();
+        let set_focus_fc = set_focus.clone();
+        focus_click.connect_pressed(move |gesture, n_press, x, y| {
+            if let Some(source) = pending.borrow_mut().take() {
+                source.remove();
+            }
+            if n_press != 1 { return; }
+            if gesture.current_event_state().contains(gtk4::gdk::ModifierType::CONTROL_MASK) { return; }
+            let Some(center) = focus_fc.borrow().as_ref().map(|f| f.center) else { return };
+            let z = zoom_fc.get();
+            if z == 0.0 { return; }
+            let mx = (x - pan_fc_x.get()) / z;
+            let my = (y - pan_fc_y.get()) / z;
+            let hit = node_at(&nodes_fc.borrow(), focus_fc.borrow().as_ref(), mx, my);
+            let Some(i) = hit.filter(|&i| i != center) else { return };
+            let delay = gtk4::Settings::default()
+                .map(|s| s.gtk_double_click_time())
+                .unwrap_or(400)
+                .max(0) as u64;
+            let pending_c = pending.clone();
+            let lp = lp_fc.clone();
+            let set_focus = set_focus_fc.clone();
+            let source = glib::timeout_add_local_once(std::time::Duration::from_millis(delay), move || {
+                pending_c.borrow_mut().take();
+                lp.set(Some(i));
+                set_focus(Some(i));
+            });
+            *pending.borrow_mut() = Some(source);
+        });
+        drawing_area.add_controller(focus_click);
+    }
+
+    let focus_toggle = gtk4::ToggleButton::builder()
+        .label("Focus")
+        .tooltip_text("Show only the last clicked tangle and its neighbours")
+        .build();
+    let hops_spin = gtk4::SpinButton::with_range(1.0, 3.0, 1.0);
+    hops_spin.set_tooltip_text(Some("Hops from the focused tangle"));
+    hops_spin.set_sensitive(false);
+
     // Search entry
     let search_entry = gtk4::SearchEntry::builder()
         .placeholder_text("Search nodes...")
@@ -779,22 +1195,156 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
 
     let sq = search_query.clone();
     let da_search = drawing_area.clone();
+    let nodes_search = nodes.clone();
+    let focus_search = focus.clone();
+    let zoom_search = zoom.clone();
+    let pan_search_x = pan_x.clone();
+    let pan_search_y = pan_y.clone();
     search_entry.connect_search_changed(move |entry| {
-        *sq.borrow_mut() = entry.text().to_string();
+        let query = entry.text().to_string();
+        *sq.borrow_mut() = query.clone();
+        // Bring the matches into view
+        let query = query.trim().to_lowercase();
+        if !query.is_empty() {
+            let nodes = nodes_search.borrow();
+            let focus = focus_search.borrow();
+            let matches: Vec<(f64, f64, &MapNode)> = nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, n)| n.title.to_lowercase().contains(&query))
+                .filter_map(|(i, n)| node_position(&nodes, focus.as_ref(), i).map(|(x, y)| (x, y, n)))
+                .collect();
+            let (w, h) = (da_search.width() as f64, da_search.height() as f64);
+            if let Some((z, px, py)) = fit_view(&matches, w, h) {
+                zoom_search.set(z);
+                pan_search_x.set(px);
+                pan_search_y.set(py);
+            }
+        }
         da_search.queue_draw();
     });
 
+    let filter_button = gtk4::MenuButton::builder()
+        .label(filter_label(hidden_count))
+        .tooltip_text("Filter by star, date and links")
+        .build();
+    let filter_popover = gtk4::Popover::new();
+    filter_popover.set_child(Some(&build_filter_panel(
+        db,
+        &filter,
+        &nodes,
+        &edges,
+        &drawing_area,
+        &filter_button,
+    )));
+    filter_button.set_popover(Some(&filter_popover));
+
     let hint_bar = gtk4::Label::builder()
-        .label("Drag node: Move   Drag empty: Pan   Scroll: Zoom   Ctrl+Click: Select   Shift+Alt+Drag: Lasso   Right-Drag: Link   Dbl-click: Open")
+        .label(MAP_HINTS)
         .css_classes(["tangle-map-hints"])
         .xalign(0.5)
         .build();
 
+    let lp_ft = last_pressed.clone();
+    let sel_ft = selected_nodes.clone();
+    let set_focus_ft = set_focus.clone();
+    let hops_ft = hops_spin.clone();
+    let hint_ft = hint_bar.clone();
+    focus_toggle.connect_toggled(move |btn| {
+        if !btn.is_active() {
+            set_focus_ft(None);
+            hops_ft.set_sensitive(false);
+            hint_ft.set_label(MAP_HINTS);
+            return;
+        }
+        let center = lp_ft.get().or_else(|| sel_ft.borrow().iter().next().copied());
+        let Some(center) = center else {
+            btn.set_active(false);
+            return;
+        };
+        set_focus_ft(Some(center));
+        hops_ft.set_sensitive(true);
+        hint_ft.set_label(FOCUS_HINTS);
+    });
+
+    let hops_focus = focus_hops.clone();
+    let focus_hs = focus.clone();
+    let set_focus_hs = set_focus.clone();
+    hops_spin.connect_value_changed(move |spin| {
+        hops_focus.set(spin.value_as_int().clamp(1, 3) as u32);
+        let center = focus_hs.borrow().as_ref().map(|f| f.center);
+        if center.is_some() {
+            set_focus_hs(center);
+        }
+    });
+
+    // Opened as a note's local graph: start focused on it
+    if let Some(idx) = focus_note.and_then(|id| id_to_idx.get(&id).copied()) {
+        last_pressed.set(Some(idx));
+        focus_toggle.set_active(true);
+    }
+
+    // Opened from the path finder: bring the whole path into view
+    if !path.borrow().is_empty() {
+        let nodes = nodes.borrow();
+        let points: Vec<(f64, f64, &MapNode)> = path
+            .borrow()
+            .iter()
+            .map(|&i| (nodes[i].x, nodes[i].y, &nodes[i]))
+            .collect();
+        if let Some((z, px, py)) = fit_view(&points, 800.0, 560.0) {
+            zoom.set(z);
+            pan_x.set(px);
+            pan_y.set(py);
+        }
+    }
+    let clear_path_btn = gtk4::Button::builder()
+        .label("Clear Path")
+        .tooltip_text("Stop highlighting the path")
+        .visible(!path.borrow().is_empty())
+        .build();
+    let path_clear = path.clone();
+    let da_clear = drawing_area.clone();
+    clear_path_btn.connect_clicked(move |btn| {
+        path_clear.borrow_mut().clear();
+        btn.set_visible(false);
+        da_clear.queue_draw();
+    });
+
     let bottom_bar = gtk4::Box::new(gtk4::Orientation::Horizontal, 4);
     bottom_bar.add_css_class("tangle-map-bottom");
     bottom_bar.append(&search_entry);
+    bottom_bar.append(&filter_button);
+    bottom_bar.append(&live_toggle);
+    bottom_bar.append(&focus_toggle);
+    bottom_bar.append(&hops_spin);
+    bottom_bar.append(&clear_path_btn);
     bottom_bar.append(&hint_bar);
 
+    let export_btn = gtk4::Button::builder()
+        .label("Export\u{2026}")
+        .tooltip_text("Save the map as SVG, PNG or PDF")
+        .build();
+    let dialog_ex = dialog.clone();
+    let nodes_ex = nodes.clone();
+    let edges_ex = edges.clone();
+    let focus_ex = focus.clone();
+    let zoom_ex = zoom.clone();
+    let pan_ex_x = pan_x.clone();
+    let pan_ex_y = pan_y.clone();
+    let da_ex = drawing_area.clone();
+    export_btn.connect_clicked(move |_| {
+        let view = MapView {
+            zoom: zoom_ex.get(),
+            pan_x: pan_ex_x.get(),
+            pan_y: pan_ex_y.get(),
+            width: da_ex.width().max(1) as f64,
+            height: da_ex.height().max(1) as f64,
+        };
+        show_export_dialog(dialog_ex.upcast_ref(), &nodes_ex, &edges_ex, &focus_ex, view);
+    });
+    bottom_bar.append(&export_btn);
+
     let vbox = gtk4::Box::new(gtk4::Orientation::Vertical, 0);
     vbox.append(&drawing_area);
     vbox.append(&bottom_bar);
@@ -802,3 +1352,748 @@ pub fn show_tangle_map(app: &gtk4::Application, parent: &ApplicationWindow, db:
     dialog.set_child(Some(&vbox));
     dialog.present();
 }
+
+const SETTING_FILTER_STAR: &str = "map_filter_star";
+const SETTING_FILTER_DATE_FIELD: &str = "map_filter_date_field";
+const SETTING_FILTER_FROM: &str = "map_filter_from";
+const SETTING_FILTER_TO: &str = "map_filter_to";
+const SETTING_FILTER_HIDE_ORPHANS: &str = "map_filter_hide_orphans";
+
+/// The star picker's colours, named for the filter menu.
+const STAR_COLORS: [(&str, &str); 5] = [
+    ("#ef5350", "Red"),
+    ("#ffca28", "Yellow"),
+    ("#66bb6a", "Green"),
+    ("#42a5f5", "Blue"),
+    ("#7e57c2", "Purple"),
+];
+
+/// Which tangles the map shows; remembered in `settings` between map sessions.
+struct MapFilter {
+    /// Empty for any, "starred" for any star, otherwise a star colour
+    star: String,
+    /// Apply the date range to updated_at rather than created_at
+    by_updated: bool,
+    /// Inclusive YYYY-MM-DD bounds
+    from: Option<String>,
+    to: Option<String>,
+    /// Hide tangles with no links in or out (counting hidden neighbours)
+    hide_orphans: bool,
+}
+
+impl MapFilter {
+    fn load(db: &Database) -> Self {
+        MapFilter {
+            star: db.get_setting(SETTING_FILTER_STAR).unwrap_or_default(),
+            by_updated: db.get_setting(SETTING_FILTER_DATE_FIELD).as_deref() == Some("updated"),
+            from: db.get_setting(SETTING_FILTER_FROM).and_then(|d| normalize_date(&d)),
+            to: db.get_setting(SETTING_FILTER_TO).and_then(|d| normalize_date(&d)),
+            hide_orphans: db.get_setting(SETTING_FILTER_HIDE_ORPHANS).as_deref() == Some("true"),
+        }
+    }
+
+    fn save(&self, db: &Database) {
+        let result = db
+            .set_setting(SETTING_FILTER_STAR, &self.star)
+            .and_then(|_| db.set_setting(SETTING_FILTER_DATE_FIELD, if self.by_updated { "updated" } else { "created" }))
+            .and_then(|_| db.set_setting(SETTING_FILTER_FROM, self.from.as_deref().unwrap_or("")))
+            .and_then(|_| db.set_setting(SETTING_FILTER_TO, self.to.as_deref().unwrap_or("")))
+            .and_then(|_| db.set_setting(SETTING_FILTER_HIDE_ORPHANS, if self.hide_orphans { "true" } else { "false" }));
+        if let Err(e) = result {
+            eprintln!("Error saving map filter: {}", e);
+        }
+    }
+
+    fn matches(&self, node: &MapNode, orphan: bool) -> bool {
+        let star_ok = match self.star.as_str() {
+            "" => true,
+            "starred" => node.star_color.is_some(),
+            color => node.star_color.as_deref().is_some_and(|c| c.eq_ignore_ascii_case(color)),
+        };
+        let date = if self.by_updated { &node.updated } else { &node.created };
+        let from_ok = self.from.as_ref().is_none_or(|from| date >= from);
+        let to_ok = self.to.as_ref().is_none_or(|to| date <= to);
+        star_ok && from_ok && to_ok && !(self.hide_orphans && orphan)
+    }
+}
+
+/// Mark the nodes `filter` excludes as hidden; returns how many are hidden.
+fn apply_filter(nodes: &mut [MapNode], edges: &[MapEdge], filter: &MapFilter) -> usize {
+    let mut linked = vec![false; nodes.len()];
+    for edge in edges {
+        if edge.source < linked.len() && edge.target < linked.len() {
+            linked[edge.source] = true;
+            linked[edge.target] = true;
+        }
+    }
+    let mut hidden = 0;
+    for (node, &linked) in nodes.iter_mut().zip(linked.iter()) {
+        node.hidden = !filter.matches(node, !linked);
+        if node.hidden {
+            hidden += 1;
+        }
+    }
+    hidden
+}
+
+fn filter_label(hidden: usize) -> String {
+    if hidden == 0 {
+        "Filter".to_string()
+    } else {
+        format!("Filter ({} hidden)", hidden)
+    }
+}
+
+/// RFC 3339 timestamp → local YYYY-MM-DD (empty if unparseable).
+fn local_date(rfc3339: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(rfc3339)
+        .map(|dt| dt.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+fn normalize_date(text: &str) -> Option<String> {
+    chrono::NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d")
+        .ok()
+        .map(|d| d.format("%Y-%m-%d").to_string())
+}
+
+/// Filter popover contents. Every change re-filters the map and is saved.
+fn build_filter_panel(
+    db: &Database,
+    filter: &Rc<RefCell<MapFilter>>,
+    nodes: &Rc<RefCell<Vec<MapNode>>>,
+    edges: &Rc<RefCell<Vec<MapEdge>>>,
+    drawing_area: &gtk4::DrawingArea,
+    filter_button: &gtk4::MenuButton,
+) -> gtk4::Grid {
+    let grid = gtk4::Grid::builder()
+        .row_spacing(6)
+        .column_spacing(8)
+        .margin_top(8).margin_bottom(8).margin_start(8).margin_end(8)
+        .build();
+
+    let mut star_labels = vec!["Any".to_string(), "Starred".to_string()];
+    star_labels.extend(STAR_COLORS.iter().map(|(_, name)| name.to_string()));
+    let star_labels: Vec<&str> = star_labels.iter().map(String::as_str).collect();
+    let star_drop = gtk4::DropDown::from_strings(&star_labels);
+    let date_field = gtk4::DropDown::from_strings(&["Created", "Updated"]);
+    let from_entry = gtk4::Entry::builder().placeholder_text("From YYYY-MM-DD").width_chars(14).build();
+    let to_entry = gtk4::Entry::builder().placeholder_text("To YYYY-MM-DD").width_chars(14).build();
+    let orphans_check = gtk4::CheckButton::with_label("Hide tangles without links");
+    let reset_btn = gtk4::Button::with_label("Show All");
+
+    {
+        let f = filter.borrow();
+        star_drop.set_selected(match f.star.as_str() {
+            "" => 0,
+            "starred" => 1,
+            color => STAR_COLORS
+                .iter()
+                .position(|(c, _)| c.eq_ignore_ascii_case(color))
+                .map(|p| p as u32 + 2)
+                .unwrap_or(0),
+        });
+        date_field.set_selected(if f.by_updated { 1 } else { 0 });
+        from_entry.set_text(f.from.as_deref().unwrap_or(""));
+        to_entry.set_text(f.to.as_deref().unwrap_or(""));
+        orphans_check.set_active(f.hide_orphans);
+    }
+
+    grid.attach(&gtk4::Label::builder().label("Star").xalign(0.0).build(), 0, 0, 1, 1);
+    grid.attach(&star_drop, 1, 0, 2, 1);
+    grid.attach(&gtk4::Label::builder().label("Date").xalign(0.0).build(), 0, 1, 1, 1);
+    grid.attach(&date_field, 1, 1, 2, 1);
+    grid.attach(&from_entry, 1, 2, 1, 1);
+    grid.attach(&to_entry, 2, 2, 1, 1);
+    grid.attach(&orphans_check, 0, 3, 3, 1);
+    grid.attach(&reset_btn, 0, 4, 3, 1);
+
+    // Read the widgets into the filter, re-filter and remember it
+    let refresh: Rc<dyn Fn()> = {
+        let db = db.clone();
+        let filter = filter.clone();
+        let nodes = nodes.clone();
+        let edges = edges.clone();
+        let da = drawing_area.clone();
+        let button = filter_button.clone();
+        let star_drop = star_drop.clone();
+        let date_field = date_field.clone();
+        let from_entry = from_entry.clone();
+        let to_entry = to_entry.clone();
+        let orphans_check = orphans_check.clone();
+        Rc::new(move || {
+            // Bad dates are flagged and left out of the filter
+            let read_date = |entry: &gtk4::Entry| {
+                let text = entry.text();
+                let date = normalize_date(&text);
+                if date.is_none() && !text.trim().is_empty() {
+                    entry.add_css_class("error");
+                } else {
+                    entry.remove_css_class("error");
+                }
+                date
+            };
+            let mut f = filter.borrow_mut();
+            f.star = match star_drop.selected() {
+                0 => String::new(),
+                1 => "starred".to_string(),
+                n => STAR_COLORS
+                    .get(n as usize - 2)
+                    .map(|(c, _)| c.to_string())
+                    .unwrap_or_default(),
+            };
+            f.by_updated = date_field.selected() == 1;
+            f.from = read_date(&from_entry);
+            f.to = read_date(&to_entry);
+            f.hide_orphans = orphans_check.is_active();
+            f.save(&db);
+            let hidden = apply_filter(&mut nodes.borrow_mut(), &edges.borrow(), &f);
+            button.set_label(&filter_label(hidden));
+            da.queue_draw();
+        })
+    };
+
+    let r = refresh.clone();
+    star_drop.connect_selected_notify(move |_| r());
+    let r = refresh.clone();
+    date_field.connect_selected_notify(move |_| r());
+    let r = refresh.clone();
+    from_entry.connect_changed(move |_| r());
+    let r = refresh.clone();
+    to_entry.connect_changed(move |_| r());
+    let r = refresh.clone();
+    orphans_check.connect_toggled(move |_| r());
+
+    reset_btn.connect_clicked(move |_| {
+        star_drop.set_selected(0);
+        from_entry.set_text("");
+        to_entry.set_text("");
+        orphans_check.set_active(false);
+    });
+
+    grid
+}
+
+const MAP_HINTS: &str = "Drag node: Move   Drag empty: Pan   Scroll: Zoom   Ctrl+Click: Select   Shift+Alt+Drag: Lasso   Right-Drag: Link   Right-click link: Label/Delete   Drag link end: Retarget   Dbl-click: Open";
+const FOCUS_HINTS: &str = "Click: Re-centre   Drag: Pan   Scroll: Zoom   Dbl-click: Open";
+
+/// Edge colours in focus mode: links leaving the centre tangle, and links into it.
+const OUTGOING_RGB: (f64, f64, f64) = (0.7, 0.53, 1.0);
+const INCOMING_RGB: (f64, f64, f64) = (1.0, 0.6, 0.2);
+
+/// Where node `i` is drawn: its map position, or its ring position in focus
+/// mode (`None` when the filter or the focus view hides it).
+fn node_position(nodes: &[MapNode], focus: Option<&FocusView>, i: usize) -> Option<(f64, f64)> {
+    if nodes.get(i).is_none_or(|n| n.hidden) {
+        return None;
+    }
+    match focus {
+        Some(f) => f.positions.get(&i).copied(),
+        None => nodes.get(i).map(|n| (n.x, n.y)),
+    }
+}
+
+/// The visible node under the world point (`mx`, `my`).
+fn node_at(nodes: &[MapNode], focus: Option<&FocusView>, mx: f64, my: f64) -> Option<usize> {
+    (0..nodes.len()).find(|&i| {
+        let Some((x, y)) = node_position(nodes, focus, i) else { return false };
+        (mx - x).abs() <= nodes[i].w / 2.0 && (my - y).abs() <= nodes[i].h / 2.0
+    })
+}
+
+/// Arrowhead at the edge of `target`'s box, pointing along `from` → `to`.
+fn draw_arrowhead(cr: &gtk4::cairo::Context, from: (f64, f64), to: (f64, f64), target: &MapNode) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let len = (dx * dx + dy * dy).sqrt();
+    if len < 1e-6 {
+        return;
+    }
+    let (ux, uy) = (dx / len, dy / len);
+    let to_side = if ux.abs() > 1e-6 { target.w / 2.0 / ux.abs() } else { f64::INFINITY };
+    let to_top = if uy.abs() > 1e-6 { target.h / 2.0 / uy.abs() } else { f64::INFINITY };
+    let back = to_side.min(to_top);
+    let (px, py) = (to.0 - ux * back, to.1 - uy * back);
+    let size = 8.0;
+    cr.move_to(px, py);
+    cr.line_to(px - ux * size + uy * size * 0.5, py - uy * size - ux * size * 0.5);
+    cr.line_to(px - ux * size - uy * size * 0.5, py - uy * size + ux * size * 0.5);
+    cr.close_path();
+    let _ = cr.fill();
+}
+
+/// Room a star takes to the left of a node's title.
+const STAR_SPACE: f64 = 14.0;
+
+/// Screen-pixel distance within which the pointer hits an edge or its end.
+const EDGE_HIT_TOLERANCE: f64 = 6.0;
+
+/// Point `u` (0 to 1) along the curve `draw_edges` draws from `s` to `t`.
+fn edge_curve_point(s: (f64, f64), t: (f64, f64), u: f64) -> (f64, f64) {
+    let m = ((s.0 + t.0) / 2.0, (s.1 + t.1) / 2.0 - 20.0);
+    let v = 1.0 - u;
+    // Cubic Bézier with both control points at m
+    let a = v * v * v;
+    let b = 3.0 * v * u;
+    let c = u * u * u;
+    (a * s.0 + b * m.0 + c * t.0, a * s.1 + b * m.1 + c * t.1)
+}
+
+/// The visible edge passing within `tolerance` of (`mx`, `my`), closest first.
+fn edge_at(nodes: &[MapNode], edges: &[MapEdge], focus: Option<&FocusView>, mx: f64, my: f64, tolerance: f64) -> Option<usize> {
+    const SEGMENTS: usize = 24;
+    let mut best: Option<(usize, f64)> = None;
+    for (i, edge) in edges.iter().enumerate() {
+        let (Some(s), Some(t)) = (
+            node_position(nodes, focus, edge.source),
+            node_position(nodes, focus, edge.target),
+        ) else {
+            continue;
+        };
+        let mut prev = s;
+        for k in 1..=SEGMENTS {
+            let next = edge_curve_point(s, t, k as f64 / SEGMENTS as f64);
+            let d = point_segment_distance((mx, my), prev, next);
+            if d <= tolerance && best.is_none_or(|(_, bd)| d < bd) {
+                best = Some((i, d));
+            }
+            prev = next;
+        }
+    }
+    best.map(|(i, _)| i)
+}
+
+fn point_segment_distance(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len_sq = dx * dx + dy * dy;
+    let u = if len_sq > 0.0 {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (p.0 - (a.0 + u * dx)).hypot(p.1 - (a.1 + u * dy))
+}
+
+/// Where an edge's curve leaves its target's box on the full map: the grip
+/// for dragging the link onto another tangle.
+fn edge_end_handle(nodes: &[MapNode], edge: &MapEdge) -> Option<(f64, f64)> {
+    let s = node_position(nodes, None, edge.source)?;
+    let t = node_position(nodes, None, edge.target)?;
+    let target = &nodes[edge.target];
+    (0..=50)
+        .map(|k| edge_curve_point(s, t, 1.0 - k as f64 / 50.0))
+        .find(|&(x, y)| (x - t.0).abs() > target.w / 2.0 || (y - t.1).abs() > target.h / 2.0)
+}
+
+/// "Source → Target", plus the label when there is one.
+fn edge_description(nodes: &[MapNode], edge: &MapEdge) -> String {
+    let mut text = format!("{} \u{2192} {}", nodes[edge.source].title, nodes[edge.target].title);
+    if let Some(ref label) = edge.label {
+        text.push_str(&format!("\n\u{201c}{}\u{201d}", label));
+    }
+    text
+}
+
+fn draw_edges(
+    cr: &gtk4::cairo::Context,
+    nodes: &[MapNode],
+    edges: &[MapEdge],
+    focus: Option<&FocusView>,
+    hovered: Option<usize>,
+) {
+    // In focus mode links from and to the centre get their own colours
+    for (i, edge) in edges.iter().enumerate() {
+        let (Some((sx, sy)), Some((tx, ty))) = (
+            node_position(nodes, focus, edge.source),
+            node_position(nodes, focus, edge.target),
+        ) else {
+            continue;
+        };
+        match focus {
+            Some(f) if edge.source == f.center => {
+                cr.set_source_rgba(OUTGOING_RGB.0, OUTGOING_RGB.1, OUTGOING_RGB.2, 0.9);
+                cr.set_line_width(2.0);
+            }
+            Some(f) if edge.target == f.center => {
+                cr.set_source_rgba(INCOMING_RGB.0, INCOMING_RGB.1, INCOMING_RGB.2, 0.9);
+                cr.set_line_width(2.0);
+            }
+            Some(_) => {
+                cr.set_source_rgba(0.6, 0.6, 0.7, 0.35);
+                cr.set_line_width(1.0);
+            }
+            None => {
+                cr.set_source_rgba(0.7, 0.53, 1.0, 0.4);
+                cr.set_line_width(1.5);
+            }
+        }
+        let is_hovered = hovered == Some(i);
+        if is_hovered {
+            cr.set_source_rgba(1.0, 0.8, 0.2, 0.9);
+            cr.set_line_width(3.0);
+        }
+        let mx = (sx + tx) / 2.0;
+        let my = (sy + ty) / 2.0 - 20.0;
+        cr.move_to(sx, sy);
+        cr.curve_to(mx, my, mx, my, tx, ty);
+        let _ = cr.stroke();
+        if focus.is_some() || is_hovered {
+            draw_arrowhead(cr, (mx, my), (tx, ty), &nodes[edge.target]);
+        }
+        // Grip for dragging the link's end to another tangle
+        if is_hovered && focus.is_none() {
+            if let Some((hx, hy)) = edge_end_handle(nodes, edge) {
+                cr.arc(hx, hy, 4.0, 0.0, std::f64::consts::TAU);
+                let _ = cr.fill();
+            }
+        }
+
+        // Label on a small plate at the middle of the curve
+        if let Some(ref label) = edge.label {
+            let (lx, ly) = edge_curve_point((sx, sy), (tx, ty), 0.5);
+            cr.set_font_size(10.0);
+            if let Ok(extents) = cr.text_extents(label) {
+                let (w, h) = (extents.width() + 8.0, extents.height() + 6.0);
+                cr.set_source_rgba(0.1, 0.1, 0.18, 0.85);
+                cr.rectangle(lx - w / 2.0, ly - h / 2.0, w, h);
+                let _ = cr.fill();
+                cr.set_source_rgba(0.8, 0.75, 0.95, 1.0);
+                cr.move_to(lx - extents.width() / 2.0 - extents.x_bearing(), ly - extents.height() / 2.0 - extents.y_bearing());
+                let _ = cr.show_text(label);
+            }
+        }
+    }
+}
+
+fn draw_nodes(
+    cr: &gtk4::cairo::Context,
+    nodes: &[MapNode],
+    focus: Option<&FocusView>,
+    query: &str,
+    selected: &std::collections::HashSet<usize>,
+) {
+    for (i, node) in nodes.iter().enumerate() {
+        let Some((cx, cy)) = node_position(nodes, focus, i) else { continue };
+        let x = cx - node.w / 2.0;
+        let y = cy - node.h / 2.0;
+
+        let nw = node.w.max(1.0);
+        let nh = node.h.max(1.0);
+        rounded_rect(cr, x, y, nw, nh, 6.0);
+
+        let highlighted = !query.is_empty() && node.title.to_lowercase().contains(query);
+        let is_selected = selected.contains(&i);
+        let is_center = focus.is_some_and(|f| f.center == i);
+        // Tangles two or more hops out are drawn fainter
+        let alpha = match focus.and_then(|f| f.distance.get(&i)) {
+            Some(&d) if d > 1 => 0.6,
+            _ => 1.0,
+        };
+
+        cr.set_source_rgba(0.1, 0.1, 0.18, 0.9);
+        let _ = cr.fill_preserve();
+        if is_center {
+            cr.set_source_rgba(1.0, 0.8, 0.2, 0.95);
+            cr.set_line_width(3.0);
+        } else if is_selected {
+            // Cyan border for selected nodes
+            cr.set_source_rgba(0.2, 0.8, 1.0, 0.9);
+            cr.set_line_width(3.0);
+        } else if highlighted {
+            cr.set_source_rgba(0.2, 1.0, 0.4, 0.9);
+            cr.set_line_width(3.0);
+        } else {
+            cr.set_source_rgba(0.7, 0.53, 1.0, 0.7 * alpha);
+            cr.set_line_width(1.5);
+        }
+        let _ = cr.stroke();
+
+        // Star, then the title centred in the remaining space
+        let star = node.star_color.as_deref().and_then(|c| gtk4::gdk::RGBA::parse(c).ok());
+        let text_left = match star {
+            Some(color) => {
+                cr.set_source_rgba(color.red() as f64, color.green() as f64, color.blue() as f64, alpha);
+                star_path(cr, x + 6.0 + STAR_SPACE / 2.0, y + nh / 2.0, 5.5);
+                let _ = cr.fill();
+                x + STAR_SPACE
+            }
+            None => x,
+        };
+        let text_w = nw - (text_left - x);
+
+        if is_center {
+            cr.set_source_rgba(1.0, 0.8, 0.2, 1.0);
+        } else if is_selected {
+            cr.set_source_rgba(0.2, 0.8, 1.0, 1.0);
+        } else if highlighted {
+            cr.set_source_rgba(0.2, 1.0, 0.4, 1.0);
+        } else {
+            cr.set_source_rgba(0.88, 0.88, 0.88, alpha);
+        }
+        cr.set_font_size(11.0);
+        let (text_x, text_y) = if let Ok(extents) = cr.text_extents(&node.title) {
+            (text_left + (text_w - extents.width()) / 2.0, y + nh / 2.0 + extents.height() / 2.0)
+        } else {
+            (text_left + 10.0, y + nh / 2.0 + 4.0)
+        };
+        cr.move_to(text_x, text_y);
+        let _ = cr.show_text(&node.title);
+    }
+}
+
+/// Colour of a highlighted shortest path.
+const PATH_RGB: (f64, f64, f64) = (1.0, 0.4, 0.7);
+
+/// Links along a highlighted path, drawn over the ordinary edges.
+fn draw_path_links(cr: &gtk4::cairo::Context, nodes: &[MapNode], focus: Option<&FocusView>, path: &[usize]) {
+    let (r, g, b) = PATH_RGB;
+    cr.set_source_rgba(r, g, b, 0.9);
+    cr.set_line_width(4.0);
+    for pair in path.windows(2) {
+        let (Some((sx, sy)), Some((tx, ty))) = (node_position(nodes, focus, pair[0]), node_position(nodes, focus, pair[1])) else {
+            continue;
+        };
+        let mx = (sx + tx) / 2.0;
+        let my = (sy + ty) / 2.0 - 20.0;
+        cr.move_to(sx, sy);
+        cr.curve_to(mx, my, mx, my, tx, ty);
+        let _ = cr.stroke();
+    }
+}
+
+/// Outlines the tangles on a highlighted path.
+fn draw_path_nodes(cr: &gtk4::cairo::Context, nodes: &[MapNode], focus: Option<&FocusView>, path: &[usize]) {
+    let (r, g, b) = PATH_RGB;
+    cr.set_source_rgba(r, g, b, 0.95);
+    cr.set_line_width(3.0);
+    for &i in path {
+        let Some((cx, cy)) = node_position(nodes, focus, i) else { continue };
+        let node = &nodes[i];
+        rounded_rect(cr, cx - node.w / 2.0 - 3.0, cy - node.h / 2.0 - 3.0, node.w + 6.0, node.h + 6.0, 8.0);
+        let _ = cr.stroke();
+    }
+}
+
+fn rounded_rect(cr: &gtk4::cairo::Context, x: f64, y: f64, w: f64, h: f64, radius: f64) {
+    cr.new_sub_path();
+    cr.arc(x + w - radius, y + radius, radius, -std::f64::consts::FRAC_PI_2, 0.0);
+    cr.arc(x + w - radius, y + h - radius, radius, 0.0, std::f64::consts::FRAC_PI_2);
+    cr.arc(x + radius, y + h - radius, radius, std::f64::consts::FRAC_PI_2, std::f64::consts::PI);
+    cr.arc(x + radius, y + radius, radius, std::f64::consts::PI, 3.0 * std::f64::consts::FRAC_PI_2);
+    cr.close_path();
+}
+
+/// Zoom and pan that fit nodes at the given centres into a `w` × `h` view.
+fn fit_view(points: &[(f64, f64, &MapNode)], w: f64, h: f64) -> Option<(f64, f64, f64)> {
+    if points.is_empty() || w <= 0.0 || h <= 0.0 {
+        return None;
+    }
+    let min_x = points.iter().map(|(x, _, n)| x - n.w / 2.0).fold(f64::INFINITY, f64::min);
+    let max_x = points.iter().map(|(x, _, n)| x + n.w / 2.0).fold(f64::NEG_INFINITY, f64::max);
+    let min_y = points.iter().map(|(_, y, n)| y - n.h / 2.0).fold(f64::INFINITY, f64::min);
+    let max_y = points.iter().map(|(_, y, n)| y + n.h / 2.0).fold(f64::NEG_INFINITY, f64::max);
+    let margin = 60.0;
+    let z = ((w - 2.0 * margin) / (max_x - min_x))
+        .min((h - 2.0 * margin) / (max_y - min_y))
+        .clamp(0.2, 2.0);
+    Some((z, w / 2.0 - (min_x + max_x) / 2.0 * z, h / 2.0 - (min_y + max_y) / 2.0 * z))
+}
+
+/// Five-pointed star path centred on (`cx`, `cy`).
+fn star_path(cr: &gtk4::cairo::Context, cx: f64, cy: f64, r: f64) {
+    for k in 0..10 {
+        let radius = if k % 2 == 0 { r } else { r * 0.45 };
+        let angle = k as f64 * std::f64::consts::PI / 5.0 - std::f64::consts::FRAC_PI_2;
+        let (px, py) = (cx + radius * angle.cos(), cy + radius * angle.sin());
+        if k == 0 {
+            cr.move_to(px, py);
+        } else {
+            cr.line_to(px, py);
+        }
+    }
+    cr.close_path();
+}
+
+/// Key for the focus-mode edge colours, drawn in screen space.
+fn draw_focus_legend(cr: &gtk4::cairo::Context, nodes: &[MapNode], focus: Option<&FocusView>) {
+    let Some(center) = focus.and_then(|f| nodes.get(f.center)) else { return };
+    cr.set_font_size(11.0);
+    let entries = [
+        (OUTGOING_RGB, format!("Links from \"{}\"", center.title)),
+        (INCOMING_RGB, format!("Links to \"{}\"", center.title)),
+    ];
+    for (row, ((r, g, b), label)) in entries.iter().enumerate() {
+        let y = 20.0 + row as f64 * 18.0;
+        cr.set_source_rgba(*r, *g, *b, 0.9);
+        cr.set_line_width(2.0);
+        cr.move_to(12.0, y - 4.0);
+        cr.line_to(32.0, y - 4.0);
+        let _ = cr.stroke();
+        cr.set_source_rgba(0.88, 0.88, 0.88, 1.0);
+        cr.move_to(40.0, y);
+        let _ = cr.show_text(label);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ExportFormat {
+    Svg,
+    Png,
+    Pdf,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Svg => "svg",
+            ExportFormat::Png => "png",
+            ExportFormat::Pdf => "pdf",
+        }
+    }
+}
+
+/// The on-screen view an export reproduces: zoom, pan and widget size.
+#[derive(Clone, Copy)]
+struct MapView {
+    zoom: f64,
+    pan_x: f64,
+    pan_y: f64,
+    width: f64,
+    height: f64,
+}
+
+/// Render the map to `path`. With `view` the export matches the window
+/// (zoom, pan, filter, focus); without, it fits every tangle at 1:1.
+fn export_map(
+    path: &std::path::Path,
+    format: ExportFormat,
+    nodes: &[MapNode],
+    edges: &[MapEdge],
+    focus: Option<&FocusView>,
+    view: Option<MapView>,
+) -> std::io::Result<()> {
+    use gtk4::cairo;
+
+    let whole: Vec<MapNode>;
+    let (nodes, focus, view) = match view {
+        Some(view) => (nodes, focus, view),
+        None => {
+            whole = nodes.iter().cloned().map(|mut n| { n.hidden = false; n }).collect();
+            let margin = 40.0;
+            let min_x = whole.iter().map(|n| n.x - n.w / 2.0).fold(f64::INFINITY, f64::min);
+            let max_x = whole.iter().map(|n| n.x + n.w / 2.0).fold(f64::NEG_INFINITY, f64::max);
+            let min_y = whole.iter().map(|n| n.y - n.h / 2.0).fold(f64::INFINITY, f64::min);
+            let max_y = whole.iter().map(|n| n.y + n.h / 2.0).fold(f64::NEG_INFINITY, f64::max);
+            let view = if whole.is_empty() {
+                MapView { zoom: 1.0, pan_x: 0.0, pan_y: 0.0, width: 800.0, height: 600.0 }
+            } else {
+                MapView {
+                    zoom: 1.0,
+                    pan_x: margin - min_x,
+                    pan_y: margin - min_y,
+                    width: max_x - min_x + 2.0 * margin,
+                    height: max_y - min_y + 2.0 * margin,
+                }
+            };
+            (whole.as_slice(), None, view)
+        }
+    };
+
+    let paint = |cr: &cairo::Context| {
+        cr.set_source_rgba(0.1, 0.1, 0.18, 1.0);
+        cr.rectangle(0.0, 0.0, view.width, view.height);
+        let _ = cr.fill();
+        let _ = cr.save();
+        cr.translate(view.pan_x, view.pan_y);
+        cr.scale(view.zoom, view.zoom);
+        draw_edges(cr, nodes, edges, focus, None);
+        draw_nodes(cr, nodes, focus, "", &std::collections::HashSet::new());
+        let _ = cr.restore();
+        draw_focus_legend(cr, nodes, focus);
+    };
+
+    let to_io = std::io::Error::other;
+    match format {
+        ExportFormat::Svg => {
+            let surface = cairo::SvgSurface::new(view.width, view.height, Some(path)).map_err(to_io)?;
+            paint(&cairo::Context::new(&surface).map_err(to_io)?);
+            surface.finish();
+            surface.status().map_err(to_io)
+        }
+        ExportFormat::Pdf => {
+            let surface = cairo::PdfSurface::new(view.width, view.height, path).map_err(to_io)?;
+            paint(&cairo::Context::new(&surface).map_err(to_io)?);
+            surface.finish();
+            surface.status().map_err(to_io)
+        }
+        ExportFormat::Png => {
+            // Twice the on-screen resolution, for slides and print
+            let scale = 2.0;
+            let surface = cairo::ImageSurface::create(
+                cairo::Format::ARgb32,
+                (view.width * scale).ceil() as i32,
+                (view.height * scale).ceil() as i32,
+            )
+            .map_err(to_io)?;
+            {
+                let cr = cairo::Context::new(&surface).map_err(to_io)?;
+                cr.scale(scale, scale);
+                paint(&cr);
+            }
+            let mut file = std::fs::File::create(path)?;
+            surface.write_to_png(&mut file).map_err(std::io::Error::other)
+        }
+    }
+}
+
+/// Options and file chooser for `export_map`.
+fn show_export_dialog(
+    parent: &gtk4::Window,
+    nodes: &Rc<RefCell<Vec<MapNode>>>,
+    edges: &Rc<RefCell<Vec<MapEdge>>>,
+    focus: &Rc<RefCell<Option<FocusView>>>,
+    current_view: MapView,
+) {
+    let scope_drop = gtk4::DropDown::from_strings(&["Current view", "Whole graph"]);
+    let scope = scope_drop.clone();
+    let nodes = nodes.clone();
+    let edges = edges.clone();
+    let focus = focus.clone();
+    let extras = crate::dialogs::export_dialog(
+        parent,
+        "Export Tangle Map",
+        "tangle-map",
+        &[("SVG", ExportFormat::Svg), ("PNG", ExportFormat::Png), ("PDF", ExportFormat::Pdf)],
+        ExportFormat::extension,
+        move |format, path, status, _| {
+            let view = (scope.selected() == 0).then_some(current_view);
+            let result = export_map(&path, format, &nodes.borrow(), &edges.borrow(), focus.borrow().as_ref(), view);
+            status.set_text(&match result {
+                Ok(()) => format!("Saved {}", path.display()),
+                Err(e) => format!("Export failed: {}", e),
+            });
+        },
+    );
+    extras.append(&gtk4::Label::builder().label("Contents").xalign(0.0).css_classes(["dim-label"]).build());
+    extras.append(&scope_drop);
+}
+
+fn build_layout(nodes: &[MapNode], edges: &[MapEdge], keep_saved: bool) -> ForceLayout {
+    ForceLayout::new(
+        nodes.iter().map(|n| (n.x, n.y)).collect(),
+        nodes.iter().map(|n| keep_saved && n.has_saved_pos).collect(),
+        edges.iter().map(|e| (e.source, e.target)).collect(),
+    )
+}
+
+/// Persist every node's map position on a background thread.
+fn save_map_positions(db: &Database, nodes: &[MapNode]) {
+    let to_save: Vec<(i64, f64, f64)> = nodes.iter().map(|n| (n.note_id, n.x, n.y)).collect();
+    let db = db.clone();
+    std::thread::spawn(move || {
+        for (id, x, y) in to_save {
+            if let Err(e) = db.update_note_map_position(id, x, y) {
+                eprintln!("Error saving node position: {}", e);
+            }
+        }
+    });
+}
@@ -642,7 +642,7 @@ pub fn apply_note_theme(
 pub fn apply_global_theme(db: &Database) {
     let bg = db.get_setting("global_theme_bg").unwrap_or_else(|| "#1a1a2e".to_string());
     let fg = db.get_setting("global_theme_fg").unwrap_or_else(|| "#e0e0e0".to_string());
-    let accent = db.get_setting("global_theme_accent").unwrap_or_else(|| "#b388ff".to_string());
+    let accent = global_accent(db);
 
     let css = format!(r#"
         .note-window {{
@@ -715,6 +715,9 @@ pub fn apply_global_theme(db: &Database) {
             background-color: alpha({accent}, 0.25);
             border-color: {accent};
         }}
+        .backlink-context {{
+            color: alpha({fg}, 0.7);
+        }}
         .note-list-dialog {{
             background-color: {bg};
             color: {fg};
@@ -893,3 +896,19 @@ pub fn parse_hex_triple(hex: &str) -> (f64, f64, f64) {
         (0.5, 0.5, 0.5)
     }
 }
+
+/// The global accent colour, as `apply_global_theme` uses it.
+pub fn global_accent(db: &Database) -> String {
+    db.get_setting("global_theme_accent").unwrap_or_else(|| "#b388ff".to_string())
+}
+
+/// Pango markup for a search snippet or link context: the text between
+/// `SNIPPET_START` and `SNIPPET_END` is bold in `accent`.
+pub fn highlight_markup(text: &str, accent: &str) -> String {
+    gtk4::glib::markup_escape_text(&text.replace('\n', " "))
+        .replace(
+            crate::database::SNIPPET_START,
+            &format!("<span foreground=\"{}\" weight=\"bold\">", gtk4::glib::markup_escape_text(accent)),
+        )
+        .replace(crate::database::SNIPPET_END, "</span>")
+}
@@ -0,0 +1,362 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use crate::database::{Database, Note};
+use crate::html::{escape_html, escape_html_attr, tokenize_html, HtmlToken};
+use crate::markdown::{plan_import_title, ImportOptions, ImportReport};
+
+/// Highlight color used for Tomboy's `<highlight>` markup.
+const HIGHLIGHT_COLOR: &str = "#fff176";
+
+/// A Tomboy/Gnote note read from its `.note` file.
+#[derive(Debug, Clone)]
+pub struct TomboyNote {
+    pub title: String,
+    pub content: String,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// Where Gnote or Tomboy keep their notes, if either exists.
+pub fn default_notes_dir() -> Option<PathBuf> {
+    let data = dirs::data_dir()?;
+    ["gnote", "tomboy"]
+        .iter()
+        .map(|name| data.join(name))
+        .find(|dir| dir.is_dir())
+}
+
+/// Import every `.note` file in `dir` (templates excluded). Internal links
+/// become tangle links and the notes keep their create/change dates.
+pub fn import_notes_dir(db: &Database, dir: &Path, options: &ImportOptions) -> std::io::Result<ImportReport> {
+    let mut report = ImportReport::default();
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "note"))
+        .collect();
+    paths.sort();
+
+    let mut notes: Vec<TomboyNote> = Vec::new();
+    for path in &paths {
+        match std::fs::read_to_string(path) {
+            Ok(xml) => match parse_note(&xml) {
+                Some(note) if note.tags.iter().any(|t| t == "system:template") => {}
+                Some(note) => notes.push(note),
+                None => report.errors.push(format!("{}: not a Tomboy note", path.display())),
+            },
+            Err(e) => report.errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    // Titles are decided before converting so links follow any renames
+    let mut taken: HashSet<String> = HashSet::new();
+    let mut titles: HashMap<String, String> = HashMap::new();
+    let mut planned: Vec<(TomboyNote, String)> = Vec::new();
+    for note in notes {
+        if let Some(title) = plan_import_title(db, &mut taken, &note.title, options, &mut report) {
+            titles.entry(note.title.to_lowercase()).or_insert_with(|| title.clone());
+            planned.push((note, title));
+        }
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    for (tomboy, title) in planned {
+        let resolve = |target: &str| {
+            titles.get(&target.to_lowercase()).cloned().unwrap_or_else(|| target.to_string())
+        };
+        let content = note_content_to_html(&tomboy.content, &resolve);
+        let note = Note {
+            id: None,
+            title: title.clone(),
+            content,
+            created_at: tomboy.created_at.clone().unwrap_or_else(|| now.clone()),
+            updated_at: tomboy.updated_at.clone().or(tomboy.created_at).unwrap_or_else(|| now.clone()),
+            position_x: 100.0,
+            position_y: 100.0,
+            is_visible: false,
+            always_on_top: false,
+            width: 500,
+            height: 400,
+            theme_bg: None,
+            theme_fg: None,
+            theme_accent: None,
+            custom_colors: None,
+            chromeless: false,
+            star_color: None,
+        };
+        match db.create_note(&note) {
+            Ok(_) => report.imported.push(title),
+            Err(e) => report.errors.push(format!("{}: {}", title, e)),
+        }
+    }
+    Ok(report)
+}
+
+/// Read title, dates, tags and the raw `<note-content>` markup from a
+/// `.note` file. Returns None if there is no title.
+pub fn parse_note(xml: &str) -> Option<TomboyNote> {
+    let title = element_text(xml, "title")?.trim().to_string();
+    if title.is_empty() {
+        return None;
+    }
+    let content = inner_xml(xml, "note-content").unwrap_or_default().to_string();
+    let tags = tokenize_html(xml)
+        .into_iter()
+        .scan(false, |in_tag, token| {
+            Some(match token {
+                HtmlToken::StartTag(name, _) if name == "tag" => { *in_tag = true; None }
+                HtmlToken::EndTag(name) if name == "tag" => { *in_tag = false; None }
+                HtmlToken::Text(text) if *in_tag => Some(text.trim().to_string()),
+                _ => None,
+            })
+        })
+        .flatten()
+        .collect();
+    Some(TomboyNote {
+        title,
+        content,
+        created_at: element_text(xml, "create-date").and_then(|d| normalize_date(&d)),
+        updated_at: element_text(xml, "last-change-date").and_then(|d| normalize_date(&d)),
+        tags,
+    })
+}
+
+/// Tomboy dates carry 7 fractional digits and a local offset; store them as UTC RFC 3339.
+fn normalize_date(date: &str) -> Option<String> {
+    chrono::DateTime::parse_from_rfc3339(date.trim())
+        .ok()
+        .map(|d| d.with_timezone(&chrono::Utc).to_rfc3339())
+}
+
+fn inner_xml<'a>(xml: &'a str, element: &str) -> Option<&'a str> {
+    let open = format!("<{}", element);
+    let start = xml.find(&open)?;
+    let content_start = start + xml[start..].find('>')? + 1;
+    let end = xml[content_start..].find(&format!("</{}>", element))?;
+    Some(&xml[content_start..content_start + end])
+}
+
+/// Decoded text of the first `<element>`.
+fn element_text(xml: &str, element: &str) -> Option<String> {
+    let inner = inner_xml(xml, element)?;
+    Some(
+        tokenize_html(inner)
+            .into_iter()
+            .filter_map(|t| match t {
+                HtmlToken::Text(text) => Some(text),
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Style {
+    bold: bool,
+    italic: bool,
+    strike: bool,
+    highlight: bool,
+    mono: bool,
+    size: Option<String>,
+    /// Note title for internal/broken links, URL for `link:url`
+    link: Option<(bool, String)>,
+    list: bool,
+}
+
+/// Convert Tomboy `<note-content>` markup to the Tangles HTML subset.
+/// The first line (the note's title) is dropped; whole lines in huge/large
+/// text become h1/h2, whole monospace lines become code, list items become
+/// bullets (nesting is flattened).
+pub fn note_content_to_html(content: &str, resolve_title: &dyn Fn(&str) -> String) -> String {
+    // Flatten markup into styled runs, split into lines
+    let mut lines: Vec<Vec<(String, Style)>> = vec![Vec::new()];
+    let mut style = Style::default();
+    let mut depth: HashMap<&'static str, usize> = HashMap::new();
+    let mut sizes: Vec<String> = Vec::new();
+    let mut link_text: Option<(bool, String)> = None;
+    let mut list_depth = 0usize;
+
+    let tokens = tokenize_html(content);
+    // Link targets are the link's own text, so collect it before styling runs
+    let mut link_targets: Vec<String> = Vec::new();
+    {
+        let mut current: Option<String> = None;
+        for token in &tokens {
+            match token {
+                HtmlToken::StartTag(name, _) if name.starts_with("link:") => current = Some(String::new()),
+                HtmlToken::EndTag(name) if name.starts_with("link:") => {
+                    link_targets.push(current.take().unwrap_or_default().trim().to_string());
+                }
+                HtmlToken::Text(text) => {
+                    if let Some(ref mut c) = current {
+                        c.push_str(text);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    let mut link_index = 0;
+
+    for token in tokens {
+        match token {
+            HtmlToken::StartTag(name, _) => {
+                match name.as_str() {
+                    "bold" | "italic" | "strikethrough" | "highlight" | "monospace" => {
+                        *depth.entry(style_key(&name)).or_default() += 1;
+                    }
+                    "list-item" => list_depth += 1,
+                    n if n.starts_with("size:") => sizes.push(n[5..].to_string()),
+                    n if n.starts_with("link:") => {
+                        let target = link_targets.get(link_index).cloned().unwrap_or_default();
+                        link_index += 1;
+                        link_text = if n == "link:url" {
+                            Some((false, target))
+                        } else {
+                            Some((true, resolve_title(&target)))
+                        };
+                    }
+                    _ => {}
+                }
+            }
+            HtmlToken::EndTag(name) => match name.as_str() {
+                "bold" | "italic" | "strikethrough" | "highlight" | "monospace" => {
+                    let d = depth.entry(style_key(&name)).or_default();
+                    *d = d.saturating_sub(1);
+                }
+                "list-item" => list_depth = list_depth.saturating_sub(1),
+                n if n.starts_with("size:") => {
+                    sizes.pop();
+                }
+                n if n.starts_with("link:") => link_text = None,
+                _ => {}
+            },
+            HtmlToken::Text(text) => {
+                style.bold = depth.get("bold").is_some_and(|&d| d > 0);
+                style.italic = depth.get("italic").is_some_and(|&d| d > 0);
+                style.strike = depth.get("strike").is_some_and(|&d| d > 0);
+                style.highlight = depth.get("highlight").is_some_and(|&d| d > 0);
+                style.mono = depth.get("mono").is_some_and(|&d| d > 0);
+                style.size = sizes.last().cloned();
+                style.link = link_text.clone();
+                style.list = list_depth > 0;
+                for (i, part) in text.split('\n').enumerate() {
+                    if i > 0 {
+                        lines.push(Vec::new());
+                    }
+                    let Some(line) = lines.last_mut() else { continue };
+                    match line.last_mut() {
+                        _ if part.is_empty() => {}
+                        // The tokenizer splits text at entities; keep one run per style
+                        Some((last, last_style)) if *last_style == style => last.push_str(part),
+                        _ => line.push((part.to_string(), style.clone())),
+                    }
+                }
+            }
+        }
+    }
+
+    // Drop the title line and the blank line Tomboy puts after it
+    let mut lines = lines.into_iter().skip(1).peekable();
+    while lines.peek().is_some_and(|l| line_text(l).trim().is_empty()) {
+        lines.next();
+    }
+    let mut lines: Vec<Vec<(String, Style)>> = lines.collect();
+    while lines.last().is_some_and(|l| line_text(l).trim().is_empty()) {
+        lines.pop();
+    }
+
+    let mut html = String::new();
+    let mut in_list = false;
+    for line in &lines {
+        let is_list = line.iter().any(|(_, s)| s.list);
+        if is_list != in_list {
+            html.push_str(if is_list { "<ul>\n" } else { "</ul>\n" });
+            in_list = is_list;
+        }
+        let visible: Vec<&Style> = line
+            .iter()
+            .filter(|(t, _)| !t.trim().is_empty())
+            .map(|(_, s)| s)
+            .collect();
+        let all = |f: &dyn Fn(&Style) -> bool| !visible.is_empty() && visible.iter().all(|s| f(s));
+
+        let (open, close, block_style) = if is_list {
+            ("<li>", "</li>", None)
+        } else if all(&|s| s.size.as_deref() == Some("huge")) {
+            ("<h1>", "</h1>", Some("size"))
+        } else if all(&|s| s.size.as_deref() == Some("large")) {
+            ("<h2>", "</h2>", Some("size"))
+        } else if all(&|s| s.mono) {
+            ("<pre><code>", "</code></pre>", Some("mono"))
+        } else {
+            ("<p>", "</p>", None)
+        };
+        html.push_str(open);
+        for (text, s) in line {
+            html.push_str(&render_run(text, s, block_style));
+        }
+        html.push_str(close);
+        html.push('\n');
+    }
+    if in_list {
+        html.push_str("</ul>\n");
+    }
+    html
+}
+
+fn style_key(tag: &str) -> &'static str {
+    match tag {
+        "bold" => "bold",
+        "italic" => "italic",
+        "strikethrough" => "strike",
+        "highlight" => "highlight",
+        _ => "mono",
+    }
+}
+
+fn line_text(line: &[(String, Style)]) -> String {
+    line.iter().map(|(t, _)| t.as_str()).collect()
+}
+
+/// One styled run as inline HTML. `block_style` is the markup already
+/// expressed by the enclosing block, so it isn't repeated inline.
+fn render_run(text: &str, style: &Style, block_style: Option<&str>) -> String {
+    let text = if style.list { text.trim_start_matches('\u{2022}') } else { text };
+    let mut html = escape_html(text);
+    if style.highlight {
+        html = format!("<span style=\"background-color:{}\">{}</span>", HIGHLIGHT_COLOR, html);
+    }
+    if style.strike {
+        html = format!("<s>{}</s>", html);
+    }
+    if style.italic {
+        html = format!("<i>{}</i>", html);
+    }
+    // Sizes inside a line have no inline equivalent; larger text is kept bold
+    let big = matches!(style.size.as_deref(), Some("large") | Some("huge")) && block_style != Some("size");
+    if style.bold || big {
+        html = format!("<b>{}</b>", html);
+    }
+    match &style.link {
+        Some((true, title)) => format!("<a href=\"tangle://{}\" class=\"tangle\">{}</a>", escape_html_attr(title), html),
+        Some((false, url)) => format!("<a href=\"{}\">{}</a>", escape_html_attr(url), html),
+        None => html,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_escape_titles_in_href() {
+        let content = "<note-content version=\"0.1\">Title\n\
+                       See <link:internal>Say \"hi\" &amp; wave</link:internal> \
+                       at <link:url>https://e.com/?a=1&amp;b=2</link:url></note-content>";
+        let html = note_content_to_html(content, &|t| t.to_string());
+        assert!(html.contains("<a href=\"tangle://Say &quot;hi&quot; &amp; wave\" class=\"tangle\">"), "{}", html);
+        assert!(html.contains("<a href=\"https://e.com/?a=1&amp;b=2\">"), "{}", html);
+        assert_eq!(crate::database::extract_tangle_targets(&html), ["Say \"hi\" & wave"]);
+    }
+}